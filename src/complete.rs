@@ -0,0 +1,123 @@
+use crate::{error::MsmError, point_coords, point_from_coords};
+use ff::{Field, PrimeField};
+use group::Curve;
+use halo2::{halo2curves::CurveAffine, plonk::Expression};
+
+/// Witness of complete addition `r = p + q` as
+/// `[lambda, alpha, beta, gamma, delta, x_r, y_r]`.
+/// Identity is encoded as `(0, 0)`
+pub(crate) fn complete_add<C: CurveAffine>(
+    p: (C::Base, C::Base),
+    q: (C::Base, C::Base),
+) -> Result<[C::Base; 7], MsmError> {
+    let (x_p, y_p) = p;
+    let (x_q, y_q) = q;
+    let zero = C::Base::ZERO;
+    let inv0 = |e: C::Base| e.invert().unwrap_or(zero);
+    let lambda = if x_q != x_p {
+        (y_q - y_p) * (x_q - x_p).invert().unwrap()
+    } else if y_p != zero {
+        (x_p.square() * C::Base::from(3) + C::a()) * y_p.double().invert().unwrap()
+    } else {
+        zero
+    };
+    let alpha = inv0(x_q - x_p);
+    let beta = inv0(y_p);
+    let gamma = inv0(y_q);
+    let delta = if x_q == x_p { inv0(y_q + y_p) } else { zero };
+    let r = point_from_coords::<C>(x_p, y_p)?.to_curve() + point_from_coords::<C>(x_q, y_q)?;
+    let (x_r, y_r) = point_coords(r.to_affine());
+    Ok([lambda, alpha, beta, gamma, delta, x_r, y_r])
+}
+
+/// Complete addition constraints following the halo2 gadgets `add` chip
+/// where `aux` is `[lambda, alpha, beta, gamma, delta]` and `a` is the curve
+/// coefficient. The identity `(0, 0)` is detected by `y = 0` rather than
+/// `x = 0`, since curves such as P-256 have the points `(0, ±sqrt(b))` while
+/// a point with `y = 0` is of order two which prime order curves don't have
+pub(crate) fn complete_addition_constraints<F: PrimeField>(
+    p: (Expression<F>, Expression<F>),
+    q: (Expression<F>, Expression<F>),
+    r: (Expression<F>, Expression<F>),
+    aux: [Expression<F>; 5],
+    a: F,
+) -> Vec<(&'static str, Expression<F>)> {
+    let (x_p, y_p) = p;
+    let (x_q, y_q) = q;
+    let (x_r, y_r) = r;
+    let [lambda, alpha, beta, gamma, delta] = aux;
+    let one = Expression::Constant(F::ONE);
+
+    let x_q_minus_x_p = e!(x_q) - e!(x_p);
+    let y_q_plus_y_p = e!(y_q) + e!(y_p);
+    let if_alpha = e!(x_q_minus_x_p) * alpha;
+    let if_beta = e!(y_p) * beta;
+    let if_gamma = e!(y_q) * gamma;
+    let if_delta = e!(y_q_plus_y_p) * delta;
+
+    // (x_q - x_p) * ((x_q - x_p) * lambda - (y_q - y_p)) = 0
+    let expr_secant = e!(x_q_minus_x_p) * (e!(x_q_minus_x_p) * e!(lambda) - (e!(y_q) - e!(y_p)));
+    // (1 - (x_q - x_p) * alpha) * y_p * (2 * y_p * lambda - (3 * x_p^2 + a)) = 0
+    // where y_p turns it off for the identity p, which would otherwise need
+    // a = 0 once q is at x = 0 too
+    let expr_tangent = (e!(one) - e!(if_alpha))
+        * e!(y_p)
+        * (e!(y_p) * F::from(2) * e!(lambda)
+            - (e!(x_p).square() * F::from(3) + Expression::Constant(a)));
+
+    let nonexceptional_x_r = e!(lambda).square() - e!(x_p) - e!(x_q) - e!(x_r);
+    let nonexceptional_y_r = e!(lambda) * (e!(x_p) - e!(x_r)) - e!(y_p) - e!(y_r);
+    let y_p_y_q = e!(y_p) * e!(y_q);
+    let expr_x_r_distinct = e!(y_p_y_q) * e!(x_q_minus_x_p) * e!(nonexceptional_x_r);
+    let expr_y_r_distinct = e!(y_p_y_q) * e!(x_q_minus_x_p) * e!(nonexceptional_y_r);
+    let expr_x_r_double = e!(y_p_y_q) * e!(y_q_plus_y_p) * nonexceptional_x_r;
+    let expr_y_r_double = y_p_y_q * y_q_plus_y_p * nonexceptional_y_r;
+
+    // p is identity then r = q
+    let expr_x_r_p_zero = (e!(one) - e!(if_beta)) * (e!(x_r) - e!(x_q));
+    let expr_y_r_p_zero = (e!(one) - if_beta) * (e!(y_r) - e!(y_q));
+    // q is identity then r = p
+    let expr_x_r_q_zero = (e!(one) - e!(if_gamma)) * (e!(x_r) - e!(x_p));
+    let expr_y_r_q_zero = (e!(one) - if_gamma) * (e!(y_r) - e!(y_p));
+    // p = -q then r is identity
+    let expr_x_r_inverse = (e!(one) - e!(if_alpha) - e!(if_delta)) * x_r;
+    let expr_y_r_inverse = (one - if_alpha - if_delta) * y_r;
+
+    vec![
+        ("complete_add_secant", expr_secant),
+        ("complete_add_tangent", expr_tangent),
+        ("complete_add_x_r_distinct", expr_x_r_distinct),
+        ("complete_add_y_r_distinct", expr_y_r_distinct),
+        ("complete_add_x_r_double", expr_x_r_double),
+        ("complete_add_y_r_double", expr_y_r_double),
+        ("complete_add_x_r_p_zero", expr_x_r_p_zero),
+        ("complete_add_y_r_p_zero", expr_y_r_p_zero),
+        ("complete_add_x_r_q_zero", expr_x_r_q_zero),
+        ("complete_add_y_r_q_zero", expr_y_r_q_zero),
+        ("complete_add_x_r_inverse", expr_x_r_inverse),
+        ("complete_add_y_r_inverse", expr_y_r_inverse),
+    ]
+}
+
+/// Constraints of a point that is either on the curve or the identity
+/// encoded as `(0, 0)`. The curve equation is multiplied by both coordinates
+/// so it is only relaxed at `(0, 0)`, which is not a curve point for `b != 0`
+pub(crate) fn point_or_identity_constraints<F: PrimeField>(
+    x: Expression<F>,
+    y: Expression<F>,
+    x_2: Expression<F>,
+    x_3: Expression<F>,
+    a: F,
+    b: F,
+) -> Vec<(&'static str, Expression<F>)> {
+    let expr_x_square = e!(x) * e!(x) - e!(x_2);
+    let expr_x_cube = x_2 * e!(x) - e!(x_3);
+    let expr_curve =
+        x_3 + Expression::Constant(a) * e!(x) + Expression::Constant(b) - e!(y) * e!(y);
+    vec![
+        ("assign_x_square", expr_x_square),
+        ("assign_x_cube", expr_x_cube),
+        ("assign_x_or_identity", x * e!(expr_curve)),
+        ("assign_y_or_identity", y * expr_curve),
+    ]
+}
@@ -0,0 +1,156 @@
+use crate::error::transpose;
+use crate::util::{canonical_decomposition, decompose_big, fe_to_big};
+use crate::{AssignedValue, RegionCtx};
+use ff::PrimeField;
+use halo2::{
+    plonk::{
+        Advice, Any, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector,
+    },
+    poly::Rotation,
+};
+use num_bigint::BigUint;
+use std::marker::PhantomData;
+
+/// Canonical windowed decomposition shared by the msm gates. Values are
+/// decomposed most significant window first and the borrow chain of
+/// `max - value` shows that they are at most `max`. Windows of one bit and
+/// their diffs are constrained to be binary here, wider windows are range
+/// checked with the selector of the gate that owns the range table
+#[derive(Clone, Debug)]
+pub struct Decomposition<F: PrimeField> {
+    pub(crate) diff: Column<Advice>,
+    pub(crate) value: Column<Advice>,
+    pub(crate) borrow: Column<Advice>,
+    pub(crate) acc: Column<Advice>,
+    pub(crate) bound: Column<Fixed>,
+    /// Remaining columns of the owning gate which are left empty
+    pub(crate) padding: Vec<Column<Any>>,
+    pub(crate) s_decompose: Selector,
+    pub(crate) s_range: Option<Selector>,
+    pub(crate) window: usize,
+    pub(crate) _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Decomposition<F> {
+    /// Columns are `[diff, window, borrow, acc]` and the bound limb is placed
+    /// in the fixed column
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        columns: [Column<Advice>; 4],
+        bound: Column<Fixed>,
+        padding: Vec<Column<Any>>,
+        s_range: Option<Selector>,
+        window: usize,
+    ) -> Self {
+        let [diff, value, borrow, acc] = columns;
+        let s_decompose = meta.selector();
+        let shift = F::from(1 << window);
+        let binary = window == 1;
+        // | diff | window | borrow | acc | bound |
+        meta.create_gate("scalar decomposition", |meta| {
+            let s = meta.query_selector(s_decompose);
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let window = meta.query_advice(value, Rotation::cur());
+            let borrow = meta.query_advice(borrow, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let borrow_in = meta.query_advice(borrow, Rotation::next());
+            let bound = meta.query_fixed(bound, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            let shift = Expression::Constant(shift);
+            // acc = acc_prev * 2^w + window
+            let expr_recompose = acc - (acc_prev * e!(shift) + e!(window));
+            let expr_borrow = e!(borrow) * (e!(one) - e!(borrow));
+            // diff = bound - window - borrow_in + borrow * 2^w
+            let expr_canonical = e!(diff) - (bound - e!(window) - borrow_in + borrow * shift);
+            let mut constraints = vec![
+                ("scalar_recompose", expr_recompose),
+                ("scalar_borrow", expr_borrow),
+                ("scalar_canonical", expr_canonical),
+            ];
+            if binary {
+                constraints.push(("scalar_bit", e!(window) * (e!(one) - window)));
+                constraints.push(("scalar_diff", e!(diff) * (one - diff)));
+            }
+            Constraints::with_selector(s, constraints)
+        });
+        Self {
+            diff,
+            value,
+            borrow,
+            acc,
+            bound,
+            padding,
+            s_decompose,
+            s_range,
+            window,
+            _marker: PhantomData,
+        }
+    }
+    fn empty_padding(&self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
+        for column in self.padding.iter() {
+            ctx.empty(|| "decompose:", *column)?;
+        }
+        Ok(())
+    }
+    /// Decomposes the value into `number_of_windows` windows most significant
+    /// first such that it is at most `max`. `zero` is the assigned constant
+    /// the accumulator and the borrow chain start from
+    pub fn assign(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_windows: usize,
+        zero: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let mut bound: Vec<F> = decompose_big(max.clone(), number_of_windows, self.window);
+        bound.reverse();
+        let rows = transpose(value.value().map(|value| {
+            canonical_decomposition::<F>(fe_to_big(*value), max, number_of_windows, self.window)
+        }))?
+        .transpose_vec(number_of_windows);
+        // accumulator starts from zero
+        ctx.empty(|| "decompose:", self.diff.into())?;
+        ctx.empty(|| "decompose:", self.value.into())?;
+        ctx.empty(|| "decompose:", self.borrow.into())?;
+        ctx.copy(|| "decompose: acc", self.acc, zero)?;
+        ctx.empty(|| "decompose:", self.bound.into())?;
+        self.empty_padding(ctx)?;
+        ctx.next();
+        let mut windows = vec![];
+        let mut acc = None;
+        for (i, (row, bound)) in rows.iter().zip(bound.iter()).enumerate() {
+            ctx.enable(self.s_decompose)?;
+            if let Some(s_range) = self.s_range {
+                ctx.enable(s_range)?;
+            }
+            ctx.advice(|| "decompose: diff", self.diff, row.map(|row| row[0]))?;
+            let window = ctx.advice(|| "decompose: window", self.value, row.map(|row| row[1]))?;
+            if i == 0 {
+                // no borrow from the most significant window
+                ctx.copy(|| "decompose: borrow", self.borrow, zero)?;
+            } else {
+                ctx.advice(|| "decompose: borrow", self.borrow, row.map(|row| row[2]))?;
+            }
+            acc = Some(ctx.advice(|| "decompose: acc", self.acc, row.map(|row| row[3]))?);
+            ctx.fixed(|| "decompose: bound", self.bound, *bound)?;
+            self.empty_padding(ctx)?;
+            ctx.next();
+            windows.push(window);
+        }
+        // no borrow into the least significant window
+        ctx.empty(|| "decompose:", self.diff.into())?;
+        ctx.empty(|| "decompose:", self.value.into())?;
+        ctx.copy(|| "decompose: borrow", self.borrow, zero)?;
+        ctx.empty(|| "decompose:", self.acc.into())?;
+        ctx.empty(|| "decompose:", self.bound.into())?;
+        self.empty_padding(ctx)?;
+        ctx.next();
+        match acc {
+            Some(acc) => ctx.equal(acc.cell(), value.cell())?,
+            None => return Err(Error::Synthesis),
+        }
+        Ok(windows)
+    }
+}
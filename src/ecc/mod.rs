@@ -0,0 +1,439 @@
+use crate::{
+    error::MsmError,
+    msm_fix::config::FixMSMGate,
+    msm_var::bucket::{instructions::MSMGate, wide::config::VarMSMGateWide},
+    util::{number_of_windows, scalar_max},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::{Field, PrimeField};
+use halo2::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::{Advice, Column, Error},
+};
+use std::fmt::Debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Bit length of the magnitudes of short fixed base scalars
+pub const SHORT_SCALAR_BITS: usize = 64;
+
+/// Mirror of `halo2_gadgets::utilities::UtilitiesInstructions`
+pub trait UtilitiesInstructions<F: Field> {
+    type Var: Clone + Debug + From<AssignedCell<F, F>>;
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        column: Column<Advice>,
+        value: Value<F>,
+    ) -> Result<Self::Var, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(|| "load private", column, 0, || value)
+                    .map(Self::Var::from)
+            },
+        )
+    }
+}
+
+/// Mirror of `halo2_gadgets::ecc::FixedPoints`
+pub trait FixedPoints<C: CurveAffine>: Debug + Eq + Clone {
+    type FullScalar: Debug + Eq + Clone;
+    type ShortScalar: Debug + Eq + Clone;
+    type Base: Debug + Eq + Clone;
+}
+
+/// Mirror of `halo2_gadgets::ecc::EccInstructions` so that gadgets written
+/// against that interface can be backed by the gates of this crate. The
+/// halo2 fork of this crate differs from the one of `halo2_gadgets`, so the
+/// trait is restated here with the same associated types and signatures
+pub trait EccInstructions<C: CurveAffine>:
+    Chip<C::Base> + UtilitiesInstructions<C::Base> + Clone + Debug + Eq
+{
+    type ScalarVar: Clone + Debug;
+    type ScalarFixed: Clone + Debug;
+    type ScalarFixedShort: Clone + Debug;
+    type Point: Clone + Debug;
+    type NonIdentityPoint: Clone + Debug + Into<Self::Point>;
+    type X: Clone + Debug;
+    type FixedPoints: FixedPoints<C>;
+    fn constrain_equal(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        a: &Self::Point,
+        b: &Self::Point,
+    ) -> Result<(), Error>;
+    fn witness_point(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        value: Value<C>,
+    ) -> Result<Self::Point, Error>;
+    fn witness_point_non_id(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        value: Value<C>,
+    ) -> Result<Self::NonIdentityPoint, Error>;
+    fn witness_scalar_var(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        value: Value<C::Scalar>,
+    ) -> Result<Self::ScalarVar, Error>;
+    fn witness_scalar_fixed(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        value: Value<C::Scalar>,
+    ) -> Result<Self::ScalarFixed, Error>;
+    /// Magnitude and sign `±1` of a short scalar
+    fn scalar_fixed_from_signed_short(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        magnitude_sign: (Self::Var, Self::Var),
+    ) -> Result<Self::ScalarFixedShort, Error>;
+    fn extract_p<Point: Into<Self::Point> + Clone>(point: &Point) -> Self::X;
+    fn add_incomplete(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        a: &Self::NonIdentityPoint,
+        b: &Self::NonIdentityPoint,
+    ) -> Result<Self::NonIdentityPoint, Error>;
+    fn add<A: Into<Self::Point> + Clone, B: Into<Self::Point> + Clone>(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        a: &A,
+        b: &B,
+    ) -> Result<Self::Point, Error>;
+    fn mul(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        scalar: &Self::ScalarVar,
+        base: &Self::NonIdentityPoint,
+    ) -> Result<(Self::Point, Self::ScalarVar), Error>;
+    fn mul_fixed(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        scalar: &Self::ScalarFixed,
+        base: &<Self::FixedPoints as FixedPoints<C>>::FullScalar,
+    ) -> Result<(Self::Point, Self::ScalarFixed), Error>;
+    fn mul_fixed_short(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        scalar: &Self::ScalarFixedShort,
+        base: &<Self::FixedPoints as FixedPoints<C>>::ShortScalar,
+    ) -> Result<(Self::Point, Self::ScalarFixedShort), Error>;
+    fn mul_fixed_base_field_elem(
+        &self,
+        layouter: &mut impl Layouter<C::Base>,
+        base_field_elem: Self::Var,
+        base: &<Self::FixedPoints as FixedPoints<C>>::Base,
+    ) -> Result<Self::Point, Error>;
+}
+
+/// Index of a base the fixed base gate is configured with. Any of the bases
+/// can be used with full, short and base field element scalars
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedBase(pub usize);
+
+impl<C: CurveAffine> FixedPoints<C> for FixedBase {
+    type FullScalar = FixedBase;
+    type ShortScalar = FixedBase;
+    type Base = FixedBase;
+}
+
+/// Point that is constrained not to be the identity
+#[derive(Clone, Debug)]
+pub struct NonIdentityEccPoint<C: CurveAffine>(AssignedPoint<C>);
+
+impl<C: CurveAffine> NonIdentityEccPoint<C> {
+    pub fn point(&self) -> &AssignedPoint<C> {
+        &self.0
+    }
+}
+
+impl<C: CurveAffine> From<NonIdentityEccPoint<C>> for AssignedPoint<C> {
+    fn from(point: NonIdentityEccPoint<C>) -> Self {
+        point.0
+    }
+}
+
+/// Short fixed base scalar as its magnitude and the boolean `(1 - sign) / 2`
+#[derive(Clone, Debug)]
+pub struct EccScalarFixedShort<F: PrimeField> {
+    magnitude: AssignedValue<F>,
+    sign: AssignedValue<F>,
+}
+
+/// Gates of `EccChip`
+#[derive(Clone, Debug)]
+pub struct EccConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    pub var: VarMSMGateWide<F, App>,
+    pub fixed: FixMSMGate<F, App>,
+}
+
+/// Adapter backed by the wide bucket gate for variable base operations and
+/// the fixed base gate for fixed base multiplications. Points may be the
+/// identity which is assigned as `(0, 0)`
+#[derive(Clone, Debug)]
+pub struct EccChip<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    config: EccConfig<F, App>,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> EccChip<F, App> {
+    /// Variable base gate must be configured with `configure_single_term`,
+    /// `configure_complete_addition` and `configure_negation`, and the fixed
+    /// base gate with `configure_complete_addition` so that zero scalars
+    /// give the identity
+    pub fn new(var: VarMSMGateWide<F, App>, fixed: FixMSMGate<F, App>) -> Result<Self, MsmError> {
+        if var.s_double_add.is_none() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "single term multiplication",
+            });
+        }
+        if var.s_add_complete.is_none() || fixed.s_add_complete.is_none() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "complete addition",
+            });
+        }
+        if var.s_negate.is_none() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "conditional negation",
+            });
+        }
+        Ok(Self {
+            config: EccConfig { var, fixed },
+        })
+    }
+    /// Lays out the range tables and the fixed base table
+    pub fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        let EccConfig { var, fixed } = &self.config;
+        var.layout_range_table(ly)?;
+        if fixed.range_table != var.range_table {
+            fixed.layout_range_table(ly)?;
+        }
+        fixed.layout_point_table(ly)
+    }
+}
+
+/// Chips are equal when they are laid out on the same columns
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> PartialEq for EccChip<F, App> {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (&self.config, &other.config);
+        a.var.advice_columns() == b.var.advice_columns()
+            && a.var.fixed_colmns() == b.var.fixed_colmns()
+            && a.fixed.advice_columns() == b.fixed.advice_columns()
+            && a.fixed.fixed_colmns() == b.fixed.fixed_colmns()
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Eq for EccChip<F, App> {}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Chip<F> for EccChip<F, App> {
+    type Config = EccConfig<F, App>;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> UtilitiesInstructions<F> for EccChip<F, App> {
+    type Var = AssignedValue<F>;
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> EccInstructions<App> for EccChip<F, App> {
+    type ScalarVar = AssignedValue<F>;
+    type ScalarFixed = AssignedValue<F>;
+    type ScalarFixedShort = EccScalarFixedShort<F>;
+    type Point = AssignedPoint<App>;
+    type NonIdentityPoint = NonIdentityEccPoint<App>;
+    type X = AssignedValue<F>;
+    type FixedPoints = FixedBase;
+    fn constrain_equal(
+        &self,
+        ly: &mut impl Layouter<F>,
+        a: &Self::Point,
+        b: &Self::Point,
+    ) -> Result<(), Error> {
+        ly.assign_region(
+            || "constrain equal",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.var.equal(ctx, a, b)
+            },
+        )
+    }
+    fn witness_point(
+        &self,
+        ly: &mut impl Layouter<F>,
+        value: Value<App>,
+    ) -> Result<Self::Point, Error> {
+        ly.assign_region(
+            || "witness point",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.var.assign_point(ctx, &value)
+            },
+        )
+    }
+    fn witness_point_non_id(
+        &self,
+        ly: &mut impl Layouter<F>,
+        value: Value<App>,
+    ) -> Result<Self::NonIdentityPoint, Error> {
+        ly.assign_region(
+            || "witness non identity point",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let point = self.config.var.assign_non_identity_point(ctx, &value)?;
+                Ok(NonIdentityEccPoint(point))
+            },
+        )
+    }
+    fn witness_scalar_var(
+        &self,
+        ly: &mut impl Layouter<F>,
+        value: Value<App::Scalar>,
+    ) -> Result<Self::ScalarVar, Error> {
+        ly.assign_region(
+            || "witness scalar",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.var.assign_scalar(ctx, &value)
+            },
+        )
+    }
+    fn witness_scalar_fixed(
+        &self,
+        ly: &mut impl Layouter<F>,
+        value: Value<App::Scalar>,
+    ) -> Result<Self::ScalarFixed, Error> {
+        ly.assign_region(
+            || "witness scalar fixed",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.fixed.assign_scalar(ctx, &value)
+            },
+        )
+    }
+    fn scalar_fixed_from_signed_short(
+        &self,
+        ly: &mut impl Layouter<F>,
+        (magnitude, sign): (Self::Var, Self::Var),
+    ) -> Result<Self::ScalarFixedShort, Error> {
+        ly.assign_region(
+            || "scalar fixed from signed short",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let sign = self.config.var.assign_sign(ctx, &sign)?;
+                Ok(EccScalarFixedShort {
+                    magnitude: magnitude.clone(),
+                    sign,
+                })
+            },
+        )
+    }
+    fn extract_p<Point: Into<Self::Point> + Clone>(point: &Point) -> Self::X {
+        let point: Self::Point = point.clone().into();
+        point.x().clone()
+    }
+    fn add_incomplete(
+        &self,
+        ly: &mut impl Layouter<F>,
+        a: &Self::NonIdentityPoint,
+        b: &Self::NonIdentityPoint,
+    ) -> Result<Self::NonIdentityPoint, Error> {
+        ly.assign_region(
+            || "add incomplete",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let point = self.config.var.add_incomplete(ctx, &a.0, &b.0)?;
+                Ok(NonIdentityEccPoint(point))
+            },
+        )
+    }
+    fn add<A: Into<Self::Point> + Clone, B: Into<Self::Point> + Clone>(
+        &self,
+        ly: &mut impl Layouter<F>,
+        a: &A,
+        b: &B,
+    ) -> Result<Self::Point, Error> {
+        let (a, b): (Self::Point, Self::Point) = (a.clone().into(), b.clone().into());
+        ly.assign_region(
+            || "add",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.var.add_complete(ctx, &a, &b)
+            },
+        )
+    }
+    fn mul(
+        &self,
+        ly: &mut impl Layouter<F>,
+        scalar: &Self::ScalarVar,
+        base: &Self::NonIdentityPoint,
+    ) -> Result<(Self::Point, Self::ScalarVar), Error> {
+        let point = ly.assign_region(
+            || "mul",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.var.mul(ctx, &base.0, scalar)
+            },
+        )?;
+        Ok((point, scalar.clone()))
+    }
+    fn mul_fixed(
+        &self,
+        ly: &mut impl Layouter<F>,
+        scalar: &Self::ScalarFixed,
+        base: &FixedBase,
+    ) -> Result<(Self::Point, Self::ScalarFixed), Error> {
+        let point = ly.assign_region(
+            || "mul fixed",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                self.config.fixed.mul(ctx, base.0, scalar)
+            },
+        )?;
+        Ok((point, scalar.clone()))
+    }
+    fn mul_fixed_short(
+        &self,
+        ly: &mut impl Layouter<F>,
+        scalar: &Self::ScalarFixedShort,
+        base: &FixedBase,
+    ) -> Result<(Self::Point, Self::ScalarFixedShort), Error> {
+        let EccConfig { var, fixed } = &self.config;
+        let point = ly.assign_region(
+            || "mul fixed short",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let point = fixed.mul_short(ctx, base.0, &scalar.magnitude, SHORT_SCALAR_BITS)?;
+                var.conditional_negate(ctx, &point, &scalar.sign)
+            },
+        )?;
+        Ok((point, scalar.clone()))
+    }
+    fn mul_fixed_base_field_elem(
+        &self,
+        ly: &mut impl Layouter<F>,
+        base_field_elem: Self::Var,
+        base: &FixedBase,
+    ) -> Result<Self::Point, Error> {
+        let fixed = &self.config.fixed;
+        ly.assign_region(
+            || "mul fixed base field element",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                // the element is taken as an integer below the native modulus
+                let max = scalar_max::<F, F>();
+                let number_of_windows = number_of_windows::<F, F>(fixed.window);
+                let windows = fixed.decompose(ctx, &base_field_elem, &max, number_of_windows)?;
+                fixed.mul_windows(ctx, base.0, &windows)
+            },
+        )
+    }
+}
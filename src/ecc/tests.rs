@@ -0,0 +1,215 @@
+use super::{EccChip, EccInstructions, FixedBase, UtilitiesInstructions};
+use crate::error::MsmError;
+use crate::msm_fix::tests::{configure_fixed_gate, fixed_bases};
+use crate::msm_var::bucket::wide::config::VarMSMGateWide;
+use crate::range::RangeTable;
+use crate::util::{big_to_fe, fe_to_big};
+use ff::Field;
+use ff::PrimeField;
+use group::Curve;
+use group::Group;
+use halo2::dev::MockProver;
+use halo2::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::CurveAffine,
+    plonk::Error,
+    plonk::{Advice, Circuit, Column, ConstraintSystem},
+};
+use rand_core::{OsRng, RngCore};
+use std::marker::PhantomData;
+
+const NUMBER_OF_FIXED: usize = 2;
+
+#[derive(Default, Clone, Debug)]
+struct Params {
+    window: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    chip: EccChip<F, App>,
+    advice: Column<Advice>,
+}
+#[derive(Debug, Default)]
+struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    _marker: PhantomData<(F, App)>,
+    window: usize,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+    type Config = TestConfig<F, App>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = Params;
+    fn without_witnesses(&self) -> Self {
+        Self {
+            _marker: PhantomData,
+            window: self.window,
+        }
+    }
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        let a = [(); 9].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
+        let window = params.window;
+        let mut var =
+            VarMSMGateWide::configure_default(meta, &a, range_table, constant, instance, window);
+        var.configure_complete_addition(meta);
+        var.configure_single_term(meta);
+        var.configure_negation(meta);
+        let mut fixed = configure_fixed_gate(
+            meta,
+            [a[0], a[1], a[2], a[3], a[4]],
+            range_table,
+            constant,
+            instance,
+            window,
+            &fixed_bases::<App>(NUMBER_OF_FIXED),
+            App::CurveExt::generator().to_affine(),
+        );
+        fixed.configure_complete_addition(meta);
+        Self::Config {
+            chip: EccChip::new(var, fixed).unwrap(),
+            advice: a[0],
+        }
+    }
+    fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!();
+    }
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
+        let ly = &mut ly;
+        let chip = &cfg.chip;
+        let bases = fixed_bases::<App>(NUMBER_OF_FIXED);
+        let p = App::CurveExt::random(OsRng).to_affine();
+        let q = App::CurveExt::random(OsRng).to_affine();
+        let identity = App::identity();
+
+        // k0 * P + k1 * G_1
+        let k0 = App::Scalar::random(OsRng);
+        let k1 = App::Scalar::random(OsRng);
+        let expected = (p * k0 + bases[1] * k1).to_affine();
+        let point = chip.witness_point_non_id(ly, Value::known(p))?;
+        let k0 = chip.witness_scalar_var(ly, Value::known(k0))?;
+        let k1 = chip.witness_scalar_fixed(ly, Value::known(k1))?;
+        let (r0, _) = chip.mul(ly, &k0, &point)?;
+        let (r1, _) = chip.mul_fixed(ly, &k1, &FixedBase(1))?;
+        let res = chip.add(ly, &r0, &r1)?;
+        let expected = chip.witness_point(ly, Value::known(expected))?;
+        chip.constrain_equal(ly, &res, &expected)?;
+        let x = EccChip::<F, App>::extract_p(&res);
+        ly.assign_region(
+            || "extract p",
+            |mut region| region.constrain_equal(x.cell(), expected.x().cell()),
+        )?;
+
+        // P + Q with incomplete addition
+        let expected = chip.witness_point(ly, Value::known((p + q).to_affine()))?;
+        let other = chip.witness_point_non_id(ly, Value::known(q))?;
+        let res = chip.add_incomplete(ly, &point, &other)?;
+        chip.constrain_equal(ly, &res.into(), &expected)?;
+
+        // P - P and P + 0 with complete addition
+        let identity = chip.witness_point(ly, Value::known(identity))?;
+        let negated = chip.witness_point(ly, Value::known(-p))?;
+        let res = chip.add(ly, &point, &negated)?;
+        chip.constrain_equal(ly, &res, &identity)?;
+        let res = chip.add(ly, &point, &identity)?;
+        chip.constrain_equal(ly, &res, &point.clone().into())?;
+
+        // 0 * G_0
+        let zero = chip.witness_scalar_fixed(ly, Value::known(App::Scalar::ZERO))?;
+        let (res, _) = chip.mul_fixed(ly, &zero, &FixedBase(0))?;
+        chip.constrain_equal(ly, &res, &identity)?;
+
+        // -m * G_0 with a short magnitude
+        let magnitude = OsRng.next_u64();
+        let expected = (-(bases[0] * App::Scalar::from(magnitude))).to_affine();
+        let magnitude = chip.load_private(
+            ly.namespace(|| "magnitude"),
+            cfg.advice,
+            Value::known(F::from(magnitude)),
+        )?;
+        let sign = chip.load_private(ly.namespace(|| "sign"), cfg.advice, Value::known(-F::ONE))?;
+        let scalar = chip.scalar_fixed_from_signed_short(ly, (magnitude, sign))?;
+        let (res, _) = chip.mul_fixed_short(ly, &scalar, &FixedBase(0))?;
+        let expected = chip.witness_point(ly, Value::known(expected))?;
+        chip.constrain_equal(ly, &res, &expected)?;
+
+        // e * G_1 with a base field element
+        let e = F::random(OsRng);
+        let expected = (bases[1] * big_to_fe::<App::Scalar>(fe_to_big(e))).to_affine();
+        let e = chip.load_private(ly.namespace(|| "element"), cfg.advice, Value::known(e))?;
+        let res = chip.mul_fixed_base_field_elem(ly, e, &FixedBase(1))?;
+        let expected = chip.witness_point(ly, Value::known(expected))?;
+        chip.constrain_equal(ly, &res, &expected)?;
+        chip.layout(ly)
+    }
+    fn params(&self) -> Self::Params {
+        Params {
+            window: self.window,
+        }
+    }
+}
+
+#[test]
+fn test_ecc_chip() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window: 4,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_ecc_chip_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let circuit = MyCircuit::<Fp, Secp256r1Affine> {
+        _marker: PhantomData::<(Fp, Secp256r1Affine)>,
+        window: 4,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_ecc_chip_unsupported() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    let meta = &mut ConstraintSystem::<Fq>::default();
+    let a = [(); 9].map(|_| meta.advice_column());
+    let constant = meta.fixed_column();
+    let instance = meta.instance_column();
+    let range_table = RangeTable::configure(meta);
+    let window = 4;
+    let mut var =
+        VarMSMGateWide::configure_default(meta, &a, range_table, constant, instance, window);
+    var.configure_complete_addition(meta);
+    let mut fixed = configure_fixed_gate(
+        meta,
+        [a[0], a[1], a[2], a[3], a[4]],
+        range_table,
+        constant,
+        instance,
+        window,
+        &fixed_bases::<EqAffine>(NUMBER_OF_FIXED),
+        EqAffine::generator(),
+    );
+    fixed.configure_complete_addition(meta);
+    assert_eq!(
+        EccChip::new(var, fixed).unwrap_err(),
+        MsmError::UnsupportedMode {
+            operation: "single term multiplication"
+        }
+    );
+}
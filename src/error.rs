@@ -0,0 +1,175 @@
+use ff::Field;
+use halo2::{circuit::Value, plonk};
+use std::fmt;
+
+/// Errors of witness generation. Gates return them as `plonk::Error` where
+/// they become `Error::Synthesis`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MsmError {
+    /// Incomplete formulas hit colliding x coordinates
+    ExceptionalAddition { operation: &'static str },
+    /// Coordinates are not on the curve or the point is the identity
+    InvalidPoint { context: &'static str },
+    /// MSM without terms
+    EmptyMsm,
+    /// Numbers of points and scalars differ
+    LengthMismatch { points: usize, scalars: usize },
+    /// Number of entries of a table doesn't match the terms
+    TableMismatch { expected: usize, actual: usize },
+    /// Memory is read at an address that is not written yet
+    UnwrittenAddress,
+    /// Memory queries don't match what the gate expects
+    InconsistentMemory { context: &'static str },
+    /// Scalar doesn't fit in the range the decomposition is bound to
+    ScalarOutOfRange,
+    /// Randomness of the divisor argument hits a degenerate case
+    UnluckyRandomness { context: &'static str },
+    /// Operation is not available with the options the gate is configured with
+    UnsupportedMode { operation: &'static str },
+    /// Declared bit length of short scalars is zero or exceeds the native field
+    InvalidBitLength { number_of_bits: usize },
+    /// Limbs don't split into whole windows or scalars have different numbers
+    /// of limbs
+    InvalidLimbs,
+    /// Memory handed to a gate is of another gate
+    MemoryMismatch,
+    /// Scalar of `ScalarChip` is given to an msm before it is reduced
+    UnreducedScalar,
+    /// Gates sharing the doubling chain are configured with different windows
+    WindowMismatch { expected: usize, actual: usize },
+    /// No window fits the msm in the rows of the circuit
+    NoWindow { number_of_terms: usize, k: u32 },
+}
+
+impl fmt::Display for MsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MsmError::ExceptionalAddition { operation } => {
+                write!(f, "exceptional case of incomplete addition in {operation}")
+            }
+            MsmError::InvalidPoint { context } => {
+                write!(
+                    f,
+                    "point is not on the curve or is the identity in {context}"
+                )
+            }
+            MsmError::EmptyMsm => write!(f, "msm has no terms"),
+            MsmError::LengthMismatch { points, scalars } => {
+                write!(f, "{points} points are given with {scalars} scalars")
+            }
+            MsmError::TableMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "table has {actual} entries where {expected} are expected"
+                )
+            }
+            MsmError::UnwrittenAddress => write!(f, "memory is read before it is written"),
+            MsmError::InconsistentMemory { context } => {
+                write!(f, "inconsistent memory queries in {context}")
+            }
+            MsmError::ScalarOutOfRange => write!(f, "scalar is out of the decomposition range"),
+            MsmError::UnluckyRandomness { context } => {
+                write!(f, "degenerate randomness in {context}")
+            }
+            MsmError::UnsupportedMode { operation } => {
+                write!(f, "{operation} is not supported in the configured mode")
+            }
+            MsmError::InvalidBitLength { number_of_bits } => {
+                write!(
+                    f,
+                    "short scalars of {number_of_bits} bits are not supported"
+                )
+            }
+            MsmError::InvalidLimbs => write!(f, "limbs don't match the window decomposition"),
+            MsmError::MemoryMismatch => write!(f, "memory is of another gate"),
+            MsmError::UnreducedScalar => write!(f, "scalar is not reduced"),
+            MsmError::WindowMismatch { expected, actual } => {
+                write!(f, "window is {actual} where {expected} is expected")
+            }
+            MsmError::NoWindow { number_of_terms, k } => {
+                write!(
+                    f,
+                    "msm of {number_of_terms} terms doesn't fit in 2^{k} rows"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MsmError {}
+
+/// `plonk::Error` has no variant carrying a message, so witness errors are
+/// reported as `Error::Synthesis`. Witness functions returning `MsmError`
+/// can be called directly to tell them apart
+impl From<MsmError> for plonk::Error {
+    fn from(_: MsmError) -> Self {
+        plonk::Error::Synthesis
+    }
+}
+
+/// Checks that the MSM has terms and as many scalars as points
+pub(crate) fn check_terms(
+    number_of_points: usize,
+    number_of_scalars: usize,
+) -> Result<(), MsmError> {
+    if number_of_points == 0 {
+        return Err(MsmError::EmptyMsm);
+    }
+    if number_of_points != number_of_scalars {
+        return Err(MsmError::LengthMismatch {
+            points: number_of_points,
+            scalars: number_of_scalars,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the fixed base gate shares the window of the variable base
+/// gate and that the latter is not in the GLV mode
+pub(crate) fn check_mixed(window: usize, fixed_window: usize, glv: bool) -> Result<(), MsmError> {
+    if fixed_window != window {
+        return Err(MsmError::WindowMismatch {
+            expected: window,
+            actual: fixed_window,
+        });
+    }
+    if glv {
+        return Err(MsmError::UnsupportedMode {
+            operation: "mixed msm in the glv mode",
+        });
+    }
+    Ok(())
+}
+
+/// Checks that limbs split into whole windows and every scalar has the same
+/// number of limbs. Returns the number of limbs
+pub(crate) fn check_limbs<T>(
+    limbs: &[Vec<T>],
+    limb_bits: usize,
+    window: usize,
+) -> Result<usize, MsmError> {
+    let number_of_limbs = limbs.first().map_or(0, |limbs| limbs.len());
+    if limb_bits % window != 0
+        || number_of_limbs == 0
+        || limbs.iter().any(|limbs| limbs.len() != number_of_limbs)
+    {
+        return Err(MsmError::InvalidLimbs);
+    }
+    Ok(number_of_limbs)
+}
+
+/// Moves the error of a known value out
+pub(crate) fn transpose<T>(value: Value<Result<T, MsmError>>) -> Result<Value<T>, MsmError> {
+    let mut error = None;
+    let value = value.map(|value| value.map_err(|e| error = Some(e)).ok());
+    match error {
+        Some(error) => Err(error),
+        // known values are all set here
+        None => Ok(value.map(|value| value.unwrap())),
+    }
+}
+
+/// Inverse of the value or the given error if it is zero
+pub(crate) fn invert<F: Field>(value: Value<F>, error: MsmError) -> Result<Value<F>, MsmError> {
+    transpose(value.map(|value| Option::from(value.invert()).ok_or(error)))
+}
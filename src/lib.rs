@@ -1,10 +1,11 @@
-use std::marker::PhantomData;
+use std::{collections::BTreeMap, marker::PhantomData};
 
+use crate::error::{transpose, MsmError};
 use ff::Field;
 use group::Curve;
 use halo2::{
     circuit::{AssignedCell, Cell, Region, Value},
-    halo2curves::CurveAffine,
+    halo2curves::{CurveAffine, CurveExt},
     plonk::{Advice, Any, Column, Error, Fixed, Selector},
 };
 
@@ -19,12 +20,22 @@ macro_rules! div_ceil {
         (($a - 1) / $b) + 1
     };
 }
-pub mod msm_var;
+pub(crate) mod complete;
+pub mod decomposition;
+pub mod ecc;
+pub mod error;
+pub mod memory;
+pub mod msm;
 pub mod msm_fix;
+pub mod msm_var;
+pub mod range;
+pub mod scalar;
 pub(crate) mod util;
 
 pub type AssignedValue<F> = AssignedCell<F, F>;
 
+/// Assigned affine point. Identity is encoded as `(0, 0)` which is only
+/// meaningful for gates configured with complete addition
 #[derive(Debug, Clone)]
 pub struct AssignedPoint<C: CurveAffine> {
     x: AssignedValue<C::Base>,
@@ -32,11 +43,47 @@ pub struct AssignedPoint<C: CurveAffine> {
     _marker: PhantomData<C>,
 }
 
+/// Coordinates of the point where identity is encoded as `(0, 0)`
+pub(crate) fn point_coords<C: CurveAffine>(point: C) -> (C::Base, C::Base) {
+    let coordinates = point.coordinates();
+    if bool::from(coordinates.is_some()) {
+        let coordinates = coordinates.unwrap();
+        (*coordinates.x(), *coordinates.y())
+    } else {
+        (C::Base::ZERO, C::Base::ZERO)
+    }
+}
+
+/// Inverse of `point_coords`
+pub(crate) fn point_from_coords<C: CurveAffine>(x: C::Base, y: C::Base) -> Result<C, MsmError> {
+    if x == C::Base::ZERO && y == C::Base::ZERO {
+        Ok(C::identity())
+    } else {
+        Option::from(C::from_xy(x, y)).ok_or(MsmError::InvalidPoint {
+            context: "coordinates",
+        })
+    }
+}
+
+/// Domain of the hash to curve used in aux generator derivation
+pub const AUX_GENERATOR_DOMAIN: &str = "halo2msm";
+/// Tag of the aux generator used by `configure_default` constructors. Every
+/// gate configured with it derives the same point so keys are reproducible
+/// and an MSM of one gate can be checked against another
+pub const DEFAULT_AUX_GENERATOR_TAG: &[u8] = b"aux_generator";
+
+/// Derives the aux generator by hashing the tag to the curve. Unlike a
+/// randomly sampled point it is reproducible across key generations and
+/// has no known discrete log relation to the bases
+pub fn derive_aux_generator<C: CurveAffine>(tag: &[u8]) -> C {
+    let hasher = C::CurveExt::hash_to_curve(AUX_GENERATOR_DOMAIN);
+    let aux_generator = hasher(tag).to_affine();
+    assert!(bool::from(!aux_generator.is_identity()));
+    aux_generator
+}
+
 pub(crate) fn coords<C: CurveAffine>(point: Value<C>) -> Value<(C::Base, C::Base)> {
-    point.map(|c| {
-        let coordinates = c.coordinates().unwrap();
-        (coordinates.x().clone(), coordinates.y().clone())
-    })
+    point.map(point_coords)
 }
 
 impl<C: CurveAffine> AssignedPoint<C> {
@@ -53,61 +100,103 @@ impl<C: CurveAffine> AssignedPoint<C> {
     pub fn y(&self) -> &AssignedValue<C::Base> {
         &self.y
     }
-    pub fn value(&self) -> Value<C> {
+    pub fn value(&self) -> Result<Value<C>, MsmError> {
         let x = self.x.value().map(|v| *v);
         let y = self.y.value().map(|v| *v);
-        x.zip(y).map(|(x, y)| C::from_xy(x, y).unwrap())
+        transpose(x.zip(y).map(|(x, y)| point_from_coords(x, y)))
     }
     pub fn coords(&self) -> Value<(C::Base, C::Base)> {
         let x = self.x.value().map(|v| *v);
         let y = self.y.value().map(|v| *v);
         x.zip(y)
     }
-    pub fn dbl(&self) -> (Value<C::Base>, Value<C::Base>) {
-        let this = self.value();
+    pub fn dbl(&self) -> Result<(Value<C::Base>, Value<C::Base>), MsmError> {
+        let this = self.value()?;
         let res = this.map(|this| (this + this).to_affine());
-        coords(res).unzip()
+        Ok(coords(res).unzip())
     }
-    fn _add(&self, other: &Self) -> Value<C> {
-        let this = self.value();
-        let other = other.value();
+    fn _add(&self, other: &Self) -> Result<Value<C>, MsmError> {
+        let this = self.value()?;
+        let other = other.value()?;
         let u = this + other;
-        u.map(|c| c.to_affine())
+        Ok(u.map(|c| c.to_affine()))
     }
-    fn _double(&self) -> Value<C> {
-        let this = self.value();
-        this.map(|this| (this + this).to_affine())
+    fn _double(&self) -> Result<Value<C>, MsmError> {
+        let this = self.value()?;
+        Ok(this.map(|this| (this + this).to_affine()))
     }
 }
 
 impl<C: CurveAffine> std::ops::Add<&AssignedPoint<C>> for AssignedPoint<C> {
-    type Output = (Value<C::Base>, Value<C::Base>);
+    type Output = Result<(Value<C::Base>, Value<C::Base>), MsmError>;
     fn add(self, other: &AssignedPoint<C>) -> Self::Output {
-        let res = self + &other.value();
-        coords(res).unzip()
+        let res = (self + &other.value()?)?;
+        Ok(coords(res).unzip())
     }
 }
 impl<C: CurveAffine> std::ops::Add<&AssignedPoint<C>> for &AssignedPoint<C> {
-    type Output = (Value<C::Base>, Value<C::Base>);
+    type Output = Result<(Value<C::Base>, Value<C::Base>), MsmError>;
     fn add(self, other: &AssignedPoint<C>) -> Self::Output {
-        let res = self + &other.value();
-        coords(res).unzip()
+        let res = (self + &other.value()?)?;
+        Ok(coords(res).unzip())
     }
 }
 impl<C: CurveAffine> std::ops::Add<&Value<C>> for &AssignedPoint<C> {
-    type Output = Value<C>;
+    type Output = Result<Value<C>, MsmError>;
     fn add(self, other: &Value<C>) -> Self::Output {
-        self.value()
+        Ok(self
+            .value()?
             .zip(*other)
-            .map(|(this, other)| (this + other).to_affine())
+            .map(|(this, other)| (this + other).to_affine()))
     }
 }
 impl<C: CurveAffine> std::ops::Add<&Value<C>> for AssignedPoint<C> {
-    type Output = Value<C>;
+    type Output = Result<Value<C>, MsmError>;
     fn add(self, other: &Value<C>) -> Self::Output {
-        self.value()
+        Ok(self
+            .value()?
             .zip(*other)
-            .map(|(this, other)| (this + other).to_affine())
+            .map(|(this, other)| (this + other).to_affine()))
+    }
+}
+
+/// Cache of constants assigned in a region. It is owned by `RegionCtx` so it
+/// is dropped together with the region whenever the floor planner reruns it
+#[derive(Debug)]
+pub struct ConstantPool<F: Field> {
+    constants: BTreeMap<F, AssignedValue<F>>,
+    saved_rows: usize,
+}
+impl<F: Field> Default for ConstantPool<F> {
+    fn default() -> Self {
+        ConstantPool {
+            constants: BTreeMap::new(),
+            saved_rows: 0,
+        }
+    }
+}
+impl<F: Field + Ord> ConstantPool<F> {
+    pub fn get(&mut self, constant: &F) -> Option<AssignedValue<F>> {
+        let assigned = self.constants.get(constant).cloned();
+        if assigned.is_some() {
+            self.saved_rows += 1;
+        }
+        assigned
+    }
+    pub fn insert(&mut self, constant: F, assigned: AssignedValue<F>) {
+        self.constants.insert(constant, assigned);
+    }
+}
+impl<F: Field> ConstantPool<F> {
+    pub fn len(&self) -> usize {
+        self.constants.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.constants.is_empty()
+    }
+    /// Number of constant rows that were not spent thanks to the cache
+    pub fn saved_rows(&self) -> usize {
+        self.saved_rows
     }
 }
 
@@ -115,14 +204,28 @@ impl<C: CurveAffine> std::ops::Add<&Value<C>> for AssignedPoint<C> {
 pub struct RegionCtx<'a, F: Field> {
     region: Region<'a, F>,
     offset: usize,
+    constants: ConstantPool<F>,
+    /// Selectors along with the number of rows they are enabled at
+    enabled: Vec<(Selector, usize)>,
 }
 impl<'a, F: Field> RegionCtx<'a, F> {
     pub fn new(region: Region<'a, F>) -> RegionCtx<'a, F> {
-        RegionCtx { region, offset: 0 }
+        RegionCtx {
+            region,
+            offset: 0,
+            constants: ConstantPool::default(),
+            enabled: vec![],
+        }
     }
     pub fn offset(&self) -> usize {
         self.offset
     }
+    pub fn constants(&self) -> &ConstantPool<F> {
+        &self.constants
+    }
+    pub fn constants_mut(&mut self) -> &mut ConstantPool<F> {
+        &mut self.constants
+    }
     pub fn fixed<A, AR>(
         &mut self,
         annotation: A,
@@ -190,7 +293,23 @@ impl<'a, F: Field> RegionCtx<'a, F> {
         self.region.constrain_equal(cell_0, cell_1)
     }
     pub fn enable(&mut self, selector: Selector) -> Result<(), Error> {
-        selector.enable(&mut self.region, self.offset)
+        selector.enable(&mut self.region, self.offset)?;
+        match self
+            .enabled
+            .iter_mut()
+            .find(|(enabled, _)| *enabled == selector)
+        {
+            Some((_, rows)) => *rows += 1,
+            None => self.enabled.push((selector, 1)),
+        }
+        Ok(())
+    }
+    /// Number of rows the selector is enabled at in the region so far
+    pub fn enabled(&self, selector: Selector) -> usize {
+        self.enabled
+            .iter()
+            .find(|(enabled, _)| *enabled == selector)
+            .map_or(0, |(_, rows)| *rows)
     }
     pub fn next(&mut self) {
         self.offset += 1
@@ -0,0 +1,240 @@
+use super::config::{MemoryChip, Sorted};
+use super::context::MemoryGate;
+use super::{Memory, SortedQuery};
+use crate::{error::MsmError, AssignedValue, RegionCtx};
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::Error,
+};
+
+/// Content of a query cell
+enum Witness<'a, F: PrimeField> {
+    Empty,
+    Advice(Value<F>),
+    Copy(&'a AssignedValue<F>),
+}
+
+impl<F: PrimeField + Ord> MemoryChip<F> {
+    /// Number of queries so far which is the timestamp of the next one
+    pub fn timestamp(&self) -> usize {
+        self.memory.timestamp()
+    }
+    /// Values stored at the address. Gates that embed queries in their own
+    /// rows read them before recording the query with `record`
+    pub(crate) fn peek(&self, address: &Value<F>) -> Value<Vec<F>> {
+        self.memory.get(address)
+    }
+    /// Records a query writing `values` and returns the values it reads
+    pub(crate) fn record(
+        &mut self,
+        address: &Value<F>,
+        values: &Value<Vec<F>>,
+    ) -> Result<Value<Vec<F>>, MsmError> {
+        self.memory.write(address, values)
+    }
+    /// Coordinates of the point stored at the address
+    pub(crate) fn peek_point(&self, address: &Value<F>) -> Value<(F, F)> {
+        self.peek(address).map(|values| (values[0], values[1]))
+    }
+    pub(crate) fn record_point(
+        &mut self,
+        address: &Value<F>,
+        coords: &Value<(F, F)>,
+    ) -> Result<(), MsmError> {
+        self.record(address, &coords.map(|(x, y)| vec![x, y]))?;
+        Ok(())
+    }
+    /// Records a query writing zeros back
+    pub(crate) fn record_zero(&mut self, address: &Value<F>) -> Result<(), MsmError> {
+        let zeros = address.map(|_| vec![F::ZERO; self.width]);
+        self.record(address, &zeros)?;
+        Ok(())
+    }
+    /// Assigns the cells row by row and fills the rest of the last row with
+    /// zeros. The timestamp goes to the fixed column at the first row
+    fn assign_cells(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        annotation: &str,
+        mut cells: Vec<Witness<'_, F>>,
+        timestamp: Option<usize>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let number_of_columns = self.columns.len();
+        let number_of_rows = self.rows(cells.len());
+        cells.resize_with(number_of_rows * number_of_columns, || Witness::Empty);
+        let mut assigned = vec![];
+        for (i, cell) in cells.into_iter().enumerate() {
+            let column = self.columns[i % number_of_columns];
+            assigned.push(match cell {
+                Witness::Empty => ctx.empty(|| annotation, column.into())?,
+                Witness::Advice(value) => ctx.advice(|| annotation, column, value)?,
+                Witness::Copy(value) => ctx.copy(|| annotation, column, value)?,
+            });
+            if (i + 1) % number_of_columns == 0 {
+                match timestamp {
+                    Some(timestamp) if i < number_of_columns => {
+                        ctx.fixed(|| annotation, self.timestamp, F::from(timestamp as u64))?
+                    }
+                    _ => ctx.empty(|| annotation, self.timestamp.into())?,
+                };
+                ctx.next();
+            }
+        }
+        Ok(assigned)
+    }
+    /// Assigns a query and returns its cells
+    fn assign_query(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        annotation: &str,
+        address: &AssignedValue<F>,
+        write: Vec<Witness<'_, F>>,
+        values: &Value<Vec<F>>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let timestamp = self.memory.timestamp();
+        let read = self.record(&address.value().copied(), values)?;
+        ctx.enable(self.s_query)?;
+        if let Some(s_address) = self.s_address {
+            ctx.enable(s_address)?;
+        }
+        let mut cells = vec![Witness::Copy(address)];
+        for i in 0..self.width {
+            cells.push(Witness::Advice(read.as_ref().map(|read| read[i])));
+        }
+        cells.extend(write);
+        self.assign_cells(ctx, annotation, cells, Some(timestamp))
+    }
+    /// Writes `values` to the address and returns the values it held
+    pub fn read_write(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        values: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        self.memory.check_width(values.len())?;
+        let write: Value<Vec<F>> =
+            Value::from_iter(values.iter().map(|value| value.value().copied()));
+        let cells = values.iter().map(Witness::Copy).collect();
+        let assigned = self.assign_query(ctx, "read write", address, cells, &write)?;
+        Ok(assigned[1..1 + self.width].to_vec())
+    }
+    pub fn write(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        values: &[AssignedValue<F>],
+    ) -> Result<(), Error> {
+        self.read_write(ctx, address, values)?;
+        Ok(())
+    }
+    /// Reads the values stored at the address. Addresses that are not
+    /// written yet read zeros
+    pub fn read(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let values = self.peek(&address.value().copied());
+        let cells = (0..self.width)
+            .map(|i| Witness::Advice(values.as_ref().map(|values| values[i])))
+            .collect();
+        let assigned = self.assign_query(ctx, "read", address, cells, &values)?;
+        let (read, write) = assigned[1..].split_at(self.width);
+        for (read, write) in read.iter().zip(write.iter()) {
+            ctx.equal(read.cell(), write.cell())?;
+        }
+        Ok(read.to_vec())
+    }
+    /// Lays out the sorted queries. It is called once after all queries are
+    /// made, and the range table has to cover the timestamp range of
+    /// `timestamp()` values
+    pub fn layout_sorted(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.assign_sorted(ly, &self.memory.sort())
+    }
+    pub(crate) fn assign_sorted(
+        &self,
+        ly: &mut impl Layouter<F>,
+        sorted_queries: &Value<Vec<SortedQuery<F>>>,
+    ) -> Result<(), Error> {
+        let number_of_queries = self.memory.timestamp();
+        ly.assign_region(
+            || "sorted rw",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let query = |i: usize| sorted_queries.as_ref().map(|queries| queries[i].clone());
+                match &self.sorted {
+                    Sorted::Rows {
+                        s_sorted,
+                        s_first,
+                        s_transition,
+                    } => {
+                        for i in 0..number_of_queries {
+                            ctx.enable(*s_sorted)?;
+                            if i == 0 {
+                                ctx.enable(*s_first)?;
+                            } else {
+                                ctx.enable(*s_transition)?;
+                            }
+                            let query = query(i);
+                            let mut cells =
+                                vec![Witness::Advice(query.as_ref().map(|query| query.address))];
+                            for j in 0..self.width {
+                                cells.push(Witness::Advice(
+                                    query.as_ref().map(|query| query.read[j]),
+                                ));
+                            }
+                            for j in 0..self.width {
+                                cells.push(Witness::Advice(
+                                    query.as_ref().map(|query| query.write[j]),
+                                ));
+                            }
+                            cells
+                                .push(Witness::Advice(query.as_ref().map(|query| query.timestamp)));
+                            self.assign_cells(ctx, "sorted rw", cells, None)?;
+                        }
+                    }
+                    Sorted::Packed { s_head, s_slots } => {
+                        for first in (0..number_of_queries).step_by(s_slots.len()) {
+                            let mut cells = vec![];
+                            let last = (first + s_slots.len()).min(number_of_queries);
+                            for (i, s_slot) in (first..last).zip(s_slots.iter()) {
+                                ctx.enable(if i == 0 { *s_head } else { *s_slot })?;
+                                let query = query(i);
+                                cells.push(Witness::Advice(
+                                    query.as_ref().map(|query| query.address),
+                                ));
+                                for j in 0..self.width {
+                                    cells.push(Witness::Advice(
+                                        query.as_ref().map(|query| query.write[j]),
+                                    ));
+                                }
+                                cells.push(Witness::Advice(
+                                    query.as_ref().map(|query| query.timestamp),
+                                ));
+                            }
+                            self.assign_cells(ctx, "sorted rw", cells, None)?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: PrimeField + Ord> MemoryGate<F> for MemoryChip<F> {
+    type Memory = Memory<F>;
+    fn new_memory(&self) -> Memory<F> {
+        Memory::new(self.width, self.memory.owner.clone())
+    }
+    fn replace_memory(&mut self, memory: Memory<F>) -> Result<Memory<F>, Error> {
+        if memory.width != self.width || memory.owner != self.memory.owner {
+            return Err(MsmError::MemoryMismatch.into());
+        }
+        Ok(std::mem::replace(&mut self.memory, memory))
+    }
+    fn layout_memory(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.layout_sorted(ly)
+    }
+}
@@ -0,0 +1,375 @@
+use super::{Memory, Owner};
+use crate::range::{RangeTable, Tag};
+use ff::PrimeField;
+use halo2::{
+    plonk::{
+        Advice, Column, ConstraintSystem, Constraints, Expression, Fixed, Selector, VirtualCells,
+    },
+    poly::Rotation,
+};
+
+/// Offline memory checking of read-write queries. A query reads the values
+/// stored at an address and writes new ones back, so a read writes back what
+/// it reads and a write reads the previous values. Queries are shuffled into
+/// a region sorted by address and timestamp where each read has to match the
+/// previous write to the same address and the first read of an address has
+/// to be zero. Addresses have to cover `0..n` without gaps, and
+/// timestamps are the order of the queries assigned to the fixed column at
+/// the first row of each query. Within an address the timestamp difference
+/// minus one is looked up in the timestamp range `0..number_of_queries` of
+/// the range table, so sorted timestamps strictly increase and a difference
+/// is at most the number of queries.
+/// Cells are placed row by row over the columns:
+///
+/// | query  | address | read_0 .. read_w | write_0 .. write_w |           |
+/// | sorted | address | read_0 .. read_w | write_0 .. write_w | timestamp |
+#[derive(Clone, Debug)]
+pub struct MemoryChip<F: PrimeField + Ord> {
+    pub(crate) columns: Vec<Column<Advice>>,
+    pub(crate) timestamp: Column<Fixed>,
+    pub(crate) width: usize,
+    pub(crate) s_query: Selector,
+    pub(crate) sorted: Sorted,
+    pub(crate) s_address: Option<Selector>,
+    pub(crate) range_table: RangeTable,
+    pub(crate) memory: Memory<F>,
+}
+
+/// Layout of the sorted queries
+#[derive(Clone, Debug)]
+pub(crate) enum Sorted {
+    /// A sorted query per row. `s_first` enables the first query and
+    /// `s_transition` the rest
+    Rows {
+        s_sorted: Selector,
+        s_first: Selector,
+        s_transition: Selector,
+    },
+    /// Sorted queries side by side in slots of `address, write.., timestamp`.
+    /// `s_head` enables the first query at the first slot and `s_slots` the
+    /// rest at each slot
+    Packed {
+        s_head: Selector,
+        s_slots: Vec<Selector>,
+    },
+}
+
+impl<F: PrimeField + Ord> MemoryChip<F> {
+    /// `width` is the number of values stored at an address
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        columns: &[Column<Advice>],
+        timestamp: Column<Fixed>,
+        width: usize,
+        range_table: RangeTable,
+    ) -> Self {
+        let chip = Self::configure_gates(meta, columns, timestamp, width, range_table);
+        range_table.lookup(meta, "timestamp diff", |meta| chip.range_inputs(meta));
+        chip
+    }
+    /// Configures the chip with packed sorted queries. See `configure_packed_gates`
+    pub fn configure_packed(
+        meta: &mut ConstraintSystem<F>,
+        columns: &[Column<Advice>],
+        timestamp: Column<Fixed>,
+        width: usize,
+        range_table: RangeTable,
+    ) -> Self {
+        let chip = Self::configure_packed_gates(meta, columns, timestamp, width, range_table);
+        range_table.lookup(meta, "timestamp diff", |meta| chip.range_inputs(meta));
+        chip
+    }
+    fn new(
+        meta: &mut ConstraintSystem<F>,
+        columns: &[Column<Advice>],
+        timestamp: Column<Fixed>,
+        width: usize,
+        range_table: RangeTable,
+        sorted: Sorted,
+    ) -> Self {
+        assert!(width > 0);
+        assert!(!columns.is_empty());
+        for column in columns.iter() {
+            meta.enable_equality(*column);
+        }
+        let owner = Owner {
+            columns: columns.to_vec(),
+            packed: matches!(sorted, Sorted::Packed { .. }),
+        };
+        Self {
+            columns: columns.to_vec(),
+            timestamp,
+            width,
+            s_query: meta.complex_selector(),
+            sorted,
+            s_address: None,
+            range_table,
+            memory: Memory::new(width, owner),
+        }
+    }
+    /// Configures the chip without its timestamp lookup. Gates that embed
+    /// queries in their own rows share their columns and timestamp column
+    /// with the chip and look up `range_inputs` along with their own
+    pub(crate) fn configure_gates(
+        meta: &mut ConstraintSystem<F>,
+        columns: &[Column<Advice>],
+        timestamp: Column<Fixed>,
+        width: usize,
+        range_table: RangeTable,
+    ) -> Self {
+        let s_sorted = meta.complex_selector();
+        let s_first = meta.selector();
+        let s_transition = meta.complex_selector();
+        let sorted = Sorted::Rows {
+            s_sorted,
+            s_first,
+            s_transition,
+        };
+        let chip = Self::new(meta, columns, timestamp, width, range_table, sorted);
+        let s_query = chip.s_query;
+        let number_of_cells = chip.query_cells();
+        // sorted queries may span more than one row
+        let prev = -(chip.rows(chip.sorted_cells()) as i32);
+        let chip_ref = &chip;
+        let one = Expression::Constant(F::ONE);
+        meta.create_gate("first query", |meta| {
+            let s = meta.query_selector(s_first);
+            // addresses start at zero and memory is initialised with zeros
+            let constraints = (0..number_of_cells - width)
+                .map(|i| chip_ref.query(meta, i, 0))
+                .collect::<Vec<_>>();
+            Constraints::with_selector(s, constraints)
+        });
+        meta.create_gate("transition", |meta| {
+            let s = meta.query_selector(s_transition);
+            let address_prev = chip_ref.query(meta, 0, prev);
+            let address = chip_ref.query(meta, 0, 0);
+            let step = address - address_prev;
+            // sorted by address
+            let mut constraints = vec![e!(step) * (e!(step) - e!(one))];
+            for i in 0..width {
+                let read = chip_ref.query(meta, 1 + i, 0);
+                let write_prev = chip_ref.query(meta, 1 + width + i, prev);
+                // read the latest write to the same address
+                constraints.push((e!(one) - e!(step)) * (e!(read) - write_prev));
+                // memory is initialised with zeros
+                constraints.push(e!(step) * read);
+            }
+            Constraints::with_selector(s, constraints)
+        });
+        meta.shuffle("one to one map", |meta| {
+            let s_query = meta.query_selector(s_query);
+            let query_timestamp = meta.query_fixed(timestamp, Rotation::cur());
+            let query = (0..number_of_cells)
+                .map(|i| chip_ref.query(meta, i, 0))
+                .chain(std::iter::once(query_timestamp))
+                .collect::<Vec<_>>();
+            let s_sorted = meta.query_selector(s_sorted);
+            let sorted = (0..number_of_cells + 1)
+                .map(|i| chip_ref.query(meta, i, 0))
+                .collect::<Vec<_>>();
+            query
+                .into_iter()
+                .zip(sorted)
+                .map(|(query, sorted)| (e!(s_query) * query, e!(s_sorted) * sorted))
+                .collect::<Vec<_>>()
+        });
+        chip
+    }
+    /// Configures the chip without its timestamp lookup and with sorted
+    /// queries packed side by side, so that the sorted region takes a row per
+    /// `number_of_slots` queries. A slot holds `address, write.., timestamp`
+    /// and the read values follow from the write of the previous slot, or are
+    /// zero when the address steps. At least two slots have to fit in a row.
+    ///
+    /// Each slot is looked up in the queries instead of being shuffled. Sorted
+    /// queries are distinct since timestamps strictly increase within an
+    /// address, and there are as many of them as queries, so they still map
+    /// one to one. The price is a lookup per slot for the map and for the
+    /// timestamps of all but the first slot, whose timestamps are in
+    /// `range_inputs`
+    ///
+    /// | sorted | address | write_0 .. write_w | timestamp | address | ..
+    pub(crate) fn configure_packed_gates(
+        meta: &mut ConstraintSystem<F>,
+        columns: &[Column<Advice>],
+        timestamp: Column<Fixed>,
+        width: usize,
+        range_table: RangeTable,
+    ) -> Self {
+        let number_of_slots = columns.len() / (2 + width);
+        assert!(
+            number_of_slots > 1,
+            "two sorted queries have to fit in a row"
+        );
+        let s_head = meta.complex_selector();
+        let s_slots = (0..number_of_slots)
+            .map(|_| meta.complex_selector())
+            .collect::<Vec<_>>();
+        let sorted = Sorted::Packed {
+            s_head,
+            s_slots: s_slots.clone(),
+        };
+        let chip = Self::new(meta, columns, timestamp, width, range_table, sorted);
+        let s_query = chip.s_query;
+        let number_of_cells = chip.query_cells();
+        let chip_ref = &chip;
+        let one = Expression::Constant(F::ONE);
+        meta.create_gate("first query", |meta| {
+            let s = meta.query_selector(s_head);
+            // addresses start at zero
+            Constraints::with_selector(s, vec![chip_ref.slot(meta, 0, 0, 0)])
+        });
+        meta.create_gate("transition", |meta| {
+            // sorted by address
+            s_slots
+                .iter()
+                .enumerate()
+                .map(|(j, s)| {
+                    let s = meta.query_selector(*s);
+                    let step = chip_ref.step(meta, j);
+                    s * e!(step) * (step - e!(one))
+                })
+                .collect::<Vec<_>>()
+        });
+        for (j, s_slot) in s_slots.iter().enumerate() {
+            meta.lookup_any("one to one map", |meta| {
+                let s_query = meta.query_selector(s_query);
+                let query_timestamp = meta.query_fixed(timestamp, Rotation::cur());
+                let query = std::iter::once(e!(one))
+                    .chain((0..number_of_cells).map(|i| chip_ref.query(meta, i, 0)))
+                    .chain(std::iter::once(query_timestamp))
+                    .map(|query| e!(s_query) * query)
+                    .collect::<Vec<_>>();
+                let s = meta.query_selector(*s_slot);
+                let s_enabled = if j == 0 {
+                    e!(s) + meta.query_selector(s_head)
+                } else {
+                    e!(s)
+                };
+                let same_address = e!(one) - chip_ref.step(meta, j);
+                let (prev, rotation) = chip_ref.prev_slot(j);
+                let mut sorted = vec![e!(s_enabled), e!(s_enabled) * chip_ref.slot(meta, j, 0, 0)];
+                for i in 0..width {
+                    // the first query reads zeros
+                    let write_prev = chip_ref.slot(meta, prev, 1 + i, rotation);
+                    sorted.push(e!(s) * e!(same_address) * write_prev);
+                }
+                for i in 0..width + 1 {
+                    sorted.push(e!(s_enabled) * chip_ref.slot(meta, j, 1 + i, 0));
+                }
+                sorted.into_iter().zip(query).collect::<Vec<_>>()
+            });
+        }
+        for (j, s_slot) in s_slots.iter().enumerate().skip(1) {
+            range_table.lookup(meta, "timestamp diff", |meta| {
+                let s = meta.query_selector(*s_slot);
+                vec![(s, Tag::Timestamp, chip_ref.packed_timestamp_diff(meta, j))]
+            });
+        }
+        chip
+    }
+    /// Bounds addresses of the queries the chip assigns to the address range
+    /// of the range table
+    pub fn configure_address_range(&mut self, meta: &mut ConstraintSystem<F>) {
+        let s_address = meta.complex_selector();
+        let column = self.columns[0];
+        self.range_table.lookup(meta, "memory address", |meta| {
+            let s = meta.query_selector(s_address);
+            let address = meta.query_advice(column, Rotation::cur());
+            vec![(s, Tag::Address, address)]
+        });
+        self.s_address = Some(s_address);
+    }
+    /// Timestamp differences of sorted queries. Timestamps strictly increase
+    /// within an address
+    pub(crate) fn range_inputs(
+        &self,
+        meta: &mut VirtualCells<'_, F>,
+    ) -> Vec<(Expression<F>, Tag, Expression<F>)> {
+        match &self.sorted {
+            Sorted::Rows { s_transition, .. } => {
+                let number_of_cells = self.query_cells();
+                let prev = -(self.rows(self.sorted_cells()) as i32);
+                let one = Expression::Constant(F::ONE);
+                let s = meta.query_selector(*s_transition);
+                let timestamp_prev = self.query(meta, number_of_cells, prev);
+                let timestamp = self.query(meta, number_of_cells, 0);
+                let address_prev = self.query(meta, 0, prev);
+                let address = self.query(meta, 0, 0);
+                let same_address = e!(one) - (address - address_prev);
+                let diff = timestamp - timestamp_prev - one;
+                vec![(s, Tag::Timestamp, same_address * diff)]
+            }
+            Sorted::Packed { s_slots, .. } => {
+                let s = meta.query_selector(s_slots[0]);
+                vec![(s, Tag::Timestamp, self.packed_timestamp_diff(meta, 0))]
+            }
+        }
+    }
+    /// Cells of a query: `address, read.., write..`
+    pub(crate) fn query_cells(&self) -> usize {
+        1 + 2 * self.width
+    }
+    /// Cells of a sorted query: the query cells and the timestamp
+    pub(crate) fn sorted_cells(&self) -> usize {
+        self.query_cells() + 1
+    }
+    /// Cells of a packed sorted query: `address, write.., timestamp`
+    pub(crate) fn slot_cells(&self) -> usize {
+        2 + self.width
+    }
+    /// Number of rows `number_of_cells` cells span
+    pub(crate) fn rows(&self, number_of_cells: usize) -> usize {
+        div_ceil!(number_of_cells, self.columns.len())
+    }
+    /// Number of rows of the sorted region of `number_of_queries` queries
+    pub(crate) fn sorted_rows(&self, number_of_queries: usize) -> usize {
+        match &self.sorted {
+            Sorted::Rows { .. } => self.rows(self.sorted_cells()) * number_of_queries,
+            Sorted::Packed { .. } if number_of_queries == 0 => 0,
+            Sorted::Packed { s_slots, .. } => div_ceil!(number_of_queries, s_slots.len()),
+        }
+    }
+    /// Queries the `i`th cell of the query enabled `rotation` rows away
+    fn query(&self, meta: &mut VirtualCells<'_, F>, i: usize, rotation: i32) -> Expression<F> {
+        let number_of_columns = self.columns.len();
+        let rotation = rotation + (i / number_of_columns) as i32;
+        meta.query_advice(self.columns[i % number_of_columns], Rotation(rotation))
+    }
+    /// Queries the `i`th cell of the packed sorted query at slot `j`
+    /// `rotation` rows away
+    fn slot(
+        &self,
+        meta: &mut VirtualCells<'_, F>,
+        j: usize,
+        i: usize,
+        rotation: i32,
+    ) -> Expression<F> {
+        let column = self.columns[j * self.slot_cells() + i];
+        meta.query_advice(column, Rotation(rotation))
+    }
+    /// Slot and rotation of the packed sorted query before the one at slot
+    /// `j`
+    fn prev_slot(&self, j: usize) -> (usize, i32) {
+        if j == 0 {
+            (self.columns.len() / self.slot_cells() - 1, -1)
+        } else {
+            (j - 1, 0)
+        }
+    }
+    /// Address difference of the packed sorted query at slot `j` and the
+    /// previous one
+    fn step(&self, meta: &mut VirtualCells<'_, F>, j: usize) -> Expression<F> {
+        let (prev, rotation) = self.prev_slot(j);
+        self.slot(meta, j, 0, 0) - self.slot(meta, prev, 0, rotation)
+    }
+    fn packed_timestamp_diff(&self, meta: &mut VirtualCells<'_, F>, j: usize) -> Expression<F> {
+        let (prev, rotation) = self.prev_slot(j);
+        let one = Expression::Constant(F::ONE);
+        let timestamp_cell = 1 + self.width;
+        let timestamp_prev = self.slot(meta, prev, timestamp_cell, rotation);
+        let timestamp = self.slot(meta, j, timestamp_cell, 0);
+        (e!(one) - self.step(meta, j)) * (timestamp - timestamp_prev - one)
+    }
+}
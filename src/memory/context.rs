@@ -0,0 +1,73 @@
+use crate::RegionCtx;
+use ff::PrimeField;
+use halo2::{circuit::Layouter, plonk::Error};
+
+/// Gates that make memory queries while their regions are assigned. The
+/// gate works on the memory `MemoryContext` lends it for a region
+pub trait MemoryGate<F: PrimeField> {
+    type Memory: Clone;
+    /// Memory without queries
+    fn new_memory(&self) -> Self::Memory;
+    /// Gives the gate `memory` and returns the one it held. Memory of another
+    /// gate is rejected with `MsmError::MemoryMismatch`
+    fn replace_memory(&mut self, memory: Self::Memory) -> Result<Self::Memory, Error>;
+    /// Lays out the sorted queries along with the tables the gate looks up
+    /// into. It is called once after all queries are made
+    fn layout_memory(&self, ly: &mut impl Layouter<F>) -> Result<(), Error>;
+}
+
+/// Memory queries of a gate owned by the caller. Queries accumulate over
+/// the regions assigned through the context and the msm calls within them,
+/// and `layout` lays out a single sorted region for all of them at the end.
+/// Floor planners may run a region closure more than once, so each run
+/// starts from the memory the previous regions left
+#[derive(Clone, Debug)]
+pub struct MemoryContext<M> {
+    memory: M,
+}
+
+impl<M: Clone> MemoryContext<M> {
+    pub fn new<F: PrimeField, G: MemoryGate<F, Memory = M>>(gate: &G) -> Self {
+        Self {
+            memory: gate.new_memory(),
+        }
+    }
+    /// Assigns a region where `gate` makes its queries
+    pub fn assign_region<F, G, A, AR, N, NR>(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        gate: &mut G,
+        name: N,
+        mut assignment: A,
+    ) -> Result<AR, Error>
+    where
+        F: PrimeField,
+        G: MemoryGate<F, Memory = M>,
+        A: FnMut(&mut RegionCtx<'_, F>, &mut G) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        let memory = &self.memory;
+        let res = ly.assign_region(name, |region| {
+            gate.replace_memory(memory.clone())?;
+            let ctx = &mut RegionCtx::new(region);
+            assignment(ctx, gate)
+        })?;
+        let empty = gate.new_memory();
+        self.memory = gate.replace_memory(empty)?;
+        Ok(res)
+    }
+    /// Lays out the sorted queries of all regions
+    pub fn layout<F, G>(self, ly: &mut impl Layouter<F>, gate: &mut G) -> Result<(), Error>
+    where
+        F: PrimeField,
+        G: MemoryGate<F, Memory = M>,
+    {
+        gate.replace_memory(self.memory)?;
+        gate.layout_memory(ly)
+    }
+    /// Memory left by the regions so far
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+}
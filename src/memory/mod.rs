@@ -0,0 +1,125 @@
+pub mod assignments;
+pub mod config;
+pub mod context;
+#[cfg(test)]
+mod tests;
+
+use crate::error::{transpose, MsmError};
+use ff::PrimeField;
+use halo2::{
+    circuit::Value,
+    plonk::{Advice, Column},
+};
+use std::collections::BTreeMap;
+
+/// Query in the order it is made. It reads the values stored at the address
+/// and writes new ones back
+#[derive(Clone, Debug)]
+pub(crate) struct Query<F: PrimeField + Ord> {
+    pub(crate) address: Value<F>,
+    pub(crate) read: Value<Vec<F>>,
+    pub(crate) write: Value<Vec<F>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SortedQuery<F: PrimeField + Ord> {
+    pub(crate) timestamp: F,
+    pub(crate) address: F,
+    pub(crate) read: Vec<F>,
+    pub(crate) write: Vec<F>,
+}
+
+/// Layout of the chip a memory belongs to. Chips of a circuit don't share
+/// query columns, so the columns and whether sorted queries are packed tell
+/// them apart
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Owner {
+    pub(crate) columns: Vec<Column<Advice>>,
+    pub(crate) packed: bool,
+}
+
+/// Witness of the memory. Addresses that are not written yet hold zeros
+#[derive(Clone, Debug)]
+pub struct Memory<F: PrimeField + Ord> {
+    pub(crate) queries: Vec<Query<F>>,
+    pub(crate) state: BTreeMap<F, Vec<F>>,
+    pub(crate) width: usize,
+    pub(crate) owner: Owner,
+}
+
+impl<F: PrimeField + Ord> Memory<F> {
+    pub(crate) fn new(width: usize, owner: Owner) -> Self {
+        Self {
+            queries: vec![],
+            state: BTreeMap::new(),
+            width,
+            owner,
+        }
+    }
+    /// Number of queries so far
+    pub fn timestamp(&self) -> usize {
+        self.queries.len()
+    }
+    /// Values stored at the address
+    pub(crate) fn get(&self, address: &Value<F>) -> Value<Vec<F>> {
+        address.map(|address| {
+            self.state
+                .get(&address)
+                .cloned()
+                .unwrap_or_else(|| vec![F::ZERO; self.width])
+        })
+    }
+    /// Records a query writing `values` and returns the values it reads
+    pub(crate) fn write(
+        &mut self,
+        address: &Value<F>,
+        values: &Value<Vec<F>>,
+    ) -> Result<Value<Vec<F>>, MsmError> {
+        transpose(values.as_ref().map(|values| self.check_width(values.len())))?;
+        let read = self.get(address);
+        address.zip(values.clone()).map(|(address, values)| {
+            self.state.insert(address, values);
+        });
+        self.queries.push(Query {
+            address: *address,
+            read: read.clone(),
+            write: values.clone(),
+        });
+        Ok(read)
+    }
+    /// Queries store `width` values at an address
+    pub(crate) fn check_width(&self, number_of_values: usize) -> Result<(), MsmError> {
+        if number_of_values != self.width {
+            return Err(MsmError::TableMismatch {
+                expected: self.width,
+                actual: number_of_values,
+            });
+        }
+        Ok(())
+    }
+    /// Queries ordered by address and then by timestamp
+    pub(crate) fn sort(&self) -> Value<Vec<SortedQuery<F>>> {
+        let sorted_queries = self
+            .queries
+            .iter()
+            .enumerate()
+            .map(|(timestamp, query)| {
+                query
+                    .address
+                    .zip(query.read.clone())
+                    .zip(query.write.clone())
+                    .map(|((address, read), write)| SortedQuery {
+                        timestamp: F::from(timestamp as u64),
+                        address,
+                        read,
+                        write,
+                    })
+            })
+            .collect::<Vec<_>>();
+        let mut sorted_queries: Value<Vec<SortedQuery<_>>> = Value::from_iter(sorted_queries);
+        sorted_queries.as_mut().map(|queries| {
+            queries.sort_by(|a, b| a.address.cmp(&b.address));
+        });
+        sorted_queries
+    }
+}
@@ -0,0 +1,332 @@
+use super::config::MemoryChip;
+use super::context::{MemoryContext, MemoryGate};
+use super::{Memory, Owner};
+use crate::error::MsmError;
+use crate::range::{RangeTable, Tag};
+use ff::{Field, PrimeField};
+use halo2::dev::MockProver;
+use halo2::halo2curves::pasta::Fq;
+use halo2::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Column, Error, Instance},
+    plonk::{Circuit, ConstraintSystem},
+};
+
+const K: u32 = 8;
+const NUMBER_OF_ADDRESSES: usize = 8;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Write(u64, Vec<u64>),
+    Read(u64),
+    /// Read whose witness is replaced with the given values
+    ForgedRead(u64, Vec<u64>),
+}
+
+/// Tampering with the sorted queries
+#[derive(Clone, Copy, Debug)]
+enum Forge {
+    /// Swaps two sorted queries
+    Swap(usize, usize),
+    /// Gives a sorted query the timestamp of the previous one
+    Duplicate(usize),
+    /// Moves a sorted query to the next address
+    Shift(usize),
+}
+
+#[derive(Default, Clone, Debug)]
+struct Params {
+    width: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: PrimeField + Ord> {
+    chip: MemoryChip<F>,
+    advice: Vec<Column<Advice>>,
+    instance: Column<Instance>,
+    range_table: RangeTable,
+}
+
+#[derive(Debug, Default)]
+struct MyCircuit {
+    width: usize,
+    ops: Vec<Op>,
+    /// Number of regions the ops are split into
+    regions: usize,
+    forge: Option<Forge>,
+}
+
+impl Circuit<Fq> for MyCircuit {
+    type Config = TestConfig<Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = Params;
+    fn without_witnesses(&self) -> Self {
+        Self {
+            width: self.width,
+            ops: self.ops.clone(),
+            regions: self.regions,
+            forge: self.forge,
+        }
+    }
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fq>,
+        params: Self::Params,
+    ) -> Self::Config {
+        let advice = (0..5).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let timestamp = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let range_table = RangeTable::configure(meta);
+        let mut chip = MemoryChip::configure(meta, &advice, timestamp, params.width, range_table);
+        chip.configure_address_range(meta);
+        TestConfig {
+            chip,
+            advice,
+            instance,
+            range_table,
+        }
+    }
+    fn configure(_: &mut ConstraintSystem<Fq>) -> Self::Config {
+        unreachable!();
+    }
+    fn synthesize(&self, mut cfg: Self::Config, mut ly: impl Layouter<Fq>) -> Result<(), Error> {
+        let ly = &mut ly;
+        let advice = &cfg.advice;
+        let mut memory = MemoryContext::new(&cfg.chip);
+        let mut reads = vec![];
+        for ops in self.ops.chunks(div_ceil!(self.ops.len(), self.regions)) {
+            let region_reads = memory.assign_region(
+                ly,
+                &mut cfg.chip,
+                || "memory",
+                |ctx, chip| {
+                    let mut reads = vec![];
+                    for op in ops.iter() {
+                        let (address, values) = match op {
+                            Op::Write(address, values) => (*address, values.clone()),
+                            Op::Read(address) | Op::ForgedRead(address, _) => (*address, vec![]),
+                        };
+                        // address and values to write
+                        let address =
+                            ctx.advice(|| "address", advice[0], Value::known(address.into()))?;
+                        let values = values
+                            .iter()
+                            .enumerate()
+                            .map(|(i, value)| {
+                                ctx.advice(|| "value", advice[i + 1], Value::known((*value).into()))
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        for column in advice.iter().skip(values.len() + 1) {
+                            ctx.empty(|| "witness:", (*column).into())?;
+                        }
+                        ctx.next();
+                        match op {
+                            Op::Write(..) => chip.write(ctx, &address, &values)?,
+                            Op::Read(_) => reads.extend(chip.read(ctx, &address)?),
+                            Op::ForgedRead(_, forged) => {
+                                address.value().map(|address| {
+                                    let forged =
+                                        forged.iter().map(|value| (*value).into()).collect();
+                                    chip.memory.state.insert(*address, forged);
+                                });
+                                reads.extend(chip.read(ctx, &address)?);
+                            }
+                        }
+                    }
+                    Ok(reads)
+                },
+            )?;
+            reads.extend(region_reads);
+        }
+        for (row, read) in reads.iter().enumerate() {
+            ly.constrain_instance(read.cell(), cfg.instance, row)?;
+        }
+        let number_of_queries = memory.memory().timestamp();
+        match self.forge {
+            None => memory.layout(ly, &mut cfg.chip)?,
+            Some(forge) => {
+                let mut sorted = memory.memory().sort();
+                sorted.as_mut().map(|sorted| match forge {
+                    Forge::Swap(i, j) => sorted.swap(i, j),
+                    Forge::Duplicate(i) => sorted[i].timestamp = sorted[i - 1].timestamp,
+                    Forge::Shift(i) => sorted[i].address += Fq::from(1),
+                });
+                cfg.chip.replace_memory(memory.memory().clone())?;
+                cfg.chip.assign_sorted(ly, &sorted)?;
+            }
+        }
+        cfg.range_table.layout(
+            ly,
+            &[
+                (Tag::Address, NUMBER_OF_ADDRESSES),
+                (Tag::Timestamp, number_of_queries),
+            ],
+        )
+    }
+    fn params(&self) -> Self::Params {
+        Params { width: self.width }
+    }
+}
+
+fn run_forged(
+    width: usize,
+    ops: Vec<Op>,
+    reads: &[u64],
+    regions: usize,
+    forge: Option<Forge>,
+) -> Result<(), Vec<halo2::dev::VerifyFailure>> {
+    let circuit = MyCircuit {
+        width,
+        ops,
+        regions,
+        forge,
+    };
+    let public_inputs = reads.iter().map(|read| Fq::from(*read)).collect();
+    let prover = match MockProver::run(K, &circuit, vec![public_inputs]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.verify()
+}
+
+fn run(width: usize, ops: Vec<Op>, reads: &[u64]) -> Result<(), Vec<halo2::dev::VerifyFailure>> {
+    run_forged(width, ops, reads, 1, None)
+}
+
+#[test]
+fn test_memory_read_after_write() {
+    let ops = vec![
+        Op::Write(0, vec![1, 2]),
+        Op::Read(0),
+        Op::Write(0, vec![3, 4]),
+        Op::Read(0),
+        Op::Read(0),
+    ];
+    assert_eq!(run(2, ops, &[1, 2, 3, 4, 3, 4]), Ok(()));
+    let ops = vec![Op::Write(0, vec![1, 2]), Op::ForgedRead(0, vec![1, 3])];
+    assert!(run(2, ops, &[1, 3]).is_err());
+}
+
+#[test]
+fn test_memory_multiple_addresses() {
+    let ops = vec![
+        Op::Write(2, vec![5]),
+        Op::Write(0, vec![1]),
+        Op::Write(1, vec![3]),
+        Op::Read(2),
+        Op::Read(0),
+        Op::Write(2, vec![7]),
+        Op::Read(1),
+        Op::Read(2),
+    ];
+    assert_eq!(run(1, ops, &[5, 1, 3, 7]), Ok(()));
+    let ops = vec![
+        Op::Write(0, vec![1, 2, 3]),
+        Op::Write(1, vec![4, 5, 6]),
+        Op::Read(1),
+        Op::Read(0),
+    ];
+    assert_eq!(run(3, ops, &[4, 5, 6, 1, 2, 3]), Ok(()));
+    // value of the other address
+    let ops = vec![
+        Op::Write(0, vec![1]),
+        Op::Write(1, vec![3]),
+        Op::ForgedRead(1, vec![1]),
+    ];
+    assert!(run(1, ops, &[1]).is_err());
+}
+
+#[test]
+fn test_memory_uninitialised_read() {
+    let ops = vec![Op::Write(1, vec![1, 2]), Op::Read(0), Op::Read(1)];
+    assert_eq!(run(2, ops, &[0, 0, 1, 2]), Ok(()));
+    let ops = vec![Op::ForgedRead(0, vec![9, 9]), Op::Read(0)];
+    assert!(run(2, ops, &[9, 9, 9, 9]).is_err());
+}
+
+#[test]
+fn test_memory_forged_timestamps() {
+    let ops = vec![Op::Write(0, vec![1]), Op::Read(0), Op::Read(0)];
+    assert_eq!(run_forged(1, ops.clone(), &[1, 1], 1, None), Ok(()));
+    for forge in [Forge::Swap(1, 2), Forge::Duplicate(2)] {
+        let failures = run_forged(1, ops.clone(), &[1, 1], 1, Some(forge)).unwrap_err();
+        assert!(
+            format!("{failures:?}").contains("timestamp diff"),
+            "{forge:?}"
+        );
+    }
+}
+
+#[test]
+fn test_memory_first_query() {
+    // the first sorted query is pinned to address zero, there is no free
+    // previous address to start the transitions from
+    let ops = vec![Op::Write(0, vec![1]), Op::Read(0)];
+    let failures = run_forged(1, ops, &[1], 1, Some(Forge::Shift(0))).unwrap_err();
+    assert!(format!("{failures:?}").contains("first query"));
+}
+
+#[test]
+fn test_memory_regions() {
+    // reads see the writes of earlier regions
+    let ops = vec![
+        Op::Write(0, vec![1]),
+        Op::Write(1, vec![2]),
+        Op::Read(0),
+        Op::Write(0, vec![3]),
+        Op::Read(1),
+        Op::Read(0),
+    ];
+    for regions in [2, 3, 6] {
+        assert_eq!(
+            run_forged(1, ops.clone(), &[1, 2, 3], regions, None),
+            Ok(())
+        );
+    }
+    let ops = vec![Op::Write(0, vec![1]), Op::ForgedRead(0, vec![2])];
+    assert!(run_forged(1, ops, &[2], 2, None).is_err());
+}
+
+#[test]
+fn test_memory_width_mismatch() {
+    let mut memory = Memory::<Fq>::new(2, Owner::default());
+    let read = memory.write(&Value::known(Fq::ZERO), &Value::known(vec![Fq::ONE]));
+    assert_eq!(
+        read.err(),
+        Some(MsmError::TableMismatch {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(memory.timestamp(), 0);
+    // the chip rejects the query at synthesis
+    let circuit = MyCircuit {
+        width: 2,
+        ops: vec![Op::Write(0, vec![1])],
+        regions: 1,
+        forge: None,
+    };
+    assert!(matches!(
+        MockProver::run(K, &circuit, vec![vec![]]),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn test_memory_of_another_chip() {
+    let meta = &mut ConstraintSystem::<Fq>::default();
+    let advice = (0..8).map(|_| meta.advice_column()).collect::<Vec<_>>();
+    let other = (0..8).map(|_| meta.advice_column()).collect::<Vec<_>>();
+    let timestamp = meta.fixed_column();
+    let range_table = RangeTable::configure(meta);
+    let mut chip = MemoryChip::configure(meta, &advice, timestamp, 2, range_table);
+    let rows = MemoryChip::configure(meta, &other, timestamp, 2, range_table);
+    let packed = MemoryChip::configure_packed(meta, &other, timestamp, 2, range_table);
+    // same width but other columns or another layout of sorted queries
+    for memory in [rows.new_memory(), packed.new_memory()] {
+        assert!(matches!(chip.replace_memory(memory), Err(Error::Synthesis)));
+    }
+    assert!(rows.new_memory().owner != packed.new_memory().owner);
+    let memory = chip.new_memory();
+    assert!(chip.replace_memory(memory).is_ok());
+}
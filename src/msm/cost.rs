@@ -0,0 +1,335 @@
+use crate::error::MsmError;
+use crate::msm_var::layout::{ADD_CELLS, DOUBLE_CELLS, QUERY_CELLS, SORTED_CELLS, TABLE_CELLS};
+use crate::util::scalar_max;
+use ff::PrimeField;
+
+/// Gate layout of the estimate. Only the default configurations are
+/// covered, that is unsigned windows without the endomorphism and with
+/// incomplete addition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    Fixed,
+    BucketNarrow,
+    BucketWide,
+    /// Wide bucket gate with two sorted queries per row
+    BucketWidePacked,
+    /// Generic bucket gate over the given number of advice columns
+    BucketGeneric(usize),
+    SlidingNarrow,
+    SlidingWide,
+    /// Generic sliding window gate over the given number of advice columns
+    SlidingGeneric(usize),
+    /// Divisor gate which runs a round per bit whatever the window
+    Eagen,
+}
+
+impl Strategy {
+    /// Advice columns of the layout. Narrow and wide bucket and sliding
+    /// window gates are the generic ones on five and nine columns. The Eagen
+    /// gate has five columns in each phase
+    pub fn advice_columns(&self) -> usize {
+        match self {
+            Strategy::Eagen => 10,
+            Strategy::Fixed | Strategy::BucketNarrow | Strategy::SlidingNarrow => 5,
+            Strategy::BucketWide | Strategy::BucketWidePacked | Strategy::SlidingWide => 9,
+            Strategy::BucketGeneric(number_of_columns)
+            | Strategy::SlidingGeneric(number_of_columns) => *number_of_columns,
+        }
+    }
+}
+
+/// Rows that `Msm::msm` emits in a fresh region along with the tables it
+/// needs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cost {
+    pub advice_columns: usize,
+    /// Rows of the msm region
+    pub advice_rows: usize,
+    /// Rows of the sorted read-write region of bucket gates
+    pub sorted_rw_rows: usize,
+    /// Lookups of the range table. Bucket gates also look up a timestamp
+    /// difference for each sorted query but the first, which the packed
+    /// layout spreads over an argument per slot
+    pub lookup_rows: usize,
+    /// Rows of the range table. Bucket gates also lay out a timestamp
+    /// difference per query
+    pub range_table_rows: usize,
+    /// Rows of the point table of the fixed base gate
+    pub point_table_rows: usize,
+    /// Smallest `k` that fits the regions and the tables
+    pub k: u32,
+}
+
+/// Largest window the planner considers
+pub const MAX_WINDOW: usize = 16;
+
+impl Cost {
+    /// Rows of the msm and the sorted read-write region
+    pub fn rows(&self) -> usize {
+        self.advice_rows + self.sorted_rw_rows
+    }
+    /// Advice cells spent on the msm and the sorted read-write region
+    pub fn area(&self) -> usize {
+        self.advice_columns * self.rows()
+    }
+}
+
+/// Rows that each operation of a layout takes
+struct Ops {
+    constant: usize,
+    write: usize,
+    read: usize,
+    add: usize,
+    rw_add: usize,
+    read_add: usize,
+    dbl: usize,
+}
+
+/// Bit length of full size scalars of the native field `F` and the scalar
+/// field `S`
+pub fn scalar_bits<F: PrimeField, S: PrimeField>() -> usize {
+    scalar_max::<F, S>().bits() as usize
+}
+
+/// Estimates the cost of an msm of `number_of_terms` terms whose scalars
+/// are at most `scalar_bits` bits. Pass `scalar_bits::<F, S>()` for the
+/// full size scalars of `msm` and the bound for those of `msm_short`
+pub fn estimate(
+    strategy: Strategy,
+    number_of_terms: usize,
+    window: usize,
+    scalar_bits: usize,
+) -> Cost {
+    assert!(number_of_terms > 0);
+    assert!(window > 0 && scalar_bits > 0);
+    let window = if strategy == Strategy::Eagen {
+        1
+    } else {
+        window
+    };
+    let number_of_rounds = div_ceil!(scalar_bits, window);
+    // decompositions start with a zero constant shared with the rest
+    let decompose = 1 + number_of_terms * (number_of_rounds + 2);
+    let advice_columns = strategy.advice_columns();
+    let max_queries = match strategy {
+        Strategy::BucketNarrow | Strategy::BucketGeneric(_) => {
+            assert!(advice_columns >= 5);
+            // sorted queries span two rows and look one query back
+            if advice_columns == 5 {
+                4
+            } else {
+                3
+            }
+        }
+        // divisor checks look three rows ahead and evaluations one row back
+        Strategy::Eagen => 5,
+        _ => 3,
+    };
+    let narrow = Ops {
+        constant: 1,
+        write: 1,
+        read: 1,
+        add: 2,
+        rw_add: 2,
+        read_add: 2,
+        dbl: 2,
+    };
+    // bucket gates write and read points with memory queries and sliding
+    // window gates with table entries
+    let generic = |number_of_columns: usize, query_cells: usize| {
+        let rows = |number_of_cells: usize| div_ceil!(number_of_cells, number_of_columns);
+        Ops {
+            constant: 1,
+            write: rows(query_cells),
+            read: rows(query_cells),
+            add: rows(ADD_CELLS),
+            rw_add: rows(ADD_CELLS),
+            read_add: rows(ADD_CELLS),
+            dbl: rows(DOUBLE_CELLS),
+        }
+    };
+    // windows, addresses of every write and addition and timestamps of the
+    // sorted queries
+    let bucket_lookups = |queries: usize| {
+        let buckets = 1 << window;
+        number_of_terms * number_of_rounds
+            + number_of_rounds * (buckets + number_of_terms)
+            + (queries - 1)
+    };
+    let mut cost = match strategy {
+        Strategy::Fixed => {
+            let (rows, lookup_rows) = fixed(&narrow, number_of_terms, window, number_of_rounds);
+            Cost {
+                advice_rows: decompose + rows,
+                lookup_rows,
+                point_table_rows: (number_of_terms << window) + 1,
+                ..Default::default()
+            }
+        }
+        Strategy::BucketWidePacked => {
+            let op_rows = generic(advice_columns, QUERY_CELLS);
+            let (rows, queries) = bucket(&op_rows, number_of_terms, window, number_of_rounds);
+            Cost {
+                advice_rows: decompose + rows,
+                sorted_rw_rows: div_ceil!(queries, 2),
+                lookup_rows: bucket_lookups(queries),
+                range_table_rows: queries,
+                ..Default::default()
+            }
+        }
+        Strategy::BucketNarrow | Strategy::BucketWide | Strategy::BucketGeneric(_) => {
+            let op_rows = generic(advice_columns, QUERY_CELLS);
+            let (rows, queries) = bucket(&op_rows, number_of_terms, window, number_of_rounds);
+            Cost {
+                advice_rows: decompose + rows,
+                sorted_rw_rows: div_ceil!(SORTED_CELLS, advice_columns) * queries,
+                lookup_rows: bucket_lookups(queries),
+                range_table_rows: queries,
+                ..Default::default()
+            }
+        }
+        Strategy::SlidingNarrow | Strategy::SlidingWide | Strategy::SlidingGeneric(_) => {
+            let op_rows = generic(advice_columns, TABLE_CELLS);
+            let (rows, lookup_rows) = sliding(&op_rows, number_of_terms, window, number_of_rounds);
+            Cost {
+                advice_rows: decompose + rows,
+                lookup_rows,
+                ..Default::default()
+            }
+        }
+        // bits are boolean so the gate has no range table. Additions and
+        // doublings take two rows as in the narrow layout
+        Strategy::Eagen => Cost {
+            advice_rows: decompose + eagen(&narrow, number_of_terms, number_of_rounds),
+            ..Default::default()
+        },
+    };
+    cost.advice_columns = advice_columns;
+    if strategy != Strategy::Eagen {
+        cost.range_table_rows += 1 << window;
+    }
+    let rows = std::cmp::max(
+        cost.advice_rows + cost.sorted_rw_rows,
+        std::cmp::max(cost.range_table_rows, cost.point_table_rows),
+    );
+    // blinding rows at the end of the columns
+    let unusable_rows = std::cmp::max(3, max_queries) + 3;
+    cost.k = (rows + unusable_rows).next_power_of_two().trailing_zeros();
+    cost
+}
+
+/// Picks the window that minimises the rows of an msm of `number_of_terms`
+/// terms among the ones that fit in `2^k` rows. Columns of a strategy don't
+/// depend on the window so the same window minimises the area, and
+/// `Cost::area` compares strategies. Ties go to the smaller range table.
+/// Signed digits need windows of at least two bits, so window 1 is skipped
+/// when `signed` is set
+pub fn plan(
+    strategy: Strategy,
+    number_of_terms: usize,
+    scalar_bits: usize,
+    k: u32,
+    signed: bool,
+) -> Option<(usize, Cost)> {
+    let min_window = if signed { 2 } else { 1 };
+    let max_window = if strategy == Strategy::Eagen {
+        1
+    } else {
+        MAX_WINDOW
+    };
+    (min_window..=max_window)
+        .map(|window| {
+            (
+                window,
+                estimate(strategy, number_of_terms, window, scalar_bits),
+            )
+        })
+        .filter(|(_, cost)| cost.k <= k)
+        .min_by_key(|(window, cost)| (cost.rows(), *window))
+}
+
+/// Window of `configure_auto` for full size scalars
+pub(crate) fn auto_window<F: PrimeField, S: PrimeField>(
+    strategy: Strategy,
+    number_of_terms: usize,
+    k: u32,
+    signed: bool,
+) -> Result<usize, MsmError> {
+    plan(strategy, number_of_terms, scalar_bits::<F, S>(), k, signed)
+        .map(|(window, _)| window)
+        .ok_or(MsmError::NoWindow { number_of_terms, k })
+}
+
+/// Rows of the bucket accumulation after the decompositions along with the
+/// number of memory queries
+fn bucket(
+    op: &Ops,
+    number_of_terms: usize,
+    window: usize,
+    number_of_rounds: usize,
+) -> (usize, usize) {
+    let buckets = 1 << window;
+    // coordinates of the initial buckets, addresses but zero and the
+    // coordinates of the correction point
+    let constants = 2 * buckets + (buckets - 1) + 2;
+    let round = buckets * op.write
+        + number_of_terms * op.rw_add
+        + 2 * op.read
+        + (buckets - 2) * (op.read_add + op.add);
+    let doublings = (number_of_rounds - 1) * (window * op.dbl + op.add);
+    let rows = constants * op.constant + number_of_rounds * round + doublings + op.add;
+    let queries = number_of_rounds * (buckets + number_of_terms + 2 + (buckets - 2));
+    (rows, queries)
+}
+
+/// Rows of the table and the accumulation of the sliding window gate after
+/// the decompositions along with the lookup rows
+fn sliding(
+    op: &Ops,
+    number_of_terms: usize,
+    window: usize,
+    number_of_rounds: usize,
+) -> (usize, usize) {
+    let table_size = 1 << window;
+    // aux offset of each table followed by its entries
+    let table = number_of_terms * (2 * op.constant + table_size * op.write)
+        + number_of_terms * (table_size - 1) * op.add;
+    let reads = op.read + (number_of_terms * number_of_rounds - 1) * op.read_add;
+    let doublings = (number_of_rounds - 1) * window * op.dbl;
+    let correction = 2 * op.constant + op.add;
+    let rows = table + reads + doublings + correction;
+    (rows, 2 * number_of_terms * number_of_rounds)
+}
+
+/// Rows of the divisor checks of the Eagen gate after the decompositions.
+/// A round doubles the accumulator, assigns the next one, takes the tangent
+/// terms of both, sums the terms four per row and evaluates the divisor at
+/// both random points
+fn eagen(op: &Ops, number_of_terms: usize, number_of_rounds: usize) -> usize {
+    // divisor has at most `number_of_terms + 2` zeros
+    let divisor_size = (number_of_terms + 2) / 2 + 1;
+    // one and the coordinates of the aux generator and the correction point
+    let constants = 5;
+    // random point over three rows and the tangent terms of the points
+    let setup = 3 + number_of_terms;
+    let inner_product = 1 + div_ceil!(number_of_terms + 2, 4);
+    let divisor = 2 * (1 + divisor_size) + 3;
+    let round = op.dbl + op.write + 2 + inner_product + divisor;
+    constants * op.constant + setup + number_of_rounds * round + op.add
+}
+
+/// Rows of the fixed base gate after the decompositions along with the
+/// lookup rows. Points are constrained to the bases loaded as constants
+fn fixed(
+    op: &Ops,
+    number_of_terms: usize,
+    window: usize,
+    number_of_rounds: usize,
+) -> (usize, usize) {
+    let bases = 2 * number_of_terms * op.constant;
+    let reads = op.read + (number_of_terms * number_of_rounds - 1) * op.read_add;
+    let doublings = (number_of_rounds - 1) * window * op.dbl;
+    let correction = 2 * op.constant + op.add;
+    let rows = bases + reads + doublings + correction;
+    (rows, 2 * number_of_terms * number_of_rounds)
+}
@@ -0,0 +1,463 @@
+use crate::{
+    error::MsmError,
+    memory::{self, context::MemoryGate},
+    msm_fix::config::FixMSMGate,
+    msm_var::{bucket, eagen, sliding},
+    scalar::AssignedScalar,
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::{Column, Error, Instance},
+};
+
+pub mod cost;
+#[cfg(test)]
+mod tests;
+
+/// Common interface of the fixed base, bucket and sliding window gates so
+/// that the strategy can be picked at configure time without touching
+/// synthesis code. Regions with msm calls are assigned through a
+/// `MemoryContext` of the gate
+pub trait Msm<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MemoryGate<F> {
+    fn window(&self) -> usize;
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Computes `sum a_i * P_i`. The fixed base gate only accepts its
+    /// configured bases and constrains the points to be equal to them
+    fn msm(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error>;
+    /// Computes `sum a_i * P_i` where scalars are given as little endian
+    /// limbs of `limb_bits` bits each. `limb_bits` must be a multiple of the
+    /// window and every scalar must have the same number of limbs, otherwise
+    /// `MsmError::InvalidLimbs` is returned
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error>;
+    /// Computes `sum a_i * P_i` with scalars reduced by `ScalarChip` which
+    /// must be configured with the same window. Scalars that are not reduced
+    /// are rejected with `MsmError::UnreducedScalar`
+    fn msm_scalars(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedScalar<F, App::Scalar>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        if !scalars.iter().all(AssignedScalar::is_reduced) {
+            return Err(MsmError::UnreducedScalar.into());
+        }
+        let limb_bits = scalars
+            .first()
+            .map_or(self.window(), AssignedScalar::limb_bits);
+        let limbs = scalars
+            .iter()
+            .map(|scalar| scalar.limbs().to_vec())
+            .collect::<Vec<_>>();
+        self.msm_limbs(ctx, points, &limbs, limb_bits)
+    }
+    fn equal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<(), Error> {
+        ctx.equal(a.x.cell(), b.x.cell())?;
+        ctx.equal(a.y.cell(), b.y.cell())
+    }
+    fn instance_column(&self) -> Column<Instance>;
+    /// Constrains coordinates of the point to instance rows `row` and
+    /// `row + 1`
+    fn expose_point(
+        &self,
+        ly: &mut impl Layouter<F>,
+        point: &AssignedPoint<App>,
+        row: usize,
+    ) -> Result<(), Error> {
+        let instance = self.instance_column();
+        ly.constrain_instance(point.x.cell(), instance, row)?;
+        ly.constrain_instance(point.y.cell(), instance, row + 1)
+    }
+    /// Constrains the native representation of the scalar to instance row
+    /// `row`
+    fn expose_scalar(
+        &self,
+        ly: &mut impl Layouter<F>,
+        scalar: &AssignedValue<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        ly.constrain_instance(scalar.cell(), self.instance_column(), row)
+    }
+    fn assign_points(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[Value<App>],
+    ) -> Result<Vec<AssignedPoint<App>>, Error> {
+        points
+            .iter()
+            .map(|point| self.assign_point(ctx, point))
+            .collect()
+    }
+    fn assign_scalars(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalars: &[Value<App::Scalar>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        scalars
+            .iter()
+            .map(|scalar| self.assign_scalar(ctx, scalar))
+            .collect()
+    }
+    /// Assigns the terms and computes their sum
+    fn msm_values(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[Value<App>],
+        scalars: &[Value<App::Scalar>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        let points = self.assign_points(ctx, points)?;
+        let scalars = self.assign_scalars(ctx, scalars)?;
+        self.msm(ctx, &points, &scalars)
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Msm<F, App> for FixMSMGate<F, App> {
+    fn window(&self) -> usize {
+        self.window
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        FixMSMGate::assign_point(self, ctx, point)
+    }
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        FixMSMGate::assign_scalar(self, ctx, scalar)
+    }
+    fn msm(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        self.constrain_bases(ctx, points)?;
+        FixMSMGate::msm(self, ctx, scalars)
+    }
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        self.constrain_bases(ctx, points)?;
+        FixMSMGate::msm_limbs(self, ctx, limbs, limb_bits)
+    }
+    fn instance_column(&self) -> Column<Instance> {
+        self.instance
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
+    /// Constrains the points to be equal to the configured bases
+    fn constrain_bases(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+    ) -> Result<(), Error> {
+        let bases = self.bases()?;
+        if points.len() != bases.len() {
+            return Err(MsmError::TableMismatch {
+                expected: bases.len(),
+                actual: points.len(),
+            }
+            .into());
+        }
+        for (point, base) in points.iter().zip(bases.iter()) {
+            let base = self.get_constant_point(ctx, base)?;
+            FixMSMGate::equal(self, ctx, point, &base)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_bucket_msm {
+    ($gate:ty) => {
+        impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Msm<F, App> for $gate {
+            fn window(&self) -> usize {
+                bucket::instructions::MSMHelper::window(self)
+            }
+            fn assign_point(
+                &self,
+                ctx: &mut RegionCtx<'_, F>,
+                point: &Value<App>,
+            ) -> Result<AssignedPoint<App>, Error> {
+                bucket::instructions::MSMGate::assign_point(self, ctx, point)
+            }
+            fn assign_scalar(
+                &self,
+                ctx: &mut RegionCtx<'_, F>,
+                scalar: &Value<App::Scalar>,
+            ) -> Result<AssignedValue<F>, Error> {
+                bucket::instructions::MSMGate::assign_scalar(self, ctx, scalar)
+            }
+            fn msm(
+                &mut self,
+                ctx: &mut RegionCtx<'_, F>,
+                points: &[AssignedPoint<App>],
+                scalars: &[AssignedValue<F>],
+            ) -> Result<AssignedPoint<App>, Error> {
+                bucket::instructions::MSMGate::msm(self, ctx, points, scalars)
+            }
+            fn msm_limbs(
+                &mut self,
+                ctx: &mut RegionCtx<'_, F>,
+                points: &[AssignedPoint<App>],
+                limbs: &[Vec<AssignedValue<F>>],
+                limb_bits: usize,
+            ) -> Result<AssignedPoint<App>, Error> {
+                bucket::instructions::MSMGate::msm_limbs(self, ctx, points, limbs, limb_bits)
+            }
+            fn instance_column(&self) -> Column<Instance> {
+                bucket::instructions::MSMGate::instance_column(self)
+            }
+        }
+    };
+}
+
+macro_rules! impl_sliding_msm {
+    ($gate:ty) => {
+        impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Msm<F, App> for $gate {
+            fn window(&self) -> usize {
+                sliding::instructions::MSMHelper::window(self)
+            }
+            fn assign_point(
+                &self,
+                ctx: &mut RegionCtx<'_, F>,
+                point: &Value<App>,
+            ) -> Result<AssignedPoint<App>, Error> {
+                sliding::instructions::MSMGate::assign_point(self, ctx, point)
+            }
+            fn assign_scalar(
+                &self,
+                ctx: &mut RegionCtx<'_, F>,
+                scalar: &Value<App::Scalar>,
+            ) -> Result<AssignedValue<F>, Error> {
+                sliding::instructions::MSMGate::assign_scalar(self, ctx, scalar)
+            }
+            fn msm(
+                &mut self,
+                ctx: &mut RegionCtx<'_, F>,
+                points: &[AssignedPoint<App>],
+                scalars: &[AssignedValue<F>],
+            ) -> Result<AssignedPoint<App>, Error> {
+                sliding::instructions::MSMGate::msm(self, ctx, points, scalars)
+            }
+            fn msm_limbs(
+                &mut self,
+                ctx: &mut RegionCtx<'_, F>,
+                points: &[AssignedPoint<App>],
+                limbs: &[Vec<AssignedValue<F>>],
+                limb_bits: usize,
+            ) -> Result<AssignedPoint<App>, Error> {
+                sliding::instructions::MSMGate::msm_limbs(self, ctx, points, limbs, limb_bits)
+            }
+            fn instance_column(&self) -> Column<Instance> {
+                sliding::instructions::MSMGate::instance_column(self)
+            }
+        }
+    };
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Msm<F, App>
+    for eagen::config::EagenMSMGate<F, App>
+{
+    /// Rounds of the divisor check are binary
+    fn window(&self) -> usize {
+        1
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        eagen::instructions::MSMGate::assign_point(self, ctx, point)
+    }
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        eagen::instructions::MSMGate::assign_scalar(self, ctx, scalar)
+    }
+    fn msm(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        eagen::instructions::MSMGate::msm(self, ctx, points, scalars)
+    }
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        eagen::instructions::MSMGate::msm_limbs(self, ctx, points, limbs, limb_bits)
+    }
+    fn instance_column(&self) -> Column<Instance> {
+        eagen::instructions::MSMGate::instance_column(self)
+    }
+}
+
+impl_bucket_msm!(bucket::generic::config::VarMSMGateGeneric<F, App>);
+impl_sliding_msm!(sliding::generic::config::VarMSMGateGeneric<F, App>);
+
+/// Gate picked at configure time
+#[derive(Clone, Debug)]
+pub enum MsmChip<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    Fixed(FixMSMGate<F, App>),
+    /// Bucket gate of any width including the narrow and wide ones
+    Bucket(bucket::generic::config::VarMSMGateGeneric<F, App>),
+    /// Sliding window gate of any width including the narrow and wide ones
+    Sliding(sliding::generic::config::VarMSMGateGeneric<F, App>),
+    Eagen(eagen::config::EagenMSMGate<F, App>),
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MsmChip<F, App> {
+    /// Loads the challenge the Eagen gate derives its random point from. It
+    /// must be called before the msm calls in each synthesis pass and does
+    /// nothing for the other gates
+    pub fn load_challenge(&mut self, ly: &impl Layouter<F>) {
+        if let MsmChip::Eagen(gate) = self {
+            gate.load_challenge(ly);
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($chip:expr, $gate:ident => $e:expr) => {
+        match $chip {
+            MsmChip::Fixed($gate) => $e,
+            MsmChip::Bucket($gate) => $e,
+            MsmChip::Sliding($gate) => $e,
+            MsmChip::Eagen($gate) => $e,
+        }
+    };
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Msm<F, App> for MsmChip<F, App> {
+    fn window(&self) -> usize {
+        dispatch!(self, gate => Msm::window(gate))
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        dispatch!(self, gate => Msm::assign_point(gate, ctx, point))
+    }
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        dispatch!(self, gate => Msm::assign_scalar(gate, ctx, scalar))
+    }
+    fn msm(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        dispatch!(self, gate => Msm::msm(gate, ctx, points, scalars))
+    }
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        dispatch!(self, gate => Msm::msm_limbs(gate, ctx, points, limbs, limb_bits))
+    }
+    fn instance_column(&self) -> Column<Instance> {
+        dispatch!(self, gate => Msm::instance_column(gate))
+    }
+}
+
+/// Memory of the gate picked at configure time
+#[derive(Clone, Debug)]
+pub enum MsmMemory<F: PrimeField + Ord> {
+    Fixed,
+    Bucket(memory::Memory<F>),
+    Sliding(sliding::rw::Memory<F>),
+    Eagen,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MemoryGate<F> for MsmChip<F, App> {
+    type Memory = MsmMemory<F>;
+    fn new_memory(&self) -> MsmMemory<F> {
+        match self {
+            MsmChip::Fixed(_) => MsmMemory::Fixed,
+            MsmChip::Bucket(gate) => MsmMemory::Bucket(gate.new_memory()),
+            MsmChip::Sliding(gate) => MsmMemory::Sliding(gate.new_memory()),
+            MsmChip::Eagen(_) => MsmMemory::Eagen,
+        }
+    }
+    fn replace_memory(&mut self, memory: MsmMemory<F>) -> Result<MsmMemory<F>, Error> {
+        Ok(match (self, memory) {
+            (MsmChip::Fixed(_), MsmMemory::Fixed) => MsmMemory::Fixed,
+            (MsmChip::Bucket(gate), MsmMemory::Bucket(memory)) => {
+                MsmMemory::Bucket(gate.replace_memory(memory)?)
+            }
+            (MsmChip::Sliding(gate), MsmMemory::Sliding(memory)) => {
+                MsmMemory::Sliding(gate.replace_memory(memory)?)
+            }
+            (MsmChip::Eagen(_), MsmMemory::Eagen) => MsmMemory::Eagen,
+            _ => return Err(MsmError::MemoryMismatch.into()),
+        })
+    }
+    fn layout_memory(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        dispatch!(self, gate => MemoryGate::layout_memory(gate, ly))
+    }
+}
+
+macro_rules! impl_from_gate {
+    ($variant:ident, $gate:ty) => {
+        impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> From<$gate> for MsmChip<F, App> {
+            fn from(gate: $gate) -> Self {
+                MsmChip::$variant(gate)
+            }
+        }
+    };
+}
+
+impl_from_gate!(Fixed, FixMSMGate<F, App>);
+impl_from_gate!(Bucket, bucket::generic::config::VarMSMGateGeneric<F, App>);
+impl_from_gate!(Sliding, sliding::generic::config::VarMSMGateGeneric<F, App>);
+impl_from_gate!(Eagen, eagen::config::EagenMSMGate<F, App>);
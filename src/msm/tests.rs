@@ -0,0 +1,668 @@
+use super::cost::{self, estimate, plan, scalar_bits, MAX_WINDOW};
+use super::{Msm, MsmChip, MsmMemory};
+use crate::error::{check_terms, MsmError};
+use crate::memory::context::{MemoryContext, MemoryGate};
+use crate::msm_fix::tests::{configure_fixed_gate, fixed_bases};
+use crate::msm_var::{bucket, eagen, sliding};
+use crate::range::RangeTable;
+use crate::util::{multiexp_naive_var, scalar_to_native};
+use crate::{derive_aux_generator, point_coords, RegionCtx, DEFAULT_AUX_GENERATOR_TAG};
+use ff::Field;
+use ff::PrimeField;
+use group::Curve;
+use group::Group;
+use halo2::dev::MockProver;
+use halo2::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::CurveAffine,
+    plonk::Error,
+    plonk::{Circuit, ConstraintSystem, SecondPhase},
+};
+use rand_core::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+#[derive(Default, Clone, Copy, Debug)]
+enum Strategy {
+    #[default]
+    Fixed,
+    BucketNarrow,
+    BucketWide,
+    BucketWidePacked,
+    BucketGeneric,
+    SlidingNarrow,
+    SlidingWide,
+    Eagen,
+}
+
+#[derive(Default, Clone, Debug)]
+struct Params {
+    window: usize,
+    number_of_points: usize,
+    strategy: Strategy,
+}
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    chip: MsmChip<F, App>,
+}
+#[derive(Debug, Default)]
+struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    _marker: PhantomData<(F, App)>,
+    window: usize,
+    number_of_points: usize,
+    strategy: Strategy,
+    /// Scalars are given as 64 bit limbs if set
+    limbs: bool,
+    /// Regions that each compute the msm with one memory context
+    regions: usize,
+    /// Rows of the msm, memory queries it made and its range lookups
+    msm_cost: Cell<(usize, usize, usize)>,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+    type Config = TestConfig<F, App>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = Params;
+    fn without_witnesses(&self) -> Self {
+        Self {
+            _marker: PhantomData,
+            window: self.window,
+            number_of_points: self.number_of_points,
+            strategy: self.strategy,
+            limbs: self.limbs,
+            regions: self.regions,
+            msm_cost: Cell::default(),
+        }
+    }
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        let a = [(); 9].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
+        let window = params.window;
+        let chip: MsmChip<F, App> = match params.strategy {
+            Strategy::Fixed => configure_fixed_gate(
+                meta,
+                [a[0], a[1], a[2], a[3], a[4]],
+                range_table,
+                constant,
+                instance,
+                window,
+                &fixed_bases::<App>(params.number_of_points),
+                App::CurveExt::generator().to_affine(),
+            )
+            .into(),
+            Strategy::BucketNarrow => bucket::narrow::config::VarMSMGateNarrow::configure_default(
+                meta,
+                &a[..5],
+                range_table,
+                constant,
+                instance,
+                window,
+            )
+            .into(),
+            Strategy::BucketWide => bucket::wide::config::VarMSMGateWide::configure_default(
+                meta,
+                &a,
+                range_table,
+                constant,
+                instance,
+                window,
+            )
+            .into(),
+            Strategy::BucketWidePacked => bucket::wide::config::VarMSMGateWide::configure_packed(
+                meta,
+                &a,
+                range_table,
+                constant,
+                instance,
+                window,
+                derive_aux_generator(DEFAULT_AUX_GENERATOR_TAG),
+            )
+            .into(),
+            Strategy::BucketGeneric => {
+                bucket::generic::config::VarMSMGateGeneric::configure_default(
+                    meta,
+                    &a[..7],
+                    range_table,
+                    constant,
+                    instance,
+                    window,
+                )
+                .into()
+            }
+            Strategy::SlidingNarrow => {
+                sliding::narrow::config::VarMSMGateNarrow::configure_default(
+                    meta,
+                    &a[..5],
+                    range_table,
+                    constant,
+                    instance,
+                    window,
+                )
+                .into()
+            }
+            Strategy::SlidingWide => sliding::wide::config::VarMSMGateWide::configure_default(
+                meta,
+                &a,
+                range_table,
+                constant,
+                instance,
+                window,
+            )
+            .into(),
+            Strategy::Eagen => {
+                let c = [(); 5].map(|_| meta.advice_column_in(SecondPhase));
+                eagen::config::EagenMSMGate::configure_default(
+                    meta,
+                    [a[0], a[1], a[2], a[3], a[4]],
+                    c,
+                    constant,
+                    instance,
+                )
+                .into()
+            }
+        };
+        Self::Config { chip }
+    }
+    fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!();
+    }
+    fn synthesize(&self, mut cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
+        macro_rules! v {
+            ($e:expr) => {
+                Value::known($e)
+            };
+        }
+        let ly = &mut ly;
+        let number_of_points = self.number_of_points;
+        // synthesis below only goes through the common interface
+        cfg.chip.load_challenge(ly);
+        let mut memory = MemoryContext::new(&cfg.chip);
+        let mut results = vec![];
+        for _ in 0..self.regions {
+            let res = memory.assign_region(
+                ly,
+                &mut cfg.chip,
+                || "app",
+                |ctx, chip| {
+                    let (points, scalars) = terms::<App>(number_of_points);
+                    let points = points.iter().map(|point| v!(*point)).collect::<Vec<_>>();
+                    let scalars = scalars
+                        .into_iter()
+                        .map(|scalar| v!(scalar))
+                        .collect::<Vec<_>>();
+                    let points = chip.assign_points(ctx, &points[..])?;
+                    if self.limbs {
+                        let limbs = scalars
+                            .iter()
+                            .map(|scalar| {
+                                let limbs = scalar
+                                    .map(|scalar| {
+                                        scalar
+                                            .to_repr()
+                                            .as_ref()
+                                            .chunks(8)
+                                            .map(|chunk| {
+                                                let limb =
+                                                    u64::from_le_bytes(chunk.try_into().unwrap());
+                                                App::Scalar::from(limb)
+                                            })
+                                            .collect::<Vec<_>>()
+                                    })
+                                    .transpose_vec(4);
+                                chip.assign_scalars(ctx, &limbs[..])
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        let res = chip.msm_limbs(ctx, &points[..], &limbs[..], 64)?;
+                        return Ok((res, vec![]));
+                    }
+                    let scalars = chip.assign_scalars(ctx, &scalars[..])?;
+                    let offset = ctx.offset();
+                    let lookups = range_lookups(chip, ctx);
+                    let res = chip.msm(ctx, &points[..], &scalars[..])?;
+                    let queries = match chip {
+                        MsmChip::Bucket(gate) => gate.memory.timestamp(),
+                        _ => 0,
+                    };
+                    let lookups = range_lookups(chip, ctx) - lookups;
+                    self.msm_cost.set((ctx.offset() - offset, queries, lookups));
+                    Ok((res, scalars))
+                },
+            )?;
+            results.push(res);
+        }
+        // every region computes the same msm
+        for (res, scalars) in results.iter() {
+            cfg.chip.expose_point(ly, res, 0)?;
+            for (i, scalar) in scalars.iter().enumerate() {
+                cfg.chip.expose_scalar(ly, scalar, 2 + i)?;
+            }
+        }
+        memory.layout(ly, &mut cfg.chip)
+    }
+    fn params(&self) -> Self::Params {
+        Params {
+            window: self.window,
+            number_of_points: self.number_of_points,
+            strategy: self.strategy,
+        }
+    }
+}
+
+/// Rows of the region so far that look up the range table
+fn range_lookups<F: PrimeField + Ord, App: CurveAffine<Base = F>>(
+    chip: &MsmChip<F, App>,
+    ctx: &RegionCtx<'_, F>,
+) -> usize {
+    match chip {
+        MsmChip::Fixed(gate) => ctx.enabled(gate.s_range),
+        MsmChip::Bucket(gate) => ctx.enabled(gate.s_range),
+        MsmChip::Sliding(gate) => ctx.enabled(gate.s_range),
+        // bits are boolean so the gate has no range table
+        MsmChip::Eagen(_) => 0,
+    }
+}
+
+/// Deterministic terms so that public inputs can be computed outside of the
+/// circuit. Points are the fixed bases so that the fixed base gate accepts them
+fn terms<App: CurveAffine>(number_of_points: usize) -> (Vec<App>, Vec<App::Scalar>) {
+    let mut rng = XorShiftRng::from_seed([
+        0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc, 0xe5, 0x59, 0x62, 0xbe, 0x5d,
+        0x76,
+    ]);
+    let points = fixed_bases::<App>(number_of_points);
+    let scalars = (0..number_of_points)
+        .map(|_| App::Scalar::random(&mut rng))
+        .collect();
+    (points, scalars)
+}
+
+fn run_strategy(strategy: Strategy, regions: usize) {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let number_of_points = 10;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window: 4,
+        number_of_points,
+        strategy,
+        limbs: false,
+        regions,
+        msm_cost: Cell::default(),
+    };
+    let (points, scalars) = terms::<EqAffine>(number_of_points);
+    let points = points
+        .iter()
+        .map(|point| point.to_curve())
+        .collect::<Vec<_>>();
+    let res = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+    let (x, y) = point_coords(res);
+    let public_inputs = vec![[x, y]
+        .into_iter()
+        .chain(
+            scalars
+                .into_iter()
+                .map(|scalar| scalar_to_native::<Fq, _>(scalar).unwrap()),
+        )
+        .collect::<Vec<_>>()];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_msm_chip_fixed() {
+    run_strategy(Strategy::Fixed, 1);
+}
+
+#[test]
+fn test_msm_chip_bucket_narrow() {
+    run_strategy(Strategy::BucketNarrow, 1);
+}
+
+#[test]
+fn test_msm_chip_bucket_wide() {
+    run_strategy(Strategy::BucketWide, 1);
+}
+
+#[test]
+fn test_msm_chip_bucket_wide_packed() {
+    run_strategy(Strategy::BucketWidePacked, 1);
+}
+
+#[test]
+fn test_msm_chip_bucket_generic() {
+    run_strategy(Strategy::BucketGeneric, 1);
+}
+
+#[test]
+fn test_msm_chip_sliding_narrow() {
+    run_strategy(Strategy::SlidingNarrow, 1);
+}
+
+#[test]
+fn test_msm_chip_sliding_wide() {
+    run_strategy(Strategy::SlidingWide, 1);
+}
+
+#[test]
+fn test_msm_chip_eagen() {
+    run_strategy(Strategy::Eagen, 1);
+}
+
+#[test]
+fn test_msm_chip_regions() {
+    for strategy in [
+        Strategy::Fixed,
+        Strategy::BucketNarrow,
+        Strategy::BucketWide,
+        Strategy::BucketWidePacked,
+        Strategy::BucketGeneric,
+        Strategy::SlidingNarrow,
+        Strategy::SlidingWide,
+        Strategy::Eagen,
+    ] {
+        run_strategy(strategy, 2);
+    }
+}
+
+#[test]
+fn test_msm_chip_empty() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    for strategy in [
+        Strategy::Fixed,
+        Strategy::BucketNarrow,
+        Strategy::BucketWide,
+        Strategy::BucketWidePacked,
+        Strategy::BucketGeneric,
+        Strategy::SlidingNarrow,
+        Strategy::SlidingWide,
+        Strategy::Eagen,
+    ] {
+        let circuit = MyCircuit::<Fq, EqAffine> {
+            _marker: PhantomData::<(Fq, EqAffine)>,
+            window: 4,
+            number_of_points: 0,
+            strategy,
+            limbs: false,
+            regions: 1,
+            msm_cost: Cell::default(),
+        };
+        let e = match MockProver::run(K, &circuit, vec![vec![]]) {
+            Ok(_) => panic!("empty msm must fail"),
+            Err(e) => e,
+        };
+        assert!(matches!(e, Error::Synthesis));
+    }
+    assert_eq!(check_terms(0, 0), Err(MsmError::EmptyMsm));
+}
+
+#[test]
+fn test_scalar_to_native_out_of_range() {
+    use halo2::halo2curves::pasta::{Fp, Fq};
+    // Fq is larger than Fp so its largest element doesn't fit
+    assert_eq!(
+        scalar_to_native::<Fp, Fq>(-Fq::ONE),
+        Err(MsmError::ScalarOutOfRange)
+    );
+    assert_eq!(scalar_to_native::<Fp, Fq>(Fq::ONE), Ok(Fp::ONE));
+}
+
+#[test]
+fn test_msm_chip_memory_mismatch() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    let mut meta = ConstraintSystem::<Fq>::default();
+    let a = [(); 5].map(|_| meta.advice_column());
+    let range_table = RangeTable::configure(&mut meta);
+    let constant = meta.fixed_column();
+    let instance = meta.instance_column();
+    let mut chip: MsmChip<Fq, EqAffine> =
+        sliding::narrow::config::VarMSMGateNarrow::configure_default(
+            &mut meta,
+            &a,
+            range_table,
+            constant,
+            instance,
+            4,
+        )
+        .into();
+    assert!(chip.replace_memory(MsmMemory::Fixed).is_err());
+    let memory = chip.new_memory();
+    assert!(chip.replace_memory(memory).is_ok());
+
+    // memory of another gate of the same kind
+    let [mut chip, other]: [MsmChip<Fq, EqAffine>; 2] = [(); 2].map(|_| {
+        let a = [(); 5].map(|_| meta.advice_column());
+        bucket::narrow::config::VarMSMGateNarrow::configure_default(
+            &mut meta,
+            &a,
+            range_table,
+            constant,
+            instance,
+            4,
+        )
+        .into()
+    });
+    assert!(chip.replace_memory(other.new_memory()).is_err());
+    let memory = chip.new_memory();
+    assert!(chip.replace_memory(memory).is_ok());
+}
+
+#[test]
+fn test_msm_chip_cost() {
+    use halo2::halo2curves::pasta::{EqAffine, Fp, Fq};
+    let number_of_points = 3;
+    let window = 3;
+    for (strategy, cost_strategy) in [
+        (Strategy::Fixed, cost::Strategy::Fixed),
+        (Strategy::BucketNarrow, cost::Strategy::BucketNarrow),
+        (Strategy::BucketWide, cost::Strategy::BucketWide),
+        (Strategy::BucketWidePacked, cost::Strategy::BucketWidePacked),
+        (Strategy::BucketGeneric, cost::Strategy::BucketGeneric(7)),
+        (Strategy::SlidingNarrow, cost::Strategy::SlidingNarrow),
+        (Strategy::SlidingWide, cost::Strategy::SlidingWide),
+        (Strategy::Eagen, cost::Strategy::Eagen),
+    ] {
+        let circuit = MyCircuit::<Fq, EqAffine> {
+            _marker: PhantomData::<(Fq, EqAffine)>,
+            window,
+            number_of_points,
+            strategy,
+            limbs: false,
+            regions: 1,
+            msm_cost: Cell::default(),
+        };
+        let (points, scalars) = terms::<EqAffine>(number_of_points);
+        let points = points
+            .iter()
+            .map(|point| point.to_curve())
+            .collect::<Vec<_>>();
+        let res = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+        let (x, y) = point_coords(res);
+        let public_inputs = vec![[x, y]
+            .into_iter()
+            .chain(
+                scalars
+                    .into_iter()
+                    .map(|scalar| scalar_to_native::<Fq, _>(scalar).unwrap()),
+            )
+            .collect::<Vec<_>>()];
+        let cost = estimate(
+            cost_strategy,
+            number_of_points,
+            window,
+            scalar_bits::<Fq, Fp>(),
+        );
+        // the estimated `k` is the smallest one synthesis fits in
+        let prover = match MockProver::run(cost.k, &circuit, public_inputs.clone()) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{strategy:?}: {e:#?}"),
+        };
+        prover.assert_satisfied();
+        assert!(
+            MockProver::run(cost.k - 1, &circuit, public_inputs).is_err(),
+            "{strategy:?}"
+        );
+        let (rows, queries, lookups) = circuit.msm_cost.get();
+        assert_eq!(cost.advice_rows, rows, "{strategy:?}");
+        let sorted_rw_rows = match strategy {
+            // sorted queries span two rows
+            Strategy::BucketNarrow => 2 * queries,
+            // two sorted queries per row
+            Strategy::BucketWidePacked => div_ceil!(queries, 2),
+            _ => queries,
+        };
+        assert_eq!(cost.sorted_rw_rows, sorted_rw_rows, "{strategy:?}");
+        // sorted queries but the first look up their timestamp difference
+        let lookup_rows = lookups + queries.saturating_sub(1);
+        assert_eq!(cost.lookup_rows, lookup_rows, "{strategy:?}");
+        // windows along with a timestamp difference per query
+        let range_table_rows = match strategy {
+            Strategy::Eagen => 0,
+            _ => (1 << window) + queries,
+        };
+        assert_eq!(cost.range_table_rows, range_table_rows, "{strategy:?}");
+    }
+}
+
+#[test]
+fn test_plan_window() {
+    use halo2::halo2curves::pasta::{EqAffine, Fp, Fq};
+    const K: u32 = 17;
+    let number_of_terms = 100;
+    let bits = scalar_bits::<Fq, Fp>();
+    for strategy in [
+        cost::Strategy::Fixed,
+        cost::Strategy::BucketNarrow,
+        cost::Strategy::BucketWide,
+        cost::Strategy::BucketWidePacked,
+        cost::Strategy::BucketGeneric(7),
+        cost::Strategy::SlidingNarrow,
+        cost::Strategy::SlidingWide,
+        cost::Strategy::Eagen,
+    ] {
+        let (window, best) = plan(strategy, number_of_terms, bits, K, false).unwrap();
+        assert!(best.k <= K);
+        for other in 1..=MAX_WINDOW {
+            let cost = estimate(strategy, number_of_terms, other, bits);
+            if cost.k <= K {
+                assert!(best.rows() <= cost.rows(), "{strategy:?} {window} {other}");
+            }
+        }
+    }
+    // nothing fits
+    assert!(plan(cost::Strategy::BucketNarrow, 10000, bits, 10, false).is_none());
+    // signed digits skip window 1, which is all the eagen gate has
+    for strategy in [cost::Strategy::BucketNarrow, cost::Strategy::SlidingWide] {
+        let (window, _) = plan(strategy, number_of_terms, bits, K, true).unwrap();
+        assert!(window > 1, "{strategy:?}");
+    }
+    assert!(plan(cost::Strategy::Eagen, number_of_terms, bits, K, true).is_none());
+
+    let mut meta = ConstraintSystem::<Fq>::default();
+    let a = [(); 5].map(|_| meta.advice_column());
+    let range_table = RangeTable::configure(&mut meta);
+    let constant = meta.fixed_column();
+    let instance = meta.instance_column();
+    let gate = bucket::narrow::config::VarMSMGateNarrow::<Fq, EqAffine>::configure_auto(
+        &mut meta,
+        &a,
+        range_table,
+        constant,
+        instance,
+        number_of_terms,
+        K,
+        false,
+    )
+    .unwrap();
+    let (window, _) = plan(
+        cost::Strategy::BucketNarrow,
+        number_of_terms,
+        bits,
+        K,
+        false,
+    )
+    .unwrap();
+    assert_eq!(Msm::window(&gate), window);
+    let gate = sliding::narrow::config::VarMSMGateNarrow::<Fq, EqAffine>::configure_auto(
+        &mut meta,
+        &a,
+        range_table,
+        constant,
+        instance,
+        number_of_terms,
+        K,
+        true,
+    )
+    .unwrap();
+    let (window, _) = plan(
+        cost::Strategy::SlidingNarrow,
+        number_of_terms,
+        bits,
+        K,
+        true,
+    )
+    .unwrap();
+    assert_eq!(Msm::window(&gate), window);
+    let err = bucket::narrow::config::VarMSMGateNarrow::<Fq, EqAffine>::configure_auto(
+        &mut meta,
+        &a,
+        range_table,
+        constant,
+        instance,
+        10000,
+        10,
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        MsmError::NoWindow {
+            number_of_terms: 10000,
+            k: 10
+        }
+    );
+}
+
+#[test]
+fn test_msm_chip_limbs() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let number_of_points = 4;
+    for strategy in [
+        Strategy::Fixed,
+        Strategy::BucketNarrow,
+        Strategy::BucketWide,
+        Strategy::BucketGeneric,
+        Strategy::SlidingNarrow,
+        Strategy::SlidingWide,
+    ] {
+        let circuit = MyCircuit::<Fq, EqAffine> {
+            _marker: PhantomData::<(Fq, EqAffine)>,
+            window: 4,
+            number_of_points,
+            strategy,
+            limbs: true,
+            regions: 1,
+            msm_cost: Cell::default(),
+        };
+        let (points, scalars) = terms::<EqAffine>(number_of_points);
+        let points = points
+            .iter()
+            .map(|point| point.to_curve())
+            .collect::<Vec<_>>();
+        let res = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+        let (x, y) = point_coords(res);
+        let prover = match MockProver::run(K, &circuit, vec![vec![x, y]]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:#?}", e),
+        };
+        prover.assert_satisfied();
+    }
+}
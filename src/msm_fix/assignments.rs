@@ -1,11 +1,22 @@
 use super::config::FixMSMGate;
-use crate::{util::decompose, AssignedPoint, AssignedValue, RegionCtx};
-use ff::PrimeField;
+use crate::{
+    complete::complete_add,
+    error::{check_limbs, invert, transpose, MsmError},
+    memory::context::MemoryGate,
+    point_coords, point_from_coords,
+    range::Tag,
+    util::{big_to_fe, number_of_windows, scalar_max, scalar_to_native, short_scalar_max},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::{Field, PrimeField};
+use group::Curve;
 use halo2::{
     circuit::{Layouter, Value},
     halo2curves::CurveAffine,
     plonk::{Advice, Column, Error, Fixed},
 };
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
 
 impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
     pub fn get_constant_point(
@@ -13,71 +24,263 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         ctx: &mut RegionCtx<'_, F>,
         point: &App,
     ) -> Result<AssignedPoint<App>, Error> {
-        let coordianates = point.coordinates().unwrap();
-        let x = coordianates.x().clone();
-        let y = coordianates.y().clone();
+        let (x, y) = point_coords(*point);
         let x = self.get_constant(ctx, x)?;
         let y = self.get_constant(ctx, y)?;
         Ok(AssignedPoint::new(x, y))
     }
 
+    /// Weight `sum(2^(w * i))` of the aux offsets accumulated over
+    /// `number_of_rounds`
+    fn round_weight(&self, number_of_rounds: usize) -> App::Scalar {
+        let weight = (0..number_of_rounds).fold(BigUint::zero(), |acc, i| {
+            acc | (BigUint::one() << (i * self.window))
+        });
+        big_to_fe::<App::Scalar>(weight)
+    }
+    /// The configured correction covers all rounds as
+    /// `sum(2^(w * i)) * sum(aux_j)`, so for fewer rounds it is rescaled by
+    /// the ratio of the round weights. Returns the negation to be added to
+    /// the accumulator
+    pub fn correction(&self, number_of_rounds: usize) -> Result<App, MsmError> {
+        let correction = if number_of_rounds == self.number_of_rounds() {
+            self.correction
+        } else {
+            let full: App::Scalar = Option::from(
+                self.round_weight(self.number_of_rounds()).invert(),
+            )
+            .ok_or(MsmError::UnsupportedMode {
+                operation: "rescaling the correction of this window",
+            })?;
+            (self.correction * (self.round_weight(number_of_rounds) * full)).to_affine()
+        };
+        Ok(-correction)
+    }
     pub fn correction_point(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
         _number_of_points: usize,
+        number_of_rounds: usize,
     ) -> Result<AssignedPoint<App>, Error> {
-        self.get_constant_point(ctx, &(-self.correction))
+        let correction = self.correction(number_of_rounds)?;
+        self.get_constant_point(ctx, &correction)
     }
 
-    pub fn decompose_scalars(&self, scalars: &[Value<App::Scalar>]) -> Vec<Vec<Value<F>>> {
-        let number_of_rounds = div_ceil!(F::NUM_BITS as usize, self.window);
-        let scalars = scalars
-            .iter()
-            .map(|scalar| {
-                let decomposed = scalar.map(|scalar| {
-                    let mut decomposed: Vec<F> = decompose(scalar, number_of_rounds, self.window);
-                    decomposed.reverse();
-                    decomposed
-                });
-                decomposed.transpose_vec(number_of_rounds)
+    /// Recovers the bases from the first two entries `aux_i` and
+    /// `aux_i + G_i` of their tables
+    pub fn bases(&self) -> Result<Vec<App>, MsmError> {
+        let table_size = 1 << self.window;
+        let entry = |address: usize| {
+            let (x, y) = self
+                .memory
+                .get(&F::from(address as u64))
+                .ok_or(MsmError::UnwrittenAddress)?;
+            point_from_coords::<App>(*x, *y)
+        };
+        (0..self.memory.len() / table_size)
+            .map(|point_idx| {
+                let offset = point_idx * table_size;
+                Ok((entry(offset + 1)?.to_curve() - entry(offset)?).to_affine())
             })
-            .collect::<Vec<_>>();
-        scalars
+            .collect()
+    }
+    pub fn number_of_rounds(&self) -> usize {
+        number_of_windows::<F, App::Scalar>(self.window)
+    }
+    /// Number of rounds for scalars of at most `number_of_bits` bits
+    pub fn number_of_short_rounds(&self, number_of_bits: usize) -> usize {
+        div_ceil!(number_of_bits, self.window)
+    }
+
+    pub fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let scalar = transpose(scalar.map(scalar_to_native::<F, App::Scalar>))?;
+        let scalar = ctx.advice(|| "assign scalar", self.a0, scalar)?;
+        ctx.empty(|| "assign scalar:", self.a1.into())?;
+        ctx.empty(|| "assign scalar:", self.a2.into())?;
+        ctx.empty(|| "assign scalar:", self.a3.into())?;
+        ctx.empty(|| "assign scalar:", self.a4.into())?;
+        ctx.empty(|| "assign scalar:", self.constant.into())?;
+        ctx.next();
+        Ok(scalar)
+    }
+    /// Decomposes the scalar into windows most significant first. Recomposition
+    /// and canonicity are constrained here while windows themselves are range
+    /// checked where they are consumed as table addresses
+    pub fn decompose_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let max = scalar_max::<F, App::Scalar>();
+        self.decompose(ctx, scalar, &max, self.number_of_rounds())
+    }
+    /// Decomposes the value into windows most significant first such that it
+    /// is at most `max`
+    pub(crate) fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_windows: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        self.decomposition
+            .assign(ctx, scalar, max, number_of_windows, &zero)
     }
 
     pub fn msm(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        scalars: &[Value<App::Scalar>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        let scalars = scalars
+            .iter()
+            .map(|scalar| self.decompose_scalar(ctx, scalar))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let number_of_rounds = self.number_of_rounds();
+        self.msm_windows(ctx, &scalars, number_of_rounds)
+    }
+    /// MSM of scalars that are declared to fit in `number_of_bits` bits such
+    /// as transcript challenges. Scalars are range constrained to the bound
+    /// and only the rounds that cover it are run
+    pub fn msm_short(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalars: &[AssignedValue<F>],
+        number_of_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let max = short_scalar_max::<F, App::Scalar>(number_of_bits)?;
+        let number_of_rounds = self.number_of_short_rounds(number_of_bits);
+        let scalars = scalars
+            .iter()
+            .map(|scalar| self.decompose(ctx, scalar, &max, number_of_rounds))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(ctx, &scalars, number_of_rounds)
+    }
+    /// MSM of scalars given as little endian limbs of `limb_bits` bits such
+    /// as recomposed range checked integers. Limbs are copied into their own
+    /// window decompositions so `limb_bits` must be a multiple of the window
+    pub fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if limbs.is_empty() {
+            return Err(MsmError::EmptyMsm.into());
+        }
+        let number_of_limbs = check_limbs(limbs, limb_bits, self.window)?;
+        let max = short_scalar_max::<F, App::Scalar>(limb_bits)?;
+        let windows_per_limb = limb_bits / self.window;
+        let scalars = limbs
+            .iter()
+            .map(|limbs| {
+                let mut windows = vec![];
+                for limb in limbs.iter().rev() {
+                    windows.extend(self.decompose(ctx, limb, &max, windows_per_limb)?);
+                }
+                Ok(windows)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(ctx, &scalars, number_of_limbs * windows_per_limb)
+    }
+    /// Single term `k * G_i` of the base at `point_idx`
+    pub fn mul(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point_idx: usize,
+        scalar: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let windows = self.decompose_scalar(ctx, scalar)?;
+        self.mul_windows(ctx, point_idx, &windows)
+    }
+    /// Single term of a scalar that is declared to fit in `number_of_bits`
+    /// bits. Only the rounds that cover the bound are run
+    pub fn mul_short(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point_idx: usize,
+        scalar: &AssignedValue<F>,
+        number_of_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let max = short_scalar_max::<F, App::Scalar>(number_of_bits)?;
+        let number_of_rounds = self.number_of_short_rounds(number_of_bits);
+        let windows = self.decompose(ctx, scalar, &max, number_of_rounds)?;
+        self.mul_windows(ctx, point_idx, &windows)
+    }
+    /// Accumulates table reads of the base at `point_idx` along the windows
+    /// most significant first. Only the table of that base is read so the
+    /// correction is the aux offset of that table
+    pub(crate) fn mul_windows(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point_idx: usize,
+        windows: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        let table_size = 1 << self.window;
+        if (point_idx + 1) * table_size > self.memory.len() {
+            return Err(MsmError::TableMismatch {
+                expected: (point_idx + 1) * table_size,
+                actual: self.memory.len(),
+            }
+            .into());
+        }
+        let mut acc = self.read_point(ctx, point_idx, &windows[0])?;
+        for window in windows.iter().skip(1) {
+            for _ in 0..self.window {
+                acc = self.dbl(ctx, &acc)?;
+            }
+            acc = self.read_add(ctx, point_idx, window, &acc)?;
+        }
+        let (x, y) = self
+            .memory
+            .get(&F::from((point_idx * table_size) as u64))
+            .ok_or(MsmError::UnwrittenAddress)?;
+        let aux = point_from_coords::<App>(*x, *y)?;
+        let correction = (-(aux * self.round_weight(windows.len()))).to_affine();
+        let correction_point = self.get_constant_point(ctx, &correction)?;
+        self.add(ctx, &acc, &correction_point)
+    }
+    /// Accumulates table reads of windows most significant first
+    fn msm_windows(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalars: &[Vec<AssignedValue<F>>],
+        number_of_rounds: usize,
     ) -> Result<AssignedPoint<App>, Error> {
         let number_of_points = scalars.len();
-        assert_eq!(self.memory.len(), scalars.len() * (1 << self.window));
-
-        let scalars = self.decompose_scalars(scalars);
-        let number_of_rounds = div_ceil!(F::NUM_BITS as usize, self.window);
-        let mut acc = None;
+        if number_of_points == 0 {
+            return Err(MsmError::EmptyMsm.into());
+        }
+        let table_size = number_of_points * (1 << self.window);
+        if self.memory.len() != table_size {
+            return Err(MsmError::TableMismatch {
+                expected: table_size,
+                actual: self.memory.len(),
+            }
+            .into());
+        }
+        // the accumulator starts with the first window of the first point
+        let mut acc = self.read_point(ctx, 0, &scalars[0][0])?;
         for round in 0..number_of_rounds {
             if round != 0 {
                 for _ in 0..self.window {
-                    acc = Some(self.dbl(ctx, &acc.unwrap())?)
+                    acc = self.dbl(ctx, &acc)?;
                 }
             }
-            let mut offset = 0;
-            for (point_idx, scalar) in scalars.iter().enumerate() {
-                acc = match &acc {
-                    Some(acc) => Some(self.read_add(ctx, point_idx, &scalar[round], &acc)?),
-                    None => {
-                        assert!(offset == 0 && round == 0);
-                        Some(self.read_point(ctx, point_idx, &scalar[round])?)
-                    }
-                };
-                offset += 1 << self.window;
+            let skip = if round == 0 { 1 } else { 0 };
+            for (point_idx, scalar) in scalars.iter().enumerate().skip(skip) {
+                acc = self.read_add(ctx, point_idx, &scalar[round], &acc)?;
             }
         }
 
-        let correction_point = self.correction_point(ctx, number_of_points)?;
+        let correction_point = self.correction_point(ctx, number_of_points, number_of_rounds)?;
 
-        let res = self.add(ctx, &acc.unwrap(), &correction_point)?;
+        let res = self.add(ctx, &acc, &correction_point)?;
 
         Ok(res)
     }
@@ -92,8 +295,8 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         ctx: &mut RegionCtx<'_, F>,
         scalar: F,
     ) -> Result<AssignedValue<F>, Error> {
-        match self.constants.get(&scalar) {
-            Some(constant) => Ok(constant.clone()),
+        match ctx.constants_mut().get(&scalar) {
+            Some(constant) => Ok(constant),
             _ => {
                 ctx.enable(self.s_assign_constant)?;
                 ctx.fixed(|| "get constant: fix", self.constant, scalar)?;
@@ -104,6 +307,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
                 let constant =
                     ctx.advice(|| "get constant: constant", self.a4, Value::known(scalar))?;
                 ctx.next();
+                ctx.constants_mut().insert(scalar, constant.clone());
                 Ok(constant)
             }
         }
@@ -113,12 +317,14 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         ctx: &mut RegionCtx<'_, F>,
         point: &Value<App>,
     ) -> Result<AssignedPoint<App>, Error> {
-        let (x, y) = point
-            .map(|c| {
-                let coordinates = c.coordinates().unwrap();
-                (coordinates.x().clone(), coordinates.y().clone())
-            })
-            .unzip();
+        let (x, y) = transpose(point.map(|c| {
+            Option::from(c.coordinates())
+                .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+                .ok_or(MsmError::InvalidPoint {
+                    context: "assign point",
+                })
+        }))?
+        .unzip();
         let x_square = x * x;
         let x_cube = x * x * x;
         ctx.enable(self.s_point)?;
@@ -134,17 +340,21 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         &self,
         ctx: &mut RegionCtx<'_, F>,
         point_idx: usize,
-        address: &Value<F>,
+        address: &AssignedValue<F>,
     ) -> Result<AssignedPoint<App>, Error> {
         ctx.enable(self.s_query)?;
         ctx.enable(self.s_range)?;
         let address_base = F::from(((1 << self.window) * point_idx) as u64);
 
-        let (x, y) = address
-            .map(|address| self.memory.get(&(address_base + address)).cloned().unwrap())
-            .unzip();
+        let (x, y) = transpose(address.value().map(|address| {
+            self.memory
+                .get(&(address_base + address))
+                .cloned()
+                .ok_or(MsmError::UnwrittenAddress)
+        }))?
+        .unzip();
 
-        let _address = ctx.advice(|| "read in place: offset", self.a0, *address)?;
+        ctx.copy(|| "read in place: offset", self.a0, address)?;
         ctx.fixed(
             || "read in place: base",
             self.constant,
@@ -157,10 +367,10 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         Ok(AssignedPoint::new(x, y))
     }
     fn read_point(
-        &mut self,
+        &self,
         ctx: &mut RegionCtx<'_, F>,
         point_idx: usize,
-        address: &Value<F>,
+        address: &AssignedValue<F>,
     ) -> Result<AssignedPoint<App>, Error> {
         let point = self.read_point_in_place(ctx, point_idx, address)?;
         ctx.empty(|| "read add: b_x", self.a3.into())?;
@@ -172,7 +382,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         &self,
         ctx: &mut RegionCtx<'_, F>,
         point_idx: usize,
-        address: &Value<F>,
+        address: &AssignedValue<F>,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error> {
         ctx.enable(self.s_add)?;
@@ -180,8 +390,13 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
 
         let t = a.x.value().zip(b.x.value()).map(|(a_x, b_x)| *b_x - *a_x);
         let t = t * t;
-        let inverse_t = t.map(|t| t.invert().unwrap());
-        let (out_x, out_y) = a + b;
+        let inverse_t = invert(
+            t,
+            MsmError::ExceptionalAddition {
+                operation: "read_add",
+            },
+        )?;
+        let (out_x, out_y) = (a + b)?;
 
         ctx.copy(|| "add: b_x", self.a3, &b.x)?;
         ctx.copy(|| "add: b_y", self.a4, &b.y)?;
@@ -197,17 +412,59 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         ctx.next();
         Ok(AssignedPoint::new(out_x, out_y))
     }
+    pub fn add_complete(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let s_add_complete = self.s_add_complete.ok_or(MsmError::UnsupportedMode {
+            operation: "complete addition",
+        })?;
+        let witness = transpose(
+            a.coords()
+                .zip(b.coords())
+                .map(|(a, b)| complete_add::<App>(a, b)),
+        )?;
+        let aux = |i: usize| witness.map(|witness| witness[i]);
+        ctx.enable(s_add_complete)?;
+        ctx.copy(|| "add complete: x_p", self.a0, &a.x)?;
+        ctx.copy(|| "add complete: y_p", self.a1, &a.y)?;
+        ctx.copy(|| "add complete: x_q", self.a2, &b.x)?;
+        ctx.copy(|| "add complete: y_q", self.a3, &b.y)?;
+        ctx.advice(|| "add complete: lambda", self.a4, aux(0))?;
+        ctx.empty(|| "add complete:", self.constant.into())?;
+        ctx.next();
+        ctx.advice(|| "add complete: alpha", self.a0, aux(1))?;
+        ctx.advice(|| "add complete: beta", self.a1, aux(2))?;
+        ctx.advice(|| "add complete: gamma", self.a2, aux(3))?;
+        ctx.advice(|| "add complete: delta", self.a3, aux(4))?;
+        ctx.empty(|| "add complete:", self.a4.into())?;
+        ctx.empty(|| "add complete:", self.constant.into())?;
+        ctx.next();
+        let x_r = ctx.advice(|| "add complete: x_r", self.a0, aux(5))?;
+        let y_r = ctx.advice(|| "add complete: y_r", self.a1, aux(6))?;
+        ctx.empty(|| "add complete:", self.a2.into())?;
+        ctx.empty(|| "add complete:", self.a3.into())?;
+        ctx.empty(|| "add complete:", self.a4.into())?;
+        ctx.empty(|| "add complete:", self.constant.into())?;
+        ctx.next();
+        Ok(AssignedPoint::new(x_r, y_r))
+    }
     pub fn add(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         a: &AssignedPoint<App>,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.add_complete(ctx, a, b);
+        }
         ctx.enable(self.s_add)?;
         let t = a.x.value().zip(b.x.value()).map(|(a_x, b_x)| *b_x - *a_x);
         let t = t * t;
-        let inverse_t = t.map(|t| t.invert().unwrap());
-        let (out_x, out_y) = a + b;
+        let inverse_t = invert(t, MsmError::ExceptionalAddition { operation: "add" })?;
+        let (out_x, out_y) = (a + b)?;
         ctx.enable(self.s_add)?;
         ctx.copy(|| "add: a_x", self.a1, &a.x)?;
         ctx.copy(|| "add: a_y", self.a2, &a.y)?;
@@ -229,11 +486,14 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         ctx: &mut RegionCtx<'_, F>,
         point: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.add_complete(ctx, point, point);
+        }
         let (x, y) = point.coords().unzip();
         let x_square = x * x;
         let x_square_square = x_square * x_square;
         let y_square = y * y;
-        let (out_x, out_y) = point.dbl();
+        let (out_x, out_y) = point.dbl()?;
         ctx.enable(self.s_double)?;
         ctx.copy(|| "double: x", self.a0, &point.x)?;
         ctx.copy(|| "double: y", self.a1, &point.y)?;
@@ -261,23 +521,8 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         ctx.equal(a.y.cell(), b.y.cell())
     }
     pub fn layout_range_table(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
-        ly.assign_table(
-            || "range table",
-            |mut table| {
-                for i in 0..1 << self.window {
-                    let value = F::from(i);
-                    table.assign_cell(
-                        || "value in range",
-                        self.range_table,
-                        i as usize,
-                        || Value::known(value),
-                    )?;
-                }
-
-                Ok(())
-            },
-        )?;
-        Ok(())
+        self.range_table
+            .layout(ly, &[(Tag::Window, 1 << self.window)])
     }
     pub fn layout_point_table(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
         ly.assign_table(
@@ -294,7 +539,13 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
                 )?;
 
                 for (address, (_address, (x, y))) in self.memory.iter().enumerate() {
-                    assert_eq!(F::from(address as u64), *_address);
+                    // entries are laid out at consecutive addresses
+                    if F::from(address as u64) != *_address {
+                        return Err(MsmError::InconsistentMemory {
+                            context: "point table",
+                        }
+                        .into());
+                    }
 
                     meta.assign_cell(
                         || "x coordinate",
@@ -324,3 +575,16 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         Ok(())
     }
 }
+
+/// Bases are fixed at configure time so the gate makes no memory queries
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MemoryGate<F> for FixMSMGate<F, App> {
+    type Memory = ();
+    fn new_memory(&self) {}
+    fn replace_memory(&mut self, _: ()) -> Result<(), Error> {
+        Ok(())
+    }
+    fn layout_memory(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.layout_range_table(ly)?;
+        self.layout_point_table(ly)
+    }
+}
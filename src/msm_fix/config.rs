@@ -1,10 +1,12 @@
-use crate::AssignedValue;
-
+use crate::complete::complete_addition_constraints;
+use crate::decomposition::Decomposition;
+use crate::range::{RangeTable, Tag};
 use ff::PrimeField;
 use halo2::{
     halo2curves::CurveAffine,
     plonk::{
-        Advice, Column, ConstraintSystem, Constraints, Expression, Fixed, Selector, TableColumn,
+        Advice, Column, ConstraintSystem, Constraints, Expression, Fixed, Instance, Selector,
+        TableColumn,
     },
     poly::Rotation,
 };
@@ -19,7 +21,8 @@ pub struct FixMSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     pub(crate) a4: Column<Advice>,
 
     pub(crate) constant: Column<Fixed>,
-    pub(crate) range_table: TableColumn,
+    pub(crate) instance: Column<Instance>,
+    pub(crate) range_table: RangeTable,
     pub(crate) x_table: TableColumn,
     pub(crate) y_table: TableColumn,
     pub(crate) address_table: TableColumn,
@@ -30,21 +33,55 @@ pub struct FixMSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     pub(crate) s_double: Selector,
     pub(crate) s_range: Selector,
     pub(crate) s_assign_constant: Selector,
+    pub(crate) decomposition: Decomposition<F>,
+    pub(crate) s_add_complete: Option<Selector>,
 
     pub(crate) memory: BTreeMap<F, (F, F)>,
     pub(crate) window: usize,
-    pub(crate) constants: BTreeMap<F, AssignedValue<F>>,
     pub(crate) correction: App,
     pub(crate) _marker: PhantomData<(F, App)>,
 }
 
 impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
-    pub fn unassign_constants(&mut self) {
-        self.constants.clear();
+    /// Enables complete addition and doubling for `add` and `dbl`. Layout:
+    ///
+    /// | a0    | a1   | a2    | a3    | a4     |
+    /// | x_p   | y_p  | x_q   | y_q   | lambda |
+    /// | alpha | beta | gamma | delta |        |
+    /// | x_r   | y_r  |       |       |        |
+    pub fn configure_complete_addition(&mut self, meta: &mut ConstraintSystem<F>) {
+        let s_add_complete = meta.selector();
+        let (a0, a1, a2, a3, a4) = (self.a0, self.a1, self.a2, self.a3, self.a4);
+        meta.create_gate("complete addition", |meta| {
+            let s = meta.query_selector(s_add_complete);
+            let x_p = meta.query_advice(a0, Rotation::cur());
+            let y_p = meta.query_advice(a1, Rotation::cur());
+            let x_q = meta.query_advice(a2, Rotation::cur());
+            let y_q = meta.query_advice(a3, Rotation::cur());
+            let lambda = meta.query_advice(a4, Rotation::cur());
+            let alpha = meta.query_advice(a0, Rotation::next());
+            let beta = meta.query_advice(a1, Rotation::next());
+            let gamma = meta.query_advice(a2, Rotation::next());
+            let delta = meta.query_advice(a3, Rotation::next());
+            let x_r = meta.query_advice(a0, Rotation(2));
+            let y_r = meta.query_advice(a1, Rotation(2));
+            Constraints::with_selector(
+                s,
+                complete_addition_constraints(
+                    (x_p, y_p),
+                    (x_q, y_q),
+                    (x_r, y_r),
+                    [lambda, alpha, beta, gamma, delta],
+                    App::a(),
+                ),
+            )
+        });
+        self.s_add_complete = Some(s_add_complete);
     }
 }
 
 impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
+    #[allow(clippy::too_many_arguments)]
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         a0: Column<Advice>,
@@ -52,11 +89,12 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         a2: Column<Advice>,
         a3: Column<Advice>,
         a4: Column<Advice>,
-        range_table: TableColumn,
+        range_table: RangeTable,
         address_table: TableColumn,
         x_table: TableColumn,
         y_table: TableColumn,
         constant: Column<Fixed>,
+        instance: Column<Instance>,
         window: usize,
         memory: BTreeMap<F, (F, F)>,
         correction: App,
@@ -71,7 +109,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
         let s_point = meta.selector();
         let s_range = meta.complex_selector();
         let s_assign_constant = meta.selector();
-        let _ = meta.instance_column();
+        meta.enable_equality(instance);
         // address @ a0
         // x @ a1
         // y @ a2
@@ -83,9 +121,11 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
             let x_3 = meta.query_advice(a3, Rotation::cur());
             let expr_x_square = e!(x) * e!(x) - e!(x_2);
             let expr_x_cube = e!(x_2) * e!(x) - e!(x_3);
+            let a = App::a();
+            let a = Expression::Constant(a);
             let b = App::b();
             let b = Expression::Constant(b);
-            let expr_assign = (x_3 + b) - e!(y) * e!(y);
+            let expr_assign = (x_3 + a * e!(x) + b) - e!(y) * e!(y);
             Constraints::with_selector(
                 s,
                 [
@@ -142,8 +182,12 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
             let nine_x_4 = e!(x_4) * F::from(9);
             let three_x_2 = e!(x_2) * F::from(3);
             let two_y = e!(y) * F::from(2);
-            let expr_out_x = four_y_2 * (e!(out_x) + two_x) - nine_x_4;
-            let expr_out_y = two_y * (e!(out_y) + y) - three_x_2 * (x - e!(out_x));
+            let a = App::a();
+            // (3x^2 + a)^2 = 9x^4 + 6ax^2 + a^2
+            let slope_square = nine_x_4 + e!(x_2) * (a * F::from(6)) + Expression::Constant(a * a);
+            let slope = three_x_2 + Expression::Constant(a);
+            let expr_out_x = four_y_2 * (e!(out_x) + two_x) - slope_square;
+            let expr_out_y = two_y * (e!(out_y) + y) - slope * (x - e!(out_x));
             Constraints::with_selector(
                 s,
                 [
@@ -162,6 +206,14 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
             let expr = advice - constant;
             Constraints::with_selector(s, [("expr", expr)])
         });
+        let decomposition = Decomposition::configure(
+            meta,
+            [a0, a1, a2, a3],
+            constant,
+            vec![a4.into()],
+            Some(s_range),
+            window,
+        );
         let s_query = meta.complex_selector();
         meta.lookup("windowed point table", |meta| {
             let s_query = meta.query_selector(s_query);
@@ -175,10 +227,10 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
                 (e!(s_query) * query_y, y_table),
             ]
         });
-        meta.lookup("range address", |meta| {
+        range_table.lookup(meta, "range address", |meta| {
             let s = meta.query_selector(s_range);
             let a0 = meta.query_advice(a0, Rotation::cur());
-            vec![(e!(s) * a0, range_table)]
+            vec![(s, Tag::Window, a0)]
         });
 
         Self {
@@ -187,6 +239,8 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
             s_double,
             s_range,
             s_assign_constant,
+            decomposition,
+            s_add_complete: None,
             a0,
             a1,
             a2,
@@ -194,6 +248,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
             a4,
             range_table,
             constant,
+            instance,
             x_table,
             y_table,
             address_table,
@@ -202,7 +257,6 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> FixMSMGate<F, App> {
 
             correction,
             memory,
-            constants: BTreeMap::new(),
             _marker: PhantomData,
         }
     }
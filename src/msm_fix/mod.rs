@@ -1,4 +1,4 @@
 pub mod assignments;
 pub mod config;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
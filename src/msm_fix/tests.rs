@@ -1,19 +1,22 @@
-use crate::util::multiexp_naive_var;
+use crate::complete::complete_add;
+use crate::point_coords;
+use crate::range::RangeTable;
+use crate::util::{multiexp_naive_var, number_of_windows};
 use crate::RegionCtx;
 use ff::Field;
 use ff::PrimeField;
 use group::Curve;
 use group::Group;
-use halo2::dev::MockProver;
+use halo2::dev::{MockProver, VerifyFailure};
 use halo2::halo2curves::pasta::Eq;
 use halo2::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     halo2curves::CurveAffine,
     plonk::Error,
-    plonk::{Circuit, ConstraintSystem},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Fixed, Instance},
 };
-use rand_core::OsRng;
 use rand_core::SeedableRng;
+use rand_core::{OsRng, RngCore};
 use rand_xorshift::XorShiftRng;
 use std::collections::BTreeMap;
 use std::vec;
@@ -40,6 +43,101 @@ pub(crate) fn incremental_table<C: CurveAffine>(point: &C, size: usize, aux: &C)
     table_affine
 }
 
+/// Deterministic bases so that configuration and synthesis agree on them
+pub(crate) fn fixed_bases<C: CurveAffine>(number_of_points: usize) -> Vec<C> {
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    (0..number_of_points)
+        .map(|_| C::CurveExt::random(&mut rng).to_affine())
+        .collect()
+}
+
+/// Configures the fixed base gate over the given columns with the point
+/// table and the correction of the bases
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn configure_fixed_gate<F: PrimeField + Ord, App: CurveAffine<Base = F>>(
+    meta: &mut ConstraintSystem<F>,
+    [a0, a1, a2, a3, a4]: [Column<Advice>; 5],
+    range_table: RangeTable,
+    constant: Column<Fixed>,
+    instance: Column<Instance>,
+    window: usize,
+    bases: &[App],
+    aux: App,
+) -> FixMSMGate<F, App> {
+    let address_table = meta.lookup_table_column();
+    let x_table = meta.lookup_table_column();
+    let y_table = meta.lookup_table_column();
+
+    let window_size = 1 << window;
+
+    let mut memory = BTreeMap::<F, (F, F)>::new();
+    let mut aux = aux.to_curve();
+
+    let mut correction = App::CurveExt::identity();
+
+    let auxes = bases
+        .iter()
+        .enumerate()
+        .map(|(point_idx, point)| {
+            let table = incremental_table(point, window_size, &aux.to_affine());
+            let cur_aux = aux.clone();
+            aux = aux.double();
+
+            let _table = table
+                .iter()
+                .enumerate()
+                .map(|(offset, point)| {
+                    let address = (point_idx * window_size) + offset;
+
+                    let coordinates = point.coordinates().unwrap();
+
+                    assert_eq!(
+                        memory.insert(
+                            F::from(address as u64),
+                            (*coordinates.x(), *coordinates.y()),
+                        ),
+                        None
+                    );
+
+                    coordinates
+                })
+                .collect::<Vec<_>>();
+
+            cur_aux
+        })
+        .collect::<Vec<_>>();
+
+    let mut aux_sum = auxes
+        .iter()
+        .fold(App::CurveExt::identity(), |acc, next| acc + next);
+
+    for _ in 0..number_of_windows::<F, App::Scalar>(window) {
+        correction += aux_sum;
+        (0..window).for_each(|_| aux_sum = aux_sum.double());
+    }
+
+    FixMSMGate::configure(
+        meta,
+        a0,
+        a1,
+        a2,
+        a3,
+        a4,
+        range_table,
+        address_table,
+        x_table,
+        y_table,
+        constant,
+        instance,
+        window,
+        memory,
+        correction.to_affine(),
+    )
+}
+
 #[derive(Default, Clone, Debug)]
 struct Params<C: CurveAffine> {
     window: usize,
@@ -56,6 +154,7 @@ struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     window: usize,
     bases: Vec<App>,
     aux: App,
+    number_of_bits: Option<usize>,
 }
 
 impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
@@ -67,6 +166,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
             window: self.window,
             bases: self.bases.clone(),
             aux: self.aux,
+            number_of_bits: self.number_of_bits,
         }
     }
     fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
@@ -76,76 +176,17 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
         let a3 = meta.advice_column();
         let a4 = meta.advice_column();
         let constant = meta.fixed_column();
-        let range_table = meta.lookup_table_column();
-        let address_table = meta.lookup_table_column();
-        let x_table = meta.lookup_table_column();
-        let y_table = meta.lookup_table_column();
-
-        let window = params.window;
-        let window_size = 1 << window;
-
-        let mut memory = BTreeMap::<F, (F, F)>::new();
-        let mut aux = params.aux.to_curve();
-
-        let mut correction = App::CurveExt::identity();
-
-        let auxes = params
-            .bases
-            .iter()
-            .enumerate()
-            .map(|(point_idx, point)| {
-                let table = incremental_table(point, window_size, &aux.to_affine());
-                let cur_aux = aux.clone();
-                aux = aux.double();
-
-                let _table = table
-                    .iter()
-                    .enumerate()
-                    .map(|(offset, point)| {
-                        let address = (point_idx * window_size) + offset;
-
-                        let coordinates = point.coordinates().unwrap();
-
-                        assert_eq!(
-                            memory.insert(
-                                F::from(address as u64),
-                                (*coordinates.x(), *coordinates.y()),
-                            ),
-                            None
-                        );
-
-                        coordinates
-                    })
-                    .collect::<Vec<_>>();
-
-                cur_aux
-            })
-            .collect::<Vec<_>>();
-
-        let mut aux_sum = auxes
-            .iter()
-            .fold(App::CurveExt::identity(), |acc, next| acc + next);
-
-        for _ in 0..div_ceil!(App::ScalarExt::NUM_BITS as usize, window) {
-            correction += aux_sum;
-            (0..window).for_each(|_| aux_sum = aux_sum.double());
-        }
-
-        let msm_gate = FixMSMGate::configure(
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
+        let msm_gate = configure_fixed_gate(
             meta,
-            a0,
-            a1,
-            a2,
-            a3,
-            a4,
+            [a0, a1, a2, a3, a4],
             range_table,
-            address_table,
-            x_table,
-            y_table,
             constant,
-            window,
-            memory,
-            correction.to_affine(),
+            instance,
+            params.window,
+            &params.bases,
+            params.aux,
         );
         Self::Config { msm_gate }
     }
@@ -159,12 +200,17 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
             };
         }
         let ly = &mut ly;
-        let rand_scalar = || App::Scalar::random(OsRng);
+        let rand_scalar = || match self.number_of_bits {
+            Some(number_of_bits) => {
+                let scalar = ((OsRng.next_u64() as u128) << 64) | OsRng.next_u64() as u128;
+                App::Scalar::from_u128(scalar >> (128 - number_of_bits))
+            }
+            None => App::Scalar::random(OsRng),
+        };
 
-        let offset = ly.assign_region(
+        let (offset, saved_rows) = ly.assign_region(
             || "app",
             |region| {
-                cfg.msm_gate.unassign_constants();
                 let ctx = &mut RegionCtx::new(region);
 
                 let scalars = (0..self.bases.len())
@@ -176,22 +222,28 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
 
                 let scalars = scalars
                     .into_iter()
-                    .map(|scalar| v!(scalar))
-                    .collect::<Vec<_>>();
+                    .map(|scalar| cfg.msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
 
-                let res1 = cfg.msm_gate.msm(ctx, &scalars[..])?;
+                let res1 = match self.number_of_bits {
+                    Some(number_of_bits) => {
+                        cfg.msm_gate.msm_short(ctx, &scalars[..], number_of_bits)?
+                    }
+                    None => cfg.msm_gate.msm(ctx, &scalars[..])?,
+                };
 
                 cfg.msm_gate.equal(ctx, &res0, &res1)?;
-                Ok(ctx.offset())
+                Ok((ctx.offset(), ctx.constants().saved_rows()))
             },
         )?;
         let number_of_points = self.bases.len();
         println!(
-            "fixed mul gate, window {}, # terms: {}, row cost: {}, area cost: {}",
+            "fixed mul gate, window {}, # terms: {}, row cost: {}, area cost: {}, constant rows saved: {}",
             self.window,
             number_of_points,
             offset / number_of_points,
             5 * offset / number_of_points,
+            saved_rows,
         );
         cfg.msm_gate.layout_range_table(ly)?;
         cfg.msm_gate.layout_point_table(ly)?;
@@ -222,7 +274,72 @@ fn test_fixed_msm() {
     let aux = Eq::generator().to_affine();
 
     let window = 6;
-    let circuit = MyCircuit::<Fq, EqAffine> { bases, window, aux };
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        bases,
+        window,
+        aux,
+        number_of_bits: None,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_fixed_msm_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1, Secp256r1Affine};
+    const K: u32 = 17;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let n = 10;
+    let bases: Vec<Secp256r1Affine> = (0..n)
+        .map(|_| Secp256r1::random(&mut rng).to_affine())
+        .collect();
+
+    let aux = Secp256r1::generator().to_affine();
+
+    let window = 4;
+    let circuit = MyCircuit::<Fp, Secp256r1Affine> {
+        bases,
+        window,
+        aux,
+        number_of_bits: None,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_fixed_msm_short() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let n = 100;
+    let bases: Vec<EqAffine> = (0..n).map(|_| Eq::random(&mut rng).to_affine()).collect();
+
+    let aux = Eq::generator().to_affine();
+
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        bases,
+        window,
+        aux,
+        number_of_bits: Some(128),
+    };
     let public_inputs = vec![vec![]];
     let prover = match MockProver::run(K, &circuit, public_inputs) {
         Ok(prover) => prover,
@@ -230,3 +347,110 @@ fn test_fixed_msm() {
     };
     prover.assert_satisfied();
 }
+
+/// Complete addition of `p = (0, sqrt(b))` and a random point where `cheat`
+/// witnesses the sum as if `p` were the identity. Such points exist on P-256
+/// and would pass as the identity if it was detected by `x = 0`
+#[derive(Default)]
+struct FakeIdentityCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    cheat: bool,
+    _marker: std::marker::PhantomData<(F, App)>,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for FakeIdentityCircuit<F, App> {
+    type Config = TestConfig<F, App>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+    fn without_witnesses(&self) -> Self {
+        Self {
+            cheat: self.cheat,
+            _marker: std::marker::PhantomData,
+        }
+    }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let a = [(); 5].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
+        let window = 2;
+        let aux = App::CurveExt::generator().to_affine();
+        let mut msm_gate = configure_fixed_gate(
+            meta,
+            a,
+            range_table,
+            constant,
+            instance,
+            window,
+            &fixed_bases::<App>(1),
+            aux,
+        );
+        msm_gate.configure_complete_addition(meta);
+        Self::Config { msm_gate }
+    }
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
+        let gate = &cfg.msm_gate;
+        let y_p = Option::from(App::b().sqrt()).expect("b is a quadratic residue");
+        let p = (F::ZERO, y_p);
+        let q = point_coords(App::CurveExt::random(OsRng).to_affine());
+        let mut witness = complete_add::<App>(p, q)?;
+        if self.cheat {
+            // beta = 0 claims that p is the identity so that r = q
+            witness[2] = F::ZERO;
+            witness[5] = q.0;
+            witness[6] = q.1;
+        }
+        ly.assign_region(
+            || "fake identity",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let s_add_complete = gate.s_add_complete.unwrap();
+                let cell = |i: usize| Value::known(witness[i]);
+                ctx.enable(s_add_complete)?;
+                ctx.advice(|| "x_p", gate.a0, Value::known(p.0))?;
+                ctx.advice(|| "y_p", gate.a1, Value::known(p.1))?;
+                ctx.advice(|| "x_q", gate.a2, Value::known(q.0))?;
+                ctx.advice(|| "y_q", gate.a3, Value::known(q.1))?;
+                ctx.advice(|| "lambda", gate.a4, cell(0))?;
+                ctx.next();
+                ctx.advice(|| "alpha", gate.a0, cell(1))?;
+                ctx.advice(|| "beta", gate.a1, cell(2))?;
+                ctx.advice(|| "gamma", gate.a2, cell(3))?;
+                ctx.advice(|| "delta", gate.a3, cell(4))?;
+                ctx.empty(|| "", gate.a4.into())?;
+                ctx.next();
+                ctx.advice(|| "x_r", gate.a0, cell(5))?;
+                ctx.advice(|| "y_r", gate.a1, cell(6))?;
+                ctx.empty(|| "", gate.a2.into())?;
+                ctx.empty(|| "", gate.a3.into())?;
+                ctx.empty(|| "", gate.a4.into())?;
+                Ok(())
+            },
+        )?;
+        gate.layout_range_table(&mut ly)?;
+        gate.layout_point_table(&mut ly)
+    }
+}
+
+fn run_fake_identity(cheat: bool) -> Result<(), Vec<VerifyFailure>> {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 10;
+    let circuit = FakeIdentityCircuit::<Fp, Secp256r1Affine> {
+        cheat,
+        _marker: std::marker::PhantomData,
+    };
+    let prover = match MockProver::run(K, &circuit, vec![vec![]]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.verify()
+}
+
+#[test]
+fn test_complete_addition_at_x_zero() {
+    assert_eq!(run_fake_identity(false), Ok(()));
+}
+
+#[test]
+fn test_complete_addition_rejects_fake_identity() {
+    assert!(run_fake_identity(true).is_err());
+}
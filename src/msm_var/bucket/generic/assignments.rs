@@ -0,0 +1,670 @@
+use super::super::instructions::MSMGate;
+use super::config::VarMSMGateGeneric;
+use crate::msm_var::layout::{
+    ADD_CELLS, COMPLETE_ADD_CELLS, DOUBLE_ADD_CELLS, DOUBLE_CELLS, GLV_CELLS, POINT_CELLS,
+    SIGNED_CELLS,
+};
+use crate::{
+    complete::complete_add,
+    coords,
+    error::{invert, transpose, MsmError},
+    memory::{context::MemoryGate, Memory},
+    point_coords, point_from_coords,
+    range::Tag,
+    util::{double_add_step, scalar_to_native, signed_recoding, window_bits},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::{Advice, Column, Error, Fixed, Instance, Selector},
+};
+use num_bigint::BigUint;
+
+/// Content of a gate cell
+enum Witness<'a, F: PrimeField> {
+    Empty,
+    Advice(Value<F>),
+    Copy(&'a AssignedValue<F>),
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> VarMSMGateGeneric<F, App> {
+    /// Assigns the cells of a gate row by row as placed by the layout and
+    /// fills the rest of the last row with zeros. `fixed` values go to the
+    /// first rows of the block
+    fn assign_cells(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        annotation: &str,
+        mut cells: Vec<Witness<'_, F>>,
+        fixed: &[F],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let width = self.layout.width();
+        let number_of_rows = self.layout.rows(cells.len());
+        cells.resize_with(number_of_rows * width, || Witness::Empty);
+        let mut assigned = vec![];
+        for (i, cell) in cells.into_iter().enumerate() {
+            let column = self.layout.column(i);
+            assigned.push(match cell {
+                Witness::Empty => ctx.empty(|| annotation, column.into())?,
+                Witness::Advice(value) => ctx.advice(|| annotation, column, value)?,
+                Witness::Copy(value) => ctx.copy(|| annotation, column, value)?,
+            });
+            if (i + 1) % width == 0 {
+                match fixed.get(self.layout.row(i)) {
+                    Some(value) => ctx.fixed(|| annotation, self.constant, *value)?,
+                    None => ctx.empty(|| annotation, self.constant.into())?,
+                };
+                ctx.next();
+            }
+        }
+        Ok(assigned)
+    }
+    /// Assigns an addition whose first cells are also a memory query if
+    /// `query` is given as the address and the timestamp
+    fn assign_add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        annotation: &'static str,
+        query: Option<(&AssignedValue<F>, usize)>,
+        a: (Witness<'_, F>, Witness<'_, F>),
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (a_x, a_y) = match (&a.0, &a.1) {
+            (Witness::Advice(x), Witness::Advice(y)) => (*x, *y),
+            (Witness::Copy(x), Witness::Copy(y)) => (x.value().copied(), y.value().copied()),
+            _ => return Err(Error::Synthesis),
+        };
+        let a_value = transpose(a_x.zip(a_y).map(|(x, y)| point_from_coords(x, y)))?;
+        let (out_x, out_y) = coords((b + &a_value)?).unzip();
+        let t = b.x.value().map(|v| *v) - a_x;
+        let t = t * t;
+        let inverse_t = invert(
+            t,
+            MsmError::ExceptionalAddition {
+                operation: annotation,
+            },
+        )?;
+        let (address, fixed) = match query {
+            Some((address, timestamp)) => (Witness::Copy(address), vec![F::from(timestamp as u64)]),
+            None => (Witness::Empty, vec![]),
+        };
+        let cells = vec![
+            address,
+            a.0,
+            a.1,
+            Witness::Advice(out_x),
+            Witness::Advice(out_y),
+            Witness::Copy(&b.x),
+            Witness::Copy(&b.y),
+            Witness::Advice(t),
+            Witness::Advice(inverse_t),
+        ];
+        debug_assert_eq!(cells.len(), ADD_CELLS);
+        let assigned = self.assign_cells(ctx, annotation, cells, &fixed)?;
+        Ok(AssignedPoint::new(assigned[3].clone(), assigned[4].clone()))
+    }
+    /// Assigns a point on the curve. The identity is assigned as `(0, 0)`
+    /// only if `s_point_complete` is given
+    fn assign_curve_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+        s_point_complete: Option<Selector>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (x, y) = match s_point_complete {
+            Some(_) => coords(*point).unzip(),
+            None => transpose(point.map(|c| {
+                Option::from(c.coordinates())
+                    .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+                    .ok_or(MsmError::InvalidPoint {
+                        context: "assign point",
+                    })
+            }))?
+            .unzip(),
+        };
+        let x_square = x * x;
+        let x_cube = x * x * x;
+        ctx.enable(s_point_complete.unwrap_or(self.s_point))?;
+        let cells = vec![
+            Witness::Advice(x),
+            Witness::Advice(y),
+            Witness::Advice(x_square),
+            Witness::Advice(x_cube),
+        ];
+        debug_assert_eq!(cells.len(), POINT_CELLS);
+        let assigned = self.assign_cells(ctx, "assign point", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[0].clone(), assigned[1].clone()))
+    }
+    /// Assigns a point that is constrained not to be the identity even if
+    /// complete addition is configured
+    pub fn assign_non_identity_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        self.assign_curve_point(ctx, point, None)
+    }
+    /// Incomplete addition even if complete addition is configured. Fails on
+    /// the exceptional cases `a = ±b`
+    pub fn add_incomplete(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        ctx.enable(self.s_add)?;
+        let a = (Witness::Copy(&a.x), Witness::Copy(&a.y));
+        self.assign_add(ctx, "add", None, a, b)
+    }
+    /// Constrains `sign` to be `1` or `-1` and returns the boolean
+    /// `(1 - sign) / 2` that `conditional_negate` takes. Both constraints are
+    /// conditional negations, `sign = 1 * (1 - 2 * bit)` and
+    /// `1 = sign * (1 - 2 * bit)`
+    pub fn assign_sign(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        sign: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let s_negate = self.s_negate.ok_or(MsmError::UnsupportedMode {
+            operation: "conditional negation",
+        })?;
+        let one = self.get_constant(ctx, F::ONE)?;
+        let bit = sign
+            .value()
+            .map(|sign| if *sign == F::ONE { F::ZERO } else { F::ONE });
+        ctx.enable(s_negate)?;
+        let cells = vec![
+            Witness::Copy(&one),
+            Witness::Advice(bit),
+            Witness::Copy(sign),
+        ];
+        let assigned = self.assign_cells(ctx, "assign sign", cells, &[])?;
+        let bit = assigned[1].clone();
+        ctx.enable(s_negate)?;
+        let cells = vec![
+            Witness::Copy(sign),
+            Witness::Copy(&bit),
+            Witness::Copy(&one),
+        ];
+        self.assign_cells(ctx, "assign sign", cells, &[])?;
+        Ok(bit)
+    }
+    /// Memory query at `address` that writes back the complete addition of
+    /// the read point and `b`
+    fn query_add_complete(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        annotation: &'static str,
+        address: &AssignedValue<F>,
+        b: &AssignedPoint<App>,
+        range: bool,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let timestamp = self.memory.timestamp();
+        let a = self.memory.peek_point(&address.value().copied());
+        let a_value = transpose(a.map(|(x, y)| point_from_coords::<App>(x, y)))?;
+        let (out_x, out_y) = coords((b + &a_value)?).unzip();
+        let (a_x, a_y) = a.unzip();
+        ctx.enable(self.memory.s_query)?;
+        if range {
+            ctx.enable(self.s_range)?;
+        }
+        let cells = vec![
+            Witness::Copy(address),
+            Witness::Advice(a_x),
+            Witness::Advice(a_y),
+            Witness::Advice(out_x),
+            Witness::Advice(out_y),
+        ];
+        let query = self.assign_cells(ctx, annotation, cells, &[F::from(timestamp as u64)])?;
+        let a = AssignedPoint::new(query[1].clone(), query[2].clone());
+        let out = self.add_complete(ctx, &a, b)?;
+        ctx.equal(out.x.cell(), query[3].cell())?;
+        ctx.equal(out.y.cell(), query[4].cell())?;
+        self.memory
+            .record_point(&address.value().copied(), &out.coords())?;
+        Ok(out)
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MSMGate<F, App>
+    for VarMSMGateGeneric<F, App>
+{
+    fn advice_columns(&self) -> Vec<Column<Advice>> {
+        self.layout.columns().to_vec()
+    }
+    fn fixed_colmns(&self) -> Vec<Column<Fixed>> {
+        vec![self.constant]
+    }
+    fn instance_column(&self) -> Column<Instance> {
+        self.instance
+    }
+    fn get_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        match ctx.constants_mut().get(&scalar) {
+            Some(constant) => Ok(constant),
+            _ => {
+                ctx.enable(self.s_assign_constant)?;
+                let cells = vec![Witness::Advice(Value::known(scalar))];
+                let constant = self.assign_cells(ctx, "get constant", cells, &[scalar])?;
+                let constant = constant[0].clone();
+                ctx.constants_mut().insert(scalar, constant.clone());
+                Ok(constant)
+            }
+        }
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        // identity is assigned as `(0, 0)` once complete addition is configured
+        self.assign_curve_point(ctx, point, self.s_point_complete)
+    }
+    fn write_point(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<(), Error> {
+        let timestamp = self.memory.timestamp();
+        let point = point.coords();
+        let (x1, y1) = point.unzip();
+        let previous_point: Value<(App::Base, App::Base)> =
+            self.memory.peek_point(&address.value().copied());
+        self.memory
+            .record_point(&address.value().copied(), &point)?;
+        let (x0, y0) = previous_point.unzip();
+        ctx.enable(self.memory.s_query)?;
+        ctx.enable(self.s_range)?;
+        let cells = vec![
+            Witness::Copy(address),
+            Witness::Advice(x0),
+            Witness::Advice(y0),
+            Witness::Advice(x1),
+            Witness::Advice(y1),
+        ];
+        self.assign_cells(ctx, "write point", cells, &[F::from(timestamp as u64)])?;
+        Ok(())
+    }
+    fn read_point(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let timestamp = self.memory.timestamp();
+        let point: Value<(App::Base, App::Base)> =
+            self.memory.peek_point(&address.value().copied());
+        self.memory.record_zero(&address.value().copied())?;
+        let (x, y) = point.unzip();
+        ctx.enable(self.memory.s_query)?;
+        // finalized with zero
+        let cells = vec![
+            Witness::Copy(address),
+            Witness::Advice(x),
+            Witness::Advice(y),
+            Witness::Empty,
+            Witness::Empty,
+        ];
+        let assigned = self.assign_cells(ctx, "read point", cells, &[F::from(timestamp as u64)])?;
+        Ok(AssignedPoint::new(assigned[1].clone(), assigned[2].clone()))
+    }
+    fn add_complete(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let s_add_complete = self.s_add_complete.ok_or(MsmError::UnsupportedMode {
+            operation: "complete addition",
+        })?;
+        let witness = transpose(
+            a.coords()
+                .zip(b.coords())
+                .map(|(a, b)| complete_add::<App>(a, b)),
+        )?;
+        let aux = |i: usize| witness.map(|witness| witness[i]);
+        ctx.enable(s_add_complete)?;
+        let mut cells = vec![
+            Witness::Copy(&a.x),
+            Witness::Copy(&a.y),
+            Witness::Copy(&b.x),
+            Witness::Copy(&b.y),
+        ];
+        cells.extend((0..7).map(|i| Witness::Advice(aux(i))));
+        debug_assert_eq!(cells.len(), COMPLETE_ADD_CELLS);
+        let assigned = self.assign_cells(ctx, "add complete", cells, &[])?;
+        Ok(AssignedPoint::new(
+            assigned[9].clone(),
+            assigned[10].clone(),
+        ))
+    }
+    fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.add_complete(ctx, a, b);
+        }
+        self.add_incomplete(ctx, a, b)
+    }
+    fn rw_add(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.query_add_complete(ctx, "rw_add", address, b, true);
+        }
+        let timestamp = self.memory.timestamp();
+        let (a_x, a_y) = self.memory.peek_point(&address.value().copied()).unzip();
+        ctx.enable(self.s_add)?;
+        ctx.enable(self.memory.s_query)?;
+        ctx.enable(self.s_range)?;
+        let a = (Witness::Advice(a_x), Witness::Advice(a_y));
+        let out = self.assign_add(ctx, "rw_add", Some((address, timestamp)), a, b)?;
+        self.memory
+            .record_point(&address.value().copied(), &out.coords())?;
+        Ok(out)
+    }
+    fn read_add(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.query_add_complete(ctx, "read_add", address, b, false);
+        }
+        let timestamp = self.memory.timestamp();
+        let (a_x, a_y) = self.memory.peek_point(&address.value().copied()).unzip();
+        ctx.enable(self.s_add)?;
+        ctx.enable(self.memory.s_query)?;
+        let a = (Witness::Advice(a_x), Witness::Advice(a_y));
+        let out = self.assign_add(ctx, "read_add", Some((address, timestamp)), a, b)?;
+        self.memory
+            .record_point(&address.value().copied(), &out.coords())?;
+        Ok(out)
+    }
+    fn dbl(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.add_complete(ctx, point, point);
+        }
+        let (x, y) = point.coords().unzip();
+        let x_square = x * x;
+        let x_square_square = x_square * x_square;
+        let y_square = y * y;
+        let (out_x, out_y) = point.dbl()?;
+        ctx.enable(self.s_double)?;
+        let cells = vec![
+            Witness::Copy(&point.x),
+            Witness::Copy(&point.y),
+            Witness::Advice(x_square),
+            Witness::Advice(x_square_square),
+            Witness::Advice(y_square),
+            Witness::Advice(out_x),
+            Witness::Advice(out_y),
+        ];
+        debug_assert_eq!(cells.len(), DOUBLE_CELLS);
+        let assigned = self.assign_cells(ctx, "double", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[5].clone(), assigned[6].clone()))
+    }
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let scalar = transpose(scalar.map(scalar_to_native::<F, App::Scalar>))?;
+        let assigned =
+            self.assign_cells(ctx, "assign scalar", vec![Witness::Advice(scalar)], &[])?;
+        Ok(assigned[0].clone())
+    }
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_windows: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        self.decomposition
+            .assign(ctx, scalar, max, number_of_windows, &zero)
+    }
+    fn recode_signed(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+    ) -> Result<Vec<(AssignedValue<F>, AssignedValue<F>)>, Error> {
+        let s_signed = self.s_signed.ok_or(MsmError::UnsupportedMode {
+            operation: "signed digits",
+        })?;
+        let rows = Value::from_iter(windows.iter().map(|window| window.value().copied()))
+            .map(|windows: Vec<F>| signed_recoding::<F>(&windows[..], self.window))
+            .transpose_vec(windows.len());
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        let mut digits = vec![];
+        for (i, (window, row)) in windows.iter().zip(rows.iter()).enumerate() {
+            ctx.enable(s_signed)?;
+            ctx.enable(self.s_range)?;
+            // the most significant digit doesn't carry out
+            let carry = if i == 0 {
+                Witness::Copy(&zero)
+            } else {
+                Witness::Advice(row.map(|row| row[1]))
+            };
+            let cells = vec![
+                Witness::Copy(window),
+                Witness::Advice(row.map(|row| row[0])),
+                carry,
+                Witness::Advice(row.map(|row| row[2])),
+            ];
+            debug_assert_eq!(cells.len(), SIGNED_CELLS);
+            let assigned = self.assign_cells(ctx, "recode signed", cells, &[])?;
+            digits.push((assigned[3].clone(), assigned[1].clone()));
+        }
+        // no carry into the least significant digit
+        let cells = vec![Witness::Empty, Witness::Empty, Witness::Copy(&zero)];
+        self.assign_cells(ctx, "recode signed", cells, &[])?;
+        Ok(digits)
+    }
+    fn conditional_negate(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+        sign: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let s_negate = self.s_negate.ok_or(MsmError::UnsupportedMode {
+            operation: "conditional negation",
+        })?;
+        let y = point
+            .y
+            .value()
+            .zip(sign.value())
+            .map(|(y, sign)| if *sign == F::ONE { -*y } else { *y });
+        ctx.enable(s_negate)?;
+        let cells = vec![
+            Witness::Copy(&point.y),
+            Witness::Copy(sign),
+            Witness::Advice(y),
+        ];
+        let assigned = self.assign_cells(ctx, "negate", cells, &[])?;
+        Ok(AssignedPoint::new(point.x.clone(), assigned[2].clone()))
+    }
+    fn glv_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        split: Value<[F; 9]>,
+    ) -> Result<[AssignedValue<F>; 9], Error> {
+        let s_glv = self
+            .s_glv
+            .ok_or(MsmError::UnsupportedMode { operation: "glv" })?;
+        ctx.enable(s_glv)?;
+        let mut cells = vec![Witness::Copy(scalar)];
+        cells.extend(
+            split
+                .transpose_array()
+                .into_iter()
+                .map(|value: Value<F>| Witness::Advice(value)),
+        );
+        debug_assert_eq!(cells.len(), GLV_CELLS);
+        let assigned = self.assign_cells(ctx, "glv", cells, &[])?;
+        let [k1, k2, quotient, s1, s2, u, v, k_lo, carry] =
+            [1, 2, 3, 4, 5, 6, 7, 8, 9].map(|i| assigned[i].clone());
+        Ok([k1, k2, quotient, s1, s2, u, v, k_lo, carry])
+    }
+    fn weighted_sum(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+        coefficients: &[F],
+    ) -> Result<AssignedValue<F>, Error> {
+        let s_weighted = self
+            .s_weighted
+            .ok_or(MsmError::UnsupportedMode { operation: "glv" })?;
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        // accumulator starts from zero
+        self.assign_cells(
+            ctx,
+            "weighted sum",
+            vec![Witness::Empty, Witness::Copy(&zero)],
+            &[],
+        )?;
+        let mut acc = zero;
+        for (window, coefficient) in windows.iter().zip(coefficients.iter()) {
+            let value = acc
+                .value()
+                .zip(window.value())
+                .map(|(acc, window)| *acc + *coefficient * window);
+            ctx.enable(s_weighted)?;
+            ctx.enable(self.s_range)?;
+            let cells = vec![Witness::Copy(window), Witness::Advice(value)];
+            let assigned = self.assign_cells(ctx, "weighted sum", cells, &[*coefficient])?;
+            acc = assigned[1].clone();
+        }
+        Ok(acc)
+    }
+    fn endomorphism(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (s_endo, glv) = match (self.s_endo, self.glv.as_ref()) {
+            (Some(s_endo), Some(glv)) => (s_endo, glv),
+            _ => return Err(MsmError::UnsupportedMode { operation: "glv" }.into()),
+        };
+        let x = point.x.value().map(|x| *x * glv.zeta);
+        ctx.enable(s_endo)?;
+        let cells = vec![Witness::Copy(&point.x), Witness::Advice(x)];
+        let assigned = self.assign_cells(ctx, "endomorphism", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[1].clone(), point.y.clone()))
+    }
+    fn double_add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        acc: &AssignedPoint<App>,
+        shifted: &AssignedPoint<App>,
+        windows: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        let s_double_add = self.s_double_add.ok_or(MsmError::UnsupportedMode {
+            operation: "single term multiplication",
+        })?;
+        let aux_generator = point_coords(self.aux_generator);
+        // accumulator of the first step is copied and the following ones are
+        // the outputs of the previous steps
+        let mut point = acc.coords();
+        let mut first = true;
+        for window in windows.iter() {
+            let bits = window
+                .value()
+                .map(|window| window_bits(*window, self.window))
+                .transpose_vec(self.window);
+            let mut acc_w = Value::known(F::ZERO);
+            for (i, bit) in bits.into_iter().enumerate() {
+                let q = bit.zip(shifted.coords()).map(|(bit, shifted)| {
+                    if bit == F::ONE {
+                        shifted
+                    } else {
+                        aux_generator
+                    }
+                });
+                let witness = transpose(point.zip(q).map(|(acc, q)| double_add_step(acc, q)))?;
+                let aux = |i: usize| witness.map(|witness| witness[i]);
+                let shift = if i == 0 { F::ZERO } else { F::from(2) };
+                acc_w = acc_w * Value::known(shift) + bit;
+                let (x, y) = point.unzip();
+                let (x, y) = if first {
+                    (Witness::Copy(&acc.x), Witness::Copy(&acc.y))
+                } else {
+                    (Witness::Advice(x), Witness::Advice(y))
+                };
+                let acc_w = if i == self.window - 1 {
+                    Witness::Copy(window)
+                } else {
+                    Witness::Advice(acc_w)
+                };
+                ctx.enable(s_double_add)?;
+                let cells = vec![
+                    x,
+                    y,
+                    Witness::Advice(bit),
+                    Witness::Advice(aux(0)),
+                    Witness::Advice(aux(1)),
+                    Witness::Advice(aux(2)),
+                    Witness::Copy(&shifted.x),
+                    Witness::Copy(&shifted.y),
+                    acc_w,
+                ];
+                debug_assert_eq!(cells.len(), DOUBLE_ADD_CELLS);
+                self.assign_cells(ctx, "double add", cells, &[shift])?;
+                point = aux(3).zip(aux(4));
+                first = false;
+            }
+        }
+        let (x, y) = point.unzip();
+        let cells = if first {
+            vec![Witness::Copy(&acc.x), Witness::Copy(&acc.y)]
+        } else {
+            vec![Witness::Advice(x), Witness::Advice(y)]
+        };
+        let assigned = self.assign_cells(ctx, "double add", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[0].clone(), assigned[1].clone()))
+    }
+    fn layout_sorted_rw(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.memory.layout_sorted(ly)
+    }
+    fn layout_range_table(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range_table.layout(
+            ly,
+            &[
+                (Tag::Window, 1 << self.window),
+                (Tag::Timestamp, self.memory.timestamp()),
+            ],
+        )
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MemoryGate<F> for VarMSMGateGeneric<F, App> {
+    type Memory = Memory<F>;
+    fn new_memory(&self) -> Memory<F> {
+        self.memory.new_memory()
+    }
+    fn replace_memory(&mut self, memory: Memory<F>) -> Result<Memory<F>, Error> {
+        self.memory.replace_memory(memory)
+    }
+    fn layout_memory(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.layout_range_table(ly)?;
+        self.layout_sorted_rw(ly)
+    }
+}
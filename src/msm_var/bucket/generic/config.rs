@@ -0,0 +1,506 @@
+use crate::complete::{complete_addition_constraints, point_or_identity_constraints};
+use crate::decomposition::Decomposition;
+use crate::error::MsmError;
+use crate::memory::config::MemoryChip;
+use crate::msm::cost::{auto_window, Strategy};
+use crate::msm_var::glv::Glv;
+use crate::msm_var::layout::{Layout, DOUBLE_ADD_CELLS, SIGNED_CELLS};
+use crate::range::{RangeTable, Tag};
+use crate::{derive_aux_generator, point_coords, DEFAULT_AUX_GENERATOR_TAG};
+use ff::{PrimeField, WithSmallOrderMulGroup};
+use halo2::{
+    halo2curves::CurveAffine,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+/// Bucket method gate over any number of advice columns. Cells of every gate
+/// are placed by `Layout` so the row and area costs can be tuned with the
+/// width, see `narrow` and `wide` for the five and nine column
+/// configurations. Memory queries are checked by `MemoryChip`
+#[derive(Clone, Debug)]
+pub struct VarMSMGateGeneric<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    pub(crate) layout: Layout,
+    pub(crate) constant: Column<Fixed>,
+    pub(crate) instance: Column<Instance>,
+    pub(crate) range_table: RangeTable,
+    pub(crate) s_point: Selector,
+    pub(crate) s_add: Selector,
+    pub(crate) s_double: Selector,
+    pub(crate) s_range: Selector,
+    pub(crate) s_assign_constant: Selector,
+    pub(crate) decomposition: Decomposition<F>,
+    pub(crate) s_add_complete: Option<Selector>,
+    pub(crate) s_point_complete: Option<Selector>,
+    pub(crate) s_signed: Option<Selector>,
+    pub(crate) s_negate: Option<Selector>,
+    pub(crate) s_endo: Option<Selector>,
+    pub(crate) s_weighted: Option<Selector>,
+    pub(crate) s_glv: Option<Selector>,
+    pub(crate) s_double_add: Option<Selector>,
+    pub(crate) window: usize,
+    pub(crate) aux_generator: App,
+    pub(crate) memory: MemoryChip<F>,
+    pub(crate) initial_buckets: Option<Vec<App>>,
+    pub(crate) correction_points: BTreeMap<usize, App>,
+    pub(crate) glv: Option<Glv<F>>,
+    pub(crate) _marker: PhantomData<(F, App)>,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> VarMSMGateGeneric<F, App> {
+    /// Enables complete addition and doubling for `add`, `dbl` and the
+    /// bucket additions, which then start from the identity rather than from
+    /// the aux generator. `assign_point` also accepts the identity as
+    /// `(0, 0)`. Cells are
+    /// `x_p, y_p, x_q, y_q, lambda, alpha, beta, gamma, delta, x_r, y_r`
+    pub fn configure_complete_addition(&mut self, meta: &mut ConstraintSystem<F>) {
+        let s_add_complete = meta.selector();
+        let s_point_complete = meta.selector();
+        let layout = &self.layout;
+        meta.create_gate("assign point or identity", |meta| {
+            let s = meta.query_selector(s_point_complete);
+            let [x, y, x_2, x_3] = [0, 1, 2, 3].map(|i| layout.query(meta, i, 0));
+            Constraints::with_selector(
+                s,
+                point_or_identity_constraints(x, y, x_2, x_3, App::a(), App::b()),
+            )
+        });
+        meta.create_gate("complete addition", |meta| {
+            let s = meta.query_selector(s_add_complete);
+            let [x_p, y_p, x_q, y_q, lambda, alpha, beta, gamma, delta, x_r, y_r] =
+                [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10].map(|i| layout.query(meta, i, 0));
+            Constraints::with_selector(
+                s,
+                complete_addition_constraints(
+                    (x_p, y_p),
+                    (x_q, y_q),
+                    (x_r, y_r),
+                    [lambda, alpha, beta, gamma, delta],
+                    App::a(),
+                ),
+            )
+        });
+        self.s_add_complete = Some(s_add_complete);
+        self.s_point_complete = Some(s_point_complete);
+    }
+    /// Enables signed digit windows in `[-2^(w-1), 2^(w-1)]` so that only
+    /// `2^(w-1) + 1` buckets are used. Windows are recoded as
+    /// `magnitude * (1 - 2 * sign) = window + carry_in - 2^w * carry` and
+    /// points are negated as `y' = y * (1 - 2 * sign)`. The range table is
+    /// kept as is since windows are still looked up there, and magnitudes are
+    /// bounded by looking up `2^(w-1) - magnitude`. Cells of a digit are
+    /// `window, sign, carry, magnitude` and `carry_in` is the carry of the
+    /// next digit
+    pub fn configure_signed_digits(&mut self, meta: &mut ConstraintSystem<F>) {
+        assert!(self.window > 1);
+        let s_signed = meta.complex_selector();
+        let layout = &self.layout;
+        let range_table = self.range_table;
+        let shift = F::from(1 << self.window);
+        let half = F::from(1 << (self.window - 1));
+        let next = layout.rows(SIGNED_CELLS) as i32;
+        meta.create_gate("signed digit recoding", |meta| {
+            let s = meta.query_selector(s_signed);
+            let [window, sign, carry, magnitude] = [0, 1, 2, 3].map(|i| layout.query(meta, i, 0));
+            let carry_in = layout.query(meta, 2, next);
+            let one = Expression::Constant(F::ONE);
+            let expr_sign = e!(sign) * (e!(one) - e!(sign));
+            let expr_carry = e!(carry) * (e!(one) - e!(carry));
+            // magnitude * (1 - 2 * sign) = window + carry_in - carry * 2^w
+            let expr_digit = magnitude * (one - sign * F::from(2))
+                - (window + carry_in - carry * Expression::Constant(shift));
+            Constraints::with_selector(
+                s,
+                [
+                    ("signed_sign", expr_sign),
+                    ("signed_carry", expr_carry),
+                    ("signed_digit", expr_digit),
+                ],
+            )
+        });
+        range_table.lookup(meta, "range magnitude", |meta| {
+            let s = meta.query_selector(s_signed);
+            let magnitude = layout.query(meta, 3, 0);
+            vec![(s, Tag::Window, Expression::Constant(half) - magnitude)]
+        });
+        self.configure_negation(meta);
+        self.s_signed = Some(s_signed);
+    }
+    /// Conditional negation `y' = y * (1 - 2 * sign)` shared by signed digits,
+    /// the GLV mode and the signed short scalars of `ecc::EccChip`. Cells are
+    /// `y, sign, y'`
+    pub fn configure_negation(&mut self, meta: &mut ConstraintSystem<F>) {
+        if self.s_negate.is_some() {
+            return;
+        }
+        let s_negate = meta.selector();
+        let layout = &self.layout;
+        meta.create_gate("conditional negation", |meta| {
+            let s = meta.query_selector(s_negate);
+            let [y, sign, y_out] = [0, 1, 2].map(|i| layout.query(meta, i, 0));
+            let one = Expression::Constant(F::ONE);
+            let expr = y_out - y * (one - sign * F::from(2));
+            Constraints::with_selector(s, [("negate_y", expr)])
+        });
+        self.s_negate = Some(s_negate);
+    }
+    /// Enables `mul` for single terms such as the ones of folding verifiers.
+    /// Each bit is a single step `acc' = (acc + q) + acc` where
+    /// `q = aux + bit * point` is selected between the aux generator and
+    /// `shifted = aux + point`, so neither buckets nor memory checking are
+    /// needed. Bits are recomposed into windows as
+    /// `acc_w = shift * acc_w_prev + bit` where the fixed shift is zero at the
+    /// first bit of a window. `x_acc != x_r` follows from `y_acc != 0` as
+    /// curves are expected to have no points of order two. Cells of a step
+    /// are `x_acc, y_acc, bit, lambda1, lambda2, inverse, x_shifted,
+    /// y_shifted, acc_w` and the output is the accumulator of the next step
+    pub fn configure_single_term(&mut self, meta: &mut ConstraintSystem<F>) {
+        let s_double_add = meta.selector();
+        let layout = &self.layout;
+        let constant = self.constant;
+        let (x_aux, y_aux) = point_coords(self.aux_generator);
+        let step = layout.rows(DOUBLE_ADD_CELLS) as i32;
+        meta.create_gate("double and add", |meta| {
+            let s = meta.query_selector(s_double_add);
+            let [x_acc, y_acc, bit, lambda1, lambda2, inverse, x_shifted, y_shifted, acc_w] =
+                [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|i| layout.query(meta, i, 0));
+            let acc_w_prev = layout.query(meta, 8, -step);
+            let shift = meta.query_fixed(constant, Rotation::cur());
+            let x_out = layout.query(meta, 0, step);
+            let y_out = layout.query(meta, 1, step);
+            let one = Expression::Constant(F::ONE);
+            let x_aux = Expression::Constant(x_aux);
+            let y_aux = Expression::Constant(y_aux);
+            // q = aux + bit * (shifted - aux)
+            let x_q = e!(x_aux) + e!(bit) * (x_shifted - x_aux);
+            let y_q = e!(y_aux) + e!(bit) * (y_shifted - y_aux);
+            let expr_bit = e!(bit) * (e!(one) - e!(bit));
+            // 1 / (x_acc - x_q) * (x_acc - x_q) = 1
+            let expr_inverse = inverse * (e!(x_acc) - e!(x_q)) - one;
+            // lambda1 * (x_acc - x_q) = y_acc - y_q
+            let expr_lambda1 = e!(lambda1) * (e!(x_acc) - e!(x_q)) - (e!(y_acc) - y_q);
+            // r = acc + q
+            let x_r = e!(lambda1).square() - e!(x_acc) - x_q;
+            // (lambda1 + lambda2) * (x_acc - x_r) = 2 * y_acc
+            let expr_lambda2 =
+                (lambda1 + e!(lambda2)) * (e!(x_acc) - e!(x_r)) - e!(y_acc) * F::from(2);
+            // out = r + acc
+            let expr_x_out = e!(x_out) - (e!(lambda2).square() - e!(x_acc) - x_r);
+            let expr_y_out = y_out - (lambda2 * (x_acc - x_out) - y_acc);
+            // acc_w = shift * acc_w_prev + bit
+            let expr_window = acc_w - (shift * acc_w_prev + bit);
+            Constraints::with_selector(
+                s,
+                [
+                    ("double_add_bit", expr_bit),
+                    ("double_add_inverse", expr_inverse),
+                    ("double_add_lambda1", expr_lambda1),
+                    ("double_add_lambda2", expr_lambda2),
+                    ("double_add_x_out", expr_x_out),
+                    ("double_add_y_out", expr_y_out),
+                    ("double_add_window", expr_window),
+                ],
+            )
+        });
+        self.s_double_add = Some(s_double_add);
+    }
+}
+
+impl<F: PrimeField + Ord + WithSmallOrderMulGroup<3>, App: CurveAffine<Base = F>>
+    VarMSMGateGeneric<F, App>
+where
+    App::Scalar: WithSmallOrderMulGroup<3>,
+{
+    /// Enables the GLV mode for curves with the endomorphism
+    /// `(x, y) -> (zeta * x, y) = lambda * (x, y)`. Each scalar is split into
+    /// two signed half size scalars so that the number of rounds is halved
+    /// while the number of terms is doubled. See `Glv` for the decomposition
+    /// check whose cells are `k, k1, k2, quotient, s1, s2, u, v, k_lo, carry`.
+    /// Cells of the endomorphism are `x, zeta * x` and the ones of the
+    /// weighted sum are `window, acc`
+    pub fn configure_glv(&mut self, meta: &mut ConstraintSystem<F>) {
+        let glv = Glv::new::<App>(self.window);
+        let s_endo = meta.selector();
+        let s_weighted = meta.selector();
+        let s_glv = meta.selector();
+        let layout = &self.layout;
+        let constant = self.constant;
+        let zeta = glv.zeta;
+        meta.create_gate("endomorphism", |meta| {
+            let s = meta.query_selector(s_endo);
+            let [x, x_out] = [0, 1].map(|i| layout.query(meta, i, 0));
+            let expr = x_out - x * zeta;
+            Constraints::with_selector(s, [("endo_x", expr)])
+        });
+        let prev = -(layout.rows(2) as i32);
+        meta.create_gate("weighted sum", |meta| {
+            let s = meta.query_selector(s_weighted);
+            let [window, acc] = [0, 1].map(|i| layout.query(meta, i, 0));
+            let acc_prev = layout.query(meta, 1, prev);
+            let coefficient = meta.query_fixed(constant, Rotation::cur());
+            // acc = acc_prev + coefficient * window
+            let expr = acc - (acc_prev + coefficient * window);
+            Constraints::with_selector(s, [("weighted_sum", expr)])
+        });
+        meta.create_gate("glv decomposition", |meta| {
+            let s = meta.query_selector(s_glv);
+            let row0 = [0, 1, 2, 3, 4].map(|i| layout.query(meta, i, 0));
+            let row1 = [5, 6, 7, 8, 9].map(|i| layout.query(meta, i, 0));
+            Constraints::with_selector(s, glv.constraints(row0, row1))
+        });
+        self.configure_negation(meta);
+        self.glv = Some(glv);
+        self.s_endo = Some(s_endo);
+        self.s_weighted = Some(s_weighted);
+        self.s_glv = Some(s_glv);
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> VarMSMGateGeneric<F, App> {
+    /// `configure` with the default aux generator on any number of advice
+    /// columns
+    pub fn configure_default(
+        meta: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        range_table: RangeTable,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+        window: usize,
+    ) -> Self {
+        let aux_generator = derive_aux_generator(DEFAULT_AUX_GENERATOR_TAG);
+        Self::configure(
+            meta,
+            advice,
+            range_table,
+            constant,
+            instance,
+            window,
+            aux_generator,
+        )
+    }
+
+    /// Configures the gate with the window `cost::plan` picks for
+    /// `number_of_terms` terms in `2^k` rows, with signed digits when
+    /// `signed` is set. Fails with `MsmError::NoWindow` if no window fits
+    #[allow(clippy::too_many_arguments)]
+    pub fn configure_auto(
+        meta: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        range_table: RangeTable,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+        number_of_terms: usize,
+        k: u32,
+        signed: bool,
+    ) -> Result<Self, MsmError> {
+        let strategy = Strategy::BucketGeneric(advice.len());
+        let window = auto_window::<F, App::Scalar>(strategy, number_of_terms, k, signed)?;
+        let mut gate =
+            Self::configure_default(meta, advice, range_table, constant, instance, window);
+        if signed {
+            gate.configure_signed_digits(meta);
+        }
+        Ok(gate)
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        range_table: RangeTable,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+        window: usize,
+        aux_generator: App,
+    ) -> Self {
+        Self::configure_layout(
+            meta,
+            advice,
+            range_table,
+            constant,
+            instance,
+            window,
+            aux_generator,
+            false,
+        )
+    }
+
+    /// Configures the gate with sorted queries side by side in slots of
+    /// `address, x, y, timestamp`, which takes about a row per slot count of
+    /// the sorted region for a lookup per slot. At least two slots, that is
+    /// eight columns, are required. See `MemoryChip::configure_packed_gates`
+    pub fn configure_packed(
+        meta: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        range_table: RangeTable,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+        window: usize,
+        aux_generator: App,
+    ) -> Self {
+        Self::configure_layout(
+            meta,
+            advice,
+            range_table,
+            constant,
+            instance,
+            window,
+            aux_generator,
+            true,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn configure_layout(
+        meta: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        range_table: RangeTable,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+        window: usize,
+        aux_generator: App,
+        packed: bool,
+    ) -> Self {
+        let layout = Layout::new(advice);
+        for column in advice.iter() {
+            meta.enable_equality(*column);
+        }
+        let s_add = meta.selector();
+        let s_double = meta.selector();
+        let s_point = meta.selector();
+        let s_range = meta.complex_selector();
+        let s_assign_constant = meta.selector();
+        meta.enable_equality(instance);
+        let layout_ref = &layout;
+        meta.create_gate("assign point", |meta| {
+            let s = meta.query_selector(s_point);
+            let [x, y, x_2, x_3] = [0, 1, 2, 3].map(|i| layout_ref.query(meta, i, 0));
+            let expr_x_square = e!(x) * e!(x) - e!(x_2);
+            let expr_x_cube = e!(x_2) * e!(x) - e!(x_3);
+            let a = App::a();
+            let a = Expression::Constant(a);
+            let b = App::b();
+            let b = Expression::Constant(b);
+            let expr_assign = (x_3 + a * e!(x) + b) - e!(y) * e!(y);
+            Constraints::with_selector(
+                s,
+                [
+                    ("assign_x_square", expr_x_square),
+                    ("assign_x_cube", expr_x_cube),
+                    ("assign_assign", expr_assign),
+                ],
+            )
+        });
+        // address, x0, y0, x1, y1 are also the memory query
+        meta.create_gate("incomplete addition", |meta| {
+            let s = meta.query_selector(s_add);
+            let [a_x, a_y, out_x, out_y, b_x, b_y, t, inverse_t] =
+                [1, 2, 3, 4, 5, 6, 7, 8].map(|i| layout_ref.query(meta, i, 0));
+            let one = Expression::Constant(F::ONE);
+            // t = (b_x - a_x) ^ 2
+            let expr_t = (e!(b_x) - e!(a_x)).square() - e!(t);
+            // 1/t * t = 1
+            let expr_inverse_t = e!(t) * e!(inverse_t) - e!(one);
+            // out_x + a_x + b_x * t = (b_y - a_y) ^ 2
+            let expr_x = (e!(out_x) + e!(a_x) + e!(b_x)) * e!(t) - (e!(b_y) - e!(a_y)).square();
+            // (out_y + a_y) * (b_x - a_x) = (b_y - a_y) * (a_x - out_x)
+            let expr_y = (e!(out_y) + e!(a_y)) * (e!(b_x) - e!(a_x))
+                - (e!(b_y) - e!(a_y)) * (e!(a_x) - e!(out_x));
+            Constraints::with_selector(
+                s,
+                [
+                    ("add_t", expr_t),
+                    ("add_inverse_t", expr_inverse_t),
+                    ("add_x", expr_x),
+                    ("add_y", expr_y),
+                ],
+            )
+        });
+        meta.create_gate("incomplete doubling", |meta| {
+            let s = meta.query_selector(s_double);
+            let [x, y, x_2, x_4, y_2, out_x, out_y] =
+                [0, 1, 2, 3, 4, 5, 6].map(|i| layout_ref.query(meta, i, 0));
+            let expr_x_square = e!(x).square() - e!(x_2);
+            let expr_x_square_square = e!(x_2).square() - e!(x_4);
+            let expr_y_square = e!(y).square() - e!(y_2);
+            let four_y_2 = e!(y_2) * F::from(4);
+            let two_x = e!(x) * F::from(2);
+            let nine_x_4 = e!(x_4) * F::from(9);
+            let three_x_2 = e!(x_2) * F::from(3);
+            let two_y = e!(y) * F::from(2);
+            let a = App::a();
+            // (3x^2 + a)^2 = 9x^4 + 6ax^2 + a^2
+            let slope_square = nine_x_4 + e!(x_2) * (a * F::from(6)) + Expression::Constant(a * a);
+            let slope = three_x_2 + Expression::Constant(a);
+            let expr_out_x = four_y_2 * (e!(out_x) + two_x) - slope_square;
+            let expr_out_y = two_y * (e!(out_y) + y) - slope * (x - e!(out_x));
+            Constraints::with_selector(
+                s,
+                [
+                    ("double_x_square", expr_x_square),
+                    ("double_x_square_square", expr_x_square_square),
+                    ("double_y_square", expr_y_square),
+                    ("double_out_x", expr_out_x),
+                    ("double_out_y", expr_out_y),
+                ],
+            )
+        });
+        meta.create_gate("assign constant", |meta| {
+            let s = meta.query_selector(s_assign_constant);
+            let advice = layout_ref.query(meta, 0, 0);
+            let constant = meta.query_fixed(constant, Rotation::cur());
+            let expr = advice - constant;
+            Constraints::with_selector(s, [("expr", expr)])
+        });
+        let decomposition = Decomposition::configure(
+            meta,
+            [0, 1, 2, 3].map(|i| layout.column(i)),
+            constant,
+            // one window per row regardless of the width
+            advice[4..].iter().map(|column| (*column).into()).collect(),
+            Some(s_range),
+            window,
+        );
+        let memory = if packed {
+            MemoryChip::configure_packed_gates(meta, layout.columns(), constant, 2, range_table)
+        } else {
+            MemoryChip::configure_gates(meta, layout.columns(), constant, 2, range_table)
+        };
+        // addresses of the msm region and timestamps of the sorted region
+        // share an argument
+        range_table.lookup(meta, "range address and timestamp diff", |meta| {
+            let s = meta.query_selector(s_range);
+            let address = layout_ref.query(meta, 0, 0);
+            let mut inputs = vec![(s, Tag::Window, address)];
+            inputs.extend(memory.range_inputs(meta));
+            inputs
+        });
+        Self {
+            layout,
+            s_point,
+            s_add,
+            s_double,
+            s_range,
+            s_assign_constant,
+            decomposition,
+            s_add_complete: None,
+            s_point_complete: None,
+            s_signed: None,
+            s_negate: None,
+            s_endo: None,
+            s_weighted: None,
+            s_glv: None,
+            s_double_add: None,
+            range_table,
+            constant,
+            instance,
+            window,
+            memory,
+            initial_buckets: None,
+            correction_points: BTreeMap::new(),
+            glv: None,
+            aux_generator,
+            _marker: PhantomData,
+        }
+    }
+}
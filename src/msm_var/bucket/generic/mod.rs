@@ -0,0 +1,5 @@
+pub mod assignments;
+pub mod config;
+pub mod msm;
+#[cfg(test)]
+mod tests;
@@ -1,35 +1,117 @@
-use crate::{util::decompose, AssignedPoint, AssignedValue, RegionCtx};
+use crate::{
+    error::{check_limbs, check_mixed, check_terms, MsmError},
+    msm_fix::config::FixMSMGate,
+    msm_var::glv::Glv,
+    point_coords,
+    util::{number_of_signed_windows, number_of_windows, scalar_max, short_scalar_max},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
 use ff::PrimeField;
+use group::{Curve, Group};
 use halo2::{
     circuit::{Layouter, Value},
     halo2curves::CurveAffine,
-    plonk::{Advice, Column, Error, Fixed},
+    plonk::{Advice, Column, Error, Fixed, Instance},
 };
+use num_bigint::BigUint;
+use num_traits::One;
+use std::collections::BTreeMap;
 
 pub trait MSMHelper<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     fn window(&self) -> usize;
-    fn reset_buckets(&mut self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error>;
-    fn gen_initial_buckets(&mut self) -> Vec<App>;
-    fn correction_point(&mut self, ctx: &mut RegionCtx<'_, F>)
-        -> Result<AssignedPoint<App>, Error>;
-    fn initial_buckets(
-        &mut self,
-        ctx: &mut RegionCtx<'_, F>,
-    ) -> Result<Vec<AssignedPoint<App>>, Error>;
-    fn decompose_scalars(&self, scalars: &[Value<App::Scalar>]) -> Vec<Vec<Value<F>>> {
-        let number_of_rounds = div_ceil!(F::NUM_BITS as usize, self.window());
-        let scalars = scalars
-            .iter()
-            .map(|scalar| {
-                let decomposed = scalar.map(|scalar| {
-                    let mut decomposed: Vec<F> = decompose(scalar, number_of_rounds, self.window());
-                    decomposed.reverse();
-                    decomposed
-                });
-                decomposed.transpose_vec(number_of_rounds)
+    /// Whether windows are recoded into signed digits
+    fn signed(&self) -> bool;
+    /// Whether bucket additions use complete formulas
+    fn complete(&self) -> bool;
+    /// Endomorphism decomposition if configured
+    fn glv(&self) -> Option<&Glv<F>>;
+    fn aux_generator(&self) -> App;
+    /// Initial buckets once they are derived from the aux generator
+    fn initial_buckets_mut(&mut self) -> &mut Option<Vec<App>>;
+    /// Correction points derived so far keyed by the number of rounds
+    fn correction_points_mut(&mut self) -> &mut BTreeMap<usize, App>;
+    /// Buckets start from `2^i * aux` so that no addition in the bucket
+    /// phase hits the identity. With complete additions they start from the
+    /// identity and the correction point is the identity too
+    fn gen_initial_buckets(&mut self) -> Vec<App> {
+        if let Some(buckets) = self.initial_buckets_mut() {
+            return buckets.clone();
+        }
+        let size = self.number_of_buckets();
+        let mut acc: App::CurveExt = if self.complete() {
+            App::CurveExt::identity()
+        } else {
+            self.aux_generator().into()
+        };
+        let initial_buckets = (0..size)
+            .map(|_| {
+                let ret = acc;
+                acc = acc.double();
+                ret.to_affine()
             })
             .collect::<Vec<_>>();
-        scalars
+        *self.initial_buckets_mut() = Some(initial_buckets.clone());
+        initial_buckets
+    }
+    /// Negation of the aux generator offsets accumulated over
+    /// `number_of_rounds`
+    fn correction(&mut self, number_of_rounds: usize) -> App {
+        if let Some(point) = self.correction_points_mut().get(&number_of_rounds) {
+            return *point;
+        }
+        let initial_buckets = self.gen_initial_buckets();
+        let bucket_sum = initial_buckets
+            .iter()
+            .skip(1)
+            .rev()
+            .fold(
+                (App::Curve::identity(), App::Curve::identity()),
+                |(sum, acc), bucket| {
+                    let sum = sum + bucket;
+                    (sum, acc + sum)
+                },
+            )
+            .1;
+        let window = self.window();
+        let correction_point = -(0..number_of_rounds)
+            .fold(App::CurveExt::identity(), |acc, _| {
+                let acc = (0..window).fold(acc, |acc, _| acc.double());
+                acc + bucket_sum
+            })
+            .to_affine();
+        self.correction_points_mut()
+            .insert(number_of_rounds, correction_point);
+        correction_point
+    }
+    fn number_of_buckets(&self) -> usize {
+        if self.signed() {
+            (1 << (self.window() - 1)) + 1
+        } else {
+            1 << self.window()
+        }
+    }
+    fn number_of_rounds(&self) -> usize {
+        match self.glv() {
+            Some(glv) => glv.number_of_rounds(self.signed()),
+            None if self.signed() => number_of_signed_windows::<F, App::Scalar>(self.window()),
+            None => number_of_windows::<F, App::Scalar>(self.window()),
+        }
+    }
+    /// Number of rounds for scalars of at most `number_of_bits` bits
+    fn number_of_short_rounds(&self, number_of_bits: usize) -> usize {
+        if self.signed() {
+            div_ceil!(number_of_bits + 1, self.window())
+        } else {
+            div_ceil!(number_of_bits, self.window())
+        }
+    }
+    /// Negation of `(2^(n+1) - 1) * aux` that the double-and-add chain of
+    /// `mul` accumulates over `n` bits
+    fn single_term_correction_point(&self) -> App {
+        let number_of_bits = number_of_windows::<F, App::Scalar>(self.window()) * self.window();
+        let aux_generator: App::CurveExt = self.aux_generator().into();
+        let acc = (0..number_of_bits).fold(aux_generator, |acc, _| acc.double() + aux_generator);
+        (-acc).to_affine()
     }
 }
 pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F, App> {
@@ -37,14 +119,164 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
         points: &[AssignedPoint<App>],
-        scalars: &[Value<App::Scalar>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        let number_of_rounds = self.number_of_rounds();
+        // with the endomorphism each term is split into two half size terms
+        let mut terms = vec![];
+        for (point, scalar) in points.iter().zip(scalars.iter()) {
+            if self.glv().is_some() {
+                let [(k1, s1), (k2, s2)] = self.decompose_glv(ctx, scalar)?;
+                let image = self.endomorphism(ctx, point)?;
+                terms.push((self.conditional_negate(ctx, point, &s1)?, k1));
+                terms.push((self.conditional_negate(ctx, &image, &s2)?, k2));
+            } else {
+                terms.push((point.clone(), self.decompose_scalar(ctx, scalar)?));
+            }
+        }
+        self.msm_windows(ctx, terms, None, number_of_rounds)
+    }
+    /// MSM of scalars that are declared to fit in `number_of_bits` bits such
+    /// as transcript challenges. Scalars are range constrained to the bound
+    /// and only the rounds that cover it are run
+    fn msm_short(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+        number_of_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        if self.glv().is_some() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "short scalar msm with glv",
+            }
+            .into());
+        }
+        let max = short_scalar_max::<F, App::Scalar>(number_of_bits)?;
+        let number_of_rounds = self.number_of_short_rounds(number_of_bits);
+        let terms = points
+            .iter()
+            .zip(scalars.iter())
+            .map(|(point, scalar)| {
+                let windows = self.decompose(ctx, scalar, &max, number_of_rounds)?;
+                Ok((point.clone(), windows))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(ctx, terms, None, number_of_rounds)
+    }
+    /// MSM of scalars given as little endian limbs of `limb_bits` bits such
+    /// as recomposed range checked integers. Limbs are copied into their own
+    /// window decompositions so `limb_bits` must be a multiple of the window
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), limbs.len())?;
+        if self.glv().is_some() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "limb scalar msm with glv",
+            }
+            .into());
+        }
+        let number_of_limbs = check_limbs(limbs, limb_bits, self.window())?;
+        let max = short_scalar_max::<F, App::Scalar>(limb_bits)?;
+        let windows_per_limb = limb_bits / self.window();
+        let mut number_of_rounds = number_of_limbs * windows_per_limb;
+        // zero window on top takes the carry of the signed recoding
+        let zero = if self.signed() {
+            number_of_rounds += 1;
+            Some(self.get_constant(ctx, F::ZERO)?)
+        } else {
+            None
+        };
+        let terms = points
+            .iter()
+            .zip(limbs.iter())
+            .map(|(point, limbs)| {
+                let mut windows = zero.iter().cloned().collect::<Vec<_>>();
+                for limb in limbs.iter().rev() {
+                    windows.extend(self.decompose(ctx, limb, &max, windows_per_limb)?);
+                }
+                Ok((point.clone(), windows))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(ctx, terms, None, number_of_rounds)
+    }
+    /// Computes `sum a_i * G_i + sum b_j * P_j` where `G_i` are the bases of
+    /// the fixed base gate and `P_j` are variable points. Both sides share a
+    /// single doubling chain and a merged correction point so the gates
+    /// must be configured with the same window. Fixed scalars are decomposed
+    /// into unsigned windows over as many rounds as the variable side runs,
+    /// which has an extra round in the signed mode
+    fn msm_mixed(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        fixed: &FixMSMGate<F, App>,
+        fixed_scalars: &[AssignedValue<F>],
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
     ) -> Result<AssignedPoint<App>, Error> {
-        let number_of_points = points.len();
-        assert!(number_of_points > 0);
-        assert_eq!(number_of_points, scalars.len());
-        let number_of_buckets = 1 << self.window();
-        let number_of_rounds = div_ceil!(F::NUM_BITS as usize, self.window());
-        let scalars = self.decompose_scalars(scalars);
+        check_terms(points.len(), scalars.len())?;
+        check_mixed(self.window(), fixed.window, self.glv().is_some())?;
+        let table_size = fixed_scalars.len() << self.window();
+        if fixed.memory.len() != table_size {
+            return Err(MsmError::TableMismatch {
+                expected: table_size,
+                actual: fixed.memory.len(),
+            }
+            .into());
+        }
+        let number_of_rounds = self.number_of_rounds();
+        let terms = points
+            .iter()
+            .zip(scalars.iter())
+            .map(|(point, scalar)| Ok((point.clone(), self.decompose_scalar(ctx, scalar)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let max = scalar_max::<F, App::Scalar>();
+        let fixed_windows = fixed_scalars
+            .iter()
+            .map(|scalar| fixed.decompose(ctx, scalar, &max, number_of_rounds))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(
+            ctx,
+            terms,
+            Some((fixed, &fixed_windows[..])),
+            number_of_rounds,
+        )
+    }
+    /// Bucket accumulation of points along with their windows most
+    /// significant first. Windows of the fixed bases if given are read from
+    /// the fixed table into the same accumulator
+    fn msm_windows(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: Vec<(AssignedPoint<App>, Vec<AssignedValue<F>>)>,
+        fixed: Option<(&FixMSMGate<F, App>, &[Vec<AssignedValue<F>>])>,
+        number_of_rounds: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let number_of_buckets = self.number_of_buckets();
+        // bucket addresses along with optional signs
+        let (points, scalars): (Vec<_>, Vec<_>) = terms
+            .into_iter()
+            .map(|(point, windows)| {
+                let digits = if self.signed() {
+                    self.recode_signed(ctx, &windows)?
+                        .into_iter()
+                        .map(|(magnitude, sign)| (magnitude, Some(sign)))
+                        .collect::<Vec<_>>()
+                } else {
+                    windows.into_iter().map(|window| (window, None)).collect()
+                };
+                Ok((point, digits))
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
         let mut acc = None;
         for round in 0..number_of_rounds {
             if round != 0 {
@@ -55,14 +287,23 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
             self.reset_buckets(ctx)?;
             // accumulate buckets
             for (scalar, point) in scalars.iter().zip(points.iter()) {
-                self.rw_add(ctx, &scalar[round], point)?;
+                let (address, sign) = &scalar[round];
+                match sign {
+                    Some(sign) => {
+                        let point = self.conditional_negate(ctx, point, sign)?;
+                        self.rw_add(ctx, address, &point)?;
+                    }
+                    None => {
+                        self.rw_add(ctx, address, point)?;
+                    }
+                }
             }
             // aggregate buckets
-            let last = self.get_constant(ctx, F::from(number_of_buckets - 1))?;
+            let last = self.get_constant(ctx, F::from(number_of_buckets as u64 - 1))?;
             let mut inner_acc = self.read_point(ctx, &last)?;
             let mut sum = inner_acc.clone();
             for i in (1..number_of_buckets - 1).rev() {
-                let address = self.get_constant(ctx, F::from(i))?;
+                let address = self.get_constant(ctx, F::from(i as u64))?;
                 // sum = B_0 + B_1 + B_2 + ...
                 sum = self.read_add(ctx, &address, &sum)?;
                 // inner_acc = 0*B_0 + 1*B_1 + 2*B_2 + ...
@@ -70,16 +311,46 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
             }
             let address = self.get_constant(ctx, F::ZERO)?;
             let _dummy_read = self.read_point(ctx, &address)?;
-            acc = match acc {
-                None => Some(inner_acc),
-                Some(_) => Some(self.add(ctx, &inner_acc, &acc.unwrap())?),
+            let mut round_acc = match acc {
+                None => inner_acc,
+                Some(acc) => self.add(ctx, &inner_acc, &acc)?,
             };
+            if let Some((fixed, fixed_windows)) = fixed {
+                for (point_idx, windows) in fixed_windows.iter().enumerate() {
+                    round_acc = fixed.read_add(ctx, point_idx, &windows[round], &round_acc)?;
+                }
+            }
+            acc = Some(round_acc);
+        }
+        let mut correction = self.correction(number_of_rounds).to_curve();
+        if let Some((fixed, _)) = fixed {
+            correction += fixed.correction(number_of_rounds)?;
         }
-        let correction_point = self.correction_point(ctx)?;
+        let correction_point = self.get_constant_point(ctx, &correction.to_affine())?;
         Ok(self.add(ctx, &acc.unwrap(), &correction_point)?)
     }
+    /// Single term scalar multiplication without buckets or memory checking.
+    /// Starting from the aux generator each bit `b` of the canonical windows
+    /// maps the accumulator to `2 * acc + aux + b * point` in a single step
+    fn mul(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let max = scalar_max::<F, App::Scalar>();
+        let number_of_windows = number_of_windows::<F, App::Scalar>(self.window());
+        let windows = self.decompose(ctx, scalar, &max, number_of_windows)?;
+        let aux_generator = self.get_constant_point(ctx, &self.aux_generator())?;
+        let shifted = self.add(ctx, &aux_generator, point)?;
+        let acc = self.double_add(ctx, &aux_generator, &shifted, &windows)?;
+        let correction_point = self.single_term_correction_point();
+        let correction_point = self.get_constant_point(ctx, &correction_point)?;
+        self.add(ctx, &acc, &correction_point)
+    }
     fn advice_columns(&self) -> Vec<Column<Advice>>;
     fn fixed_colmns(&self) -> Vec<Column<Fixed>>;
+    fn instance_column(&self) -> Column<Instance>;
     fn get_constant(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -90,9 +361,7 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         ctx: &mut RegionCtx<'_, F>,
         point: &App,
     ) -> Result<AssignedPoint<App>, Error> {
-        let coordianates = point.coordinates().unwrap();
-        let x = coordianates.x().clone();
-        let y = coordianates.y().clone();
+        let (x, y) = point_coords(*point);
         let x = self.get_constant(ctx, x)?;
         let y = self.get_constant(ctx, y)?;
         Ok(AssignedPoint::new(x, y))
@@ -111,16 +380,25 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         ctx.equal(a.x.cell(), b.x.cell())?;
         ctx.equal(a.y.cell(), b.y.cell())
     }
+    /// Adds points using incomplete formulas unless complete addition is
+    /// configured
     fn add(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         a: &AssignedPoint<App>,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error>;
+    /// Adds points with complete formulas. Identity is encoded as `(0, 0)`
+    fn add_complete(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
     fn rw_add(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        address: &Value<F>,
+        address: &AssignedValue<F>,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error>;
     fn read_add(
@@ -129,6 +407,26 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         address: &AssignedValue<F>,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error>;
+    fn initial_buckets(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+    ) -> Result<Vec<AssignedPoint<App>>, Error> {
+        let buckets: Vec<App> = self.gen_initial_buckets();
+        buckets
+            .iter()
+            .map(|point| self.get_constant_point(ctx, point))
+            .collect::<Result<Vec<_>, _>>()
+    }
+    /// Writes the initial buckets at their addresses
+    fn reset_buckets(&mut self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
+        let buckets = self.initial_buckets(ctx)?;
+        for (address, bucket) in buckets.iter().enumerate() {
+            let address = F::from(address as u64);
+            let address = self.get_constant(ctx, address)?;
+            self.write_point(ctx, &address, bucket)?;
+        }
+        Ok(())
+    }
     fn write_point(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
@@ -149,7 +447,108 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         &self,
         ctx: &mut RegionCtx<'_, F>,
         scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Decomposes the value into windows most significant first such that it
+    /// is at most `max`. Recomposition and the bound are constrained here while
+    /// windows themselves are range checked where they are consumed
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_windows: usize,
     ) -> Result<Vec<AssignedValue<F>>, Error>;
+    /// Decomposes the scalar into canonical windows which are range checked
+    /// where they are consumed as bucket addresses
+    fn decompose_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let max = scalar_max::<F, App::Scalar>();
+        self.decompose(ctx, scalar, &max, self.number_of_rounds())
+    }
+    /// Splits the scalar as `k = (-1)^s1 * k1 + (-1)^s2 * lambda * k2 mod n`
+    /// and returns windows of `k1` and `k2` along with the signs. Windows of
+    /// `k` and of the quotient are range checked here
+    fn decompose_glv(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<[(Vec<AssignedValue<F>>, AssignedValue<F>); 2], Error> {
+        let glv = self.glv().expect("glv is not configured");
+        let number_of_rounds = self.number_of_rounds();
+        let split = scalar
+            .value()
+            .map(|scalar| glv.split(*scalar, number_of_rounds));
+        let [k1, k2, quotient, s1, s2, u, v, k_lo, carry] = self.glv_check(ctx, scalar, split)?;
+        let max = scalar_max::<F, App::Scalar>();
+        let scalar_windows = self.decompose(ctx, scalar, &max, glv.scalar_windows)?;
+        let k1_windows = self.decompose(ctx, &k1, &glv.half_max(), number_of_rounds)?;
+        let k2_windows = self.decompose(ctx, &k2, &glv.half_max(), number_of_rounds)?;
+        let quotient_windows =
+            self.decompose(ctx, &quotient, &glv.quotient_max(), glv.quotient_windows())?;
+        // the carry only needs to be small which already follows from the bound
+        self.decompose(ctx, &carry, &glv.carry_max(), glv.carry_windows())?;
+        for (windows, base, sum) in [
+            (&k2_windows, &glv.lambda, u),
+            (&quotient_windows, &glv.modulus, v),
+            (&scalar_windows, &BigUint::one(), k_lo),
+        ] {
+            let coefficients = glv.coefficients(base, windows.len());
+            let acc = self.weighted_sum(ctx, windows, &coefficients)?;
+            ctx.equal(acc.cell(), sum.cell())?;
+        }
+        Ok([(k1_windows, s1), (k2_windows, s2)])
+    }
+    /// Assigns the two rows of the decomposition check and returns
+    /// `[k1, k2, quotient, s1, s2, u, v, k_lo, carry]`
+    fn glv_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        split: Value<[F; 9]>,
+    ) -> Result<[AssignedValue<F>; 9], Error>;
+    /// Accumulates `sum(coefficient_i * window_i)` while range checking the
+    /// windows
+    fn weighted_sum(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+        coefficients: &[F],
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Maps the point to `(zeta * x, y)`
+    fn endomorphism(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    /// Recodes unsigned windows into signed digits and returns
+    /// `(magnitude, sign)` pairs most significant first. Magnitudes are bounded
+    /// by `2^(w-1)` here and are range checked as bucket addresses
+    fn recode_signed(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+    ) -> Result<Vec<(AssignedValue<F>, AssignedValue<F>)>, Error>;
+    /// Negates the point if the boolean `sign` is set
+    fn conditional_negate(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+        sign: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    /// Runs `acc = (acc + q) + acc` over the bits of the windows most
+    /// significant first where `q` is selected by the bit between the aux
+    /// generator and `shifted = aux + point`. Windows are range checked here
+    /// as they are recomposed from boolean bits
+    fn double_add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        acc: &AssignedPoint<App>,
+        shifted: &AssignedPoint<App>,
+        windows: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error>;
     fn all_zero(&self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
         let advice_columns = self.advice_columns();
         for column in advice_columns.into_iter() {
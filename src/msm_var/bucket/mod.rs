@@ -1,3 +1,4 @@
+pub mod generic;
 pub mod instructions;
 pub mod narrow;
 pub mod wide;
@@ -1,9 +1,16 @@
 use super::super::instructions::MSMGate;
 use super::config::VarMSMGateNarrow;
+use crate::error::{check_mixed, MsmError};
+use crate::memory::context::MemoryContext;
+use crate::msm_fix::{
+    config::FixMSMGate,
+    tests::{configure_fixed_gate, fixed_bases},
+};
+use crate::range::RangeTable;
 use crate::util::multiexp_naive_var;
-use crate::RegionCtx;
 use ff::Field;
 use ff::PrimeField;
+use ff::WithSmallOrderMulGroup;
 use group::Curve;
 use group::Group;
 use halo2::circuit::floor_planner::V1;
@@ -14,25 +21,56 @@ use halo2::{
     plonk::Error,
     plonk::{Circuit, ConstraintSystem},
 };
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 use std::marker::PhantomData;
 
-#[derive(Default, Clone, Debug)]
+/// Options of the gate and the msm a test runs
+#[derive(Clone, Debug)]
 struct Params {
     window: usize,
+    number_of_points: usize,
+    signed: bool,
+    glv: bool,
+    single_term: bool,
+    complete: bool,
+    /// Runs `msm_short` on scalars of the given bit length
+    number_of_bits: Option<usize>,
+    /// Fixed bases of `msm_mixed`, which isn't run without them
+    number_of_fixed: usize,
+    fixed_window: usize,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            window: 4,
+            number_of_points: 20,
+            signed: false,
+            glv: false,
+            single_term: false,
+            complete: false,
+            number_of_bits: None,
+            number_of_fixed: 0,
+            fixed_window: 4,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     msm_gate: VarMSMGateNarrow<F, App>,
+    fixed_gate: Option<FixMSMGate<F, App>>,
 }
 #[derive(Debug, Default)]
 struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     _marker: PhantomData<(F, App)>,
-    window: usize,
-    number_of_points: usize,
+    params: Params,
 }
-impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+impl<F: PrimeField + Ord + WithSmallOrderMulGroup<3>, App: CurveAffine<Base = F>> Circuit<F>
+    for MyCircuit<F, App>
+where
+    App::Scalar: WithSmallOrderMulGroup<3>,
+{
     type Config = TestConfig<F, App>;
     type FloorPlanner = V1;
     type Params = Params;
@@ -40,8 +78,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
     fn without_witnesses(&self) -> Self {
         Self {
             _marker: PhantomData,
-            window: self.window,
-            number_of_points: self.number_of_points,
+            params: self.params.clone(),
         }
     }
     fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
@@ -51,22 +88,46 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
         let a3 = meta.advice_column();
         let a4 = meta.advice_column();
         let constant = meta.fixed_column();
-        let range_table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
         let window = params.window;
-        let aux = App::CurveExt::random(OsRng).to_affine();
-        let msm_gate = VarMSMGateNarrow::configure(
+        let mut msm_gate = VarMSMGateNarrow::configure_default(
             meta,
-            a0,
-            a1,
-            a2,
-            a3,
-            a4,
+            &[a0, a1, a2, a3, a4],
             range_table,
             constant,
+            instance,
             window,
-            aux,
         );
-        Self::Config { msm_gate }
+        if params.signed {
+            msm_gate.configure_signed_digits(meta);
+        }
+        if params.glv {
+            msm_gate.configure_glv(meta);
+        }
+        if params.single_term {
+            msm_gate.configure_single_term(meta);
+        }
+        if params.complete {
+            msm_gate.configure_complete_addition(meta);
+        }
+        // fixed base gate shares the columns and the range table
+        let fixed_gate = (params.number_of_fixed > 0).then(|| {
+            configure_fixed_gate(
+                meta,
+                [a0, a1, a2, a3, a4],
+                range_table,
+                constant,
+                instance,
+                params.fixed_window,
+                &fixed_bases::<App>(params.number_of_fixed),
+                App::CurveExt::generator().to_affine(),
+            )
+        });
+        Self::Config {
+            msm_gate,
+            fixed_gate,
+        }
     }
     fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
         unreachable!();
@@ -78,76 +139,295 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
             };
         }
         let ly = &mut ly;
-        let rand_scalar = || App::Scalar::random(OsRng);
+        let rand_scalar = || match self.params.number_of_bits {
+            Some(number_of_bits) => {
+                let scalar = ((OsRng.next_u64() as u128) << 64) | OsRng.next_u64() as u128;
+                App::Scalar::from_u128(scalar >> (128 - number_of_bits))
+            }
+            None => App::Scalar::random(OsRng),
+        };
         let rand_point = || App::CurveExt::random(OsRng);
-        let number_of_points = self.number_of_points;
-        let offset = ly.assign_region(
+        let number_of_points = self.params.number_of_points;
+        let mut memory = MemoryContext::new(&cfg.msm_gate);
+        let (offset, saved_rows) = memory.assign_region(
+            ly,
+            &mut cfg.msm_gate,
             || "app",
-            |region| {
-                cfg.msm_gate.unassign_constants();
-                cfg.msm_gate.clear_rw();
-                let ctx = &mut RegionCtx::new(region);
-                let points: Vec<_> = (0..number_of_points)
+            |ctx, msm_gate| {
+                let mut points: Vec<_> = (0..number_of_points)
                     .map(|_| rand_point())
                     .collect::<Vec<_>>();
-                let scalars = (0..number_of_points)
+                let mut scalars = (0..number_of_points)
                     .map(|_| rand_scalar())
                     .collect::<Vec<_>>();
-                let res0 = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
-                let res0 = cfg.msm_gate.assign_point(ctx, &v!(res0))?;
+                // the identity along with `P, -P, P, P` sharing a scalar take
+                // every bucket through `P + O`, `P - P`, `O + P` and `P + P`
+                if self.params.complete {
+                    points[0] = App::CurveExt::identity();
+                    points[2] = -points[1];
+                    points[3] = points[1];
+                    points[4] = points[1];
+                    let scalar = scalars[1];
+                    scalars[2..5].fill(scalar);
+                }
+                let fixed_scalars = (0..self.params.number_of_fixed)
+                    .map(|_| rand_scalar())
+                    .collect::<Vec<_>>();
+                let all_points = fixed_bases::<App>(self.params.number_of_fixed)
+                    .iter()
+                    .map(|base| base.to_curve())
+                    .chain(points.iter().cloned())
+                    .collect::<Vec<_>>();
+                let all_scalars = fixed_scalars
+                    .iter()
+                    .chain(scalars.iter())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let res0 = multiexp_naive_var(&all_points[..], &all_scalars[..]).to_affine();
+                let res0 = msm_gate.assign_point(ctx, &v!(res0))?;
                 let points: Vec<_> = points
                     .iter()
                     .map(|point| {
                         let point = v!(point.to_affine());
-                        cfg.msm_gate.assign_point(ctx, &point)
+                        msm_gate.assign_point(ctx, &point)
                     })
                     .collect::<Result<Vec<_>, Error>>()?;
                 let scalars = scalars
                     .into_iter()
-                    .map(|scalar| v!(scalar))
-                    .collect::<Vec<_>>();
-                let res1 = cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?;
-                cfg.msm_gate.equal(ctx, &res0, &res1)?;
-                Ok(ctx.offset())
+                    .map(|scalar| msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let fixed_scalars = fixed_scalars
+                    .into_iter()
+                    .map(|scalar| msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let res1 = if let Some(fixed_gate) = &cfg.fixed_gate {
+                    msm_gate.msm_mixed(
+                        ctx,
+                        fixed_gate,
+                        &fixed_scalars[..],
+                        &points[..],
+                        &scalars[..],
+                    )?
+                } else if self.params.single_term {
+                    assert_eq!(number_of_points, 1);
+                    msm_gate.mul(ctx, &points[0], &scalars[0])?
+                } else if let Some(number_of_bits) = self.params.number_of_bits {
+                    msm_gate.msm_short(ctx, &points[..], &scalars[..], number_of_bits)?
+                } else {
+                    msm_gate.msm(ctx, &points[..], &scalars[..])?
+                };
+                msm_gate.equal(ctx, &res0, &res1)?;
+                if self.params.complete {
+                    let point = &points[1];
+                    let value = point.value()?;
+                    let identity = App::CurveExt::identity().to_affine();
+                    let identity = msm_gate.assign_point(ctx, &v!(identity))?;
+                    let neg = msm_gate.assign_point(ctx, &value.map(|p| -p))?;
+                    let dbl = value.map(|p| (p + p).to_affine());
+                    let dbl = msm_gate.assign_point(ctx, &dbl)?;
+                    let res = msm_gate.dbl(ctx, &identity)?;
+                    msm_gate.equal(ctx, &res, &identity)?;
+                    let res = msm_gate.dbl(ctx, point)?;
+                    msm_gate.equal(ctx, &res, &dbl)?;
+                    let res = msm_gate.add(ctx, point, point)?;
+                    msm_gate.equal(ctx, &res, &dbl)?;
+                    let res = msm_gate.add(ctx, point, &neg)?;
+                    msm_gate.equal(ctx, &res, &identity)?;
+                    let res = msm_gate.add(ctx, &identity, point)?;
+                    msm_gate.equal(ctx, &res, point)?;
+                }
+                Ok((ctx.offset(), ctx.constants().saved_rows()))
             },
         )?;
 
         let row_cost = offset / number_of_points;
-        let mem_cost = cfg.msm_gate.memory.timestamp() / number_of_points;
+        let number_of_queries = memory.memory().timestamp();
+        let mem_cost = cfg.msm_gate.memory.sorted_rows(number_of_queries) / number_of_points;
         println!(
-                "narrow bucket gate, window {}, # terms: {}, msm row cost: {}, mem row cost: {}, area cost: {}",
-                self.window,
-                self.number_of_points,
+                "narrow bucket gate, single term: {}, window {}, # terms: {}, msm row cost: {}, mem row cost: {}, area cost: {}, constant rows saved: {}",
+                self.params.single_term,
+                self.params.window,
+                self.params.number_of_points,
                 row_cost,
                 mem_cost,
                 5 * (row_cost+mem_cost),
+                saved_rows,
             );
-        cfg.msm_gate.layout_range_table(ly)?;
-        cfg.msm_gate.layout_sorted_rw(ly)?;
+        memory.layout(ly, &mut cfg.msm_gate)?;
+        if let Some(fixed_gate) = &cfg.fixed_gate {
+            fixed_gate.layout_point_table(ly)?;
+        }
         Ok(())
     }
 
     fn params(&self) -> Self::Params {
-        Params {
-            window: self.window,
-        }
+        self.params.clone()
+    }
+}
+
+fn run_test<F, App>(k: u32, params: Params) -> Result<MockProver<F>, Error>
+where
+    F: PrimeField + Ord + WithSmallOrderMulGroup<3>,
+    App: CurveAffine<Base = F>,
+    App::Scalar: WithSmallOrderMulGroup<3>,
+{
+    let circuit = MyCircuit::<F, App> {
+        _marker: PhantomData::<(F, App)>,
+        params,
+    };
+    MockProver::run(k, &circuit, vec![vec![]])
+}
+
+fn assert_satisfied<F, App>(k: u32, params: Params)
+where
+    F: PrimeField + Ord + WithSmallOrderMulGroup<3>,
+    App: CurveAffine<Base = F>,
+    App::Scalar: WithSmallOrderMulGroup<3>,
+{
+    match run_test::<F, App>(k, params.clone()) {
+        Ok(prover) => prover.assert_satisfied(),
+        Err(e) => panic!("{params:?}: {e:#?}"),
     }
 }
 
 #[test]
 fn test_bucket_narrow_msm_var() {
     use halo2::halo2curves::pasta::{EqAffine, Fq};
-    const K: u32 = 21;
-    let window = 8;
-    let circuit = MyCircuit::<Fq, EqAffine> {
-        _marker: PhantomData::<(Fq, EqAffine)>,
-        window,
+    let params = Params {
+        window: 8,
         number_of_points: 10000,
+        ..Default::default()
+    };
+    assert_satisfied::<Fq, EqAffine>(21, params);
+}
+
+#[test]
+fn test_bucket_narrow_msm_var_modes() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    for signed in [false, true] {
+        for glv in [false, true] {
+            for complete in [false, true] {
+                let params = Params {
+                    signed,
+                    glv,
+                    complete,
+                    ..Default::default()
+                };
+                assert_satisfied::<Fq, EqAffine>(K, params);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_bucket_narrow_msm_var_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    for (signed, complete) in [(false, false), (true, true)] {
+        let params = Params {
+            signed,
+            complete,
+            ..Default::default()
+        };
+        assert_satisfied::<Fp, Secp256r1Affine>(K, params);
+    }
+}
+
+#[test]
+fn test_bucket_narrow_msm_var_glv_signed() {
+    use halo2::halo2curves::pasta::{EpAffine, Fp};
+    let params = Params {
+        signed: true,
+        glv: true,
+        ..Default::default()
+    };
+    assert_satisfied::<Fp, EpAffine>(17, params);
+}
+
+#[test]
+fn test_bucket_narrow_single_term() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let params = Params {
+        number_of_points: 1,
+        single_term: true,
+        ..Default::default()
+    };
+    assert_satisfied::<Fq, EqAffine>(K, params.clone());
+    assert_satisfied::<Fp, Secp256r1Affine>(K, params);
+}
+
+#[test]
+fn test_bucket_narrow_msm_var_short() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    for signed in [false, true] {
+        let params = Params {
+            signed,
+            number_of_bits: Some(128),
+            ..Default::default()
+        };
+        assert_satisfied::<Fq, EqAffine>(17, params);
+    }
+}
+
+#[test]
+fn test_bucket_narrow_msm_mixed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    // the variable side runs an extra round in the signed mode
+    for signed in [false, true] {
+        let params = Params {
+            number_of_points: 10,
+            signed,
+            number_of_fixed: 10,
+            ..Default::default()
+        };
+        assert_satisfied::<Fq, EqAffine>(17, params);
+    }
+}
+
+#[test]
+fn test_bucket_narrow_msm_mixed_window_mismatch() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    let window = 4;
+    let params = Params {
+        window,
+        number_of_points: 10,
+        number_of_fixed: 10,
+        fixed_window: window - 1,
+        ..Default::default()
+    };
+    let e = match run_test::<Fq, EqAffine>(17, params) {
+        Ok(_) => panic!("mixed msm over different windows must fail"),
+        Err(e) => e,
+    };
+    assert!(matches!(e, Error::Synthesis));
+    assert_eq!(
+        check_mixed(window, window - 1, false),
+        Err(MsmError::WindowMismatch {
+            expected: window,
+            actual: window - 1
+        })
+    );
+}
+
+#[test]
+fn test_bucket_narrow_msm_mixed_glv() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    let params = Params {
+        number_of_points: 10,
+        glv: true,
+        number_of_fixed: 10,
+        ..Default::default()
     };
-    let public_inputs = vec![vec![]];
-    let prover = match MockProver::run(K, &circuit, public_inputs) {
-        Ok(prover) => prover,
-        Err(e) => panic!("{:#?}", e),
+    let e = match run_test::<Fq, EqAffine>(17, params) {
+        Ok(_) => panic!("mixed msm in the glv mode must fail"),
+        Err(e) => e,
     };
-    prover.assert_satisfied();
+    assert!(matches!(e, Error::Synthesis));
+    assert!(matches!(
+        check_mixed(4, 4, true),
+        Err(MsmError::UnsupportedMode { .. })
+    ));
 }
@@ -1,7 +1,9 @@
 use super::super::instructions::MSMGate;
 use super::config::VarMSMGateWide;
+use crate::memory::context::MemoryContext;
+use crate::range::RangeTable;
 use crate::util::multiexp_naive_var;
-use crate::RegionCtx;
+use crate::{derive_aux_generator, DEFAULT_AUX_GENERATOR_TAG};
 use ff::Field;
 use ff::PrimeField;
 use group::Curve;
@@ -17,9 +19,28 @@ use halo2::{
 use rand_core::OsRng;
 use std::marker::PhantomData;
 
-#[derive(Default, Clone, Debug)]
+/// Options of the gate and the msm a test runs
+#[derive(Clone, Debug)]
 struct Params {
     window: usize,
+    number_of_points: usize,
+    complete: bool,
+    single_term: bool,
+    packed: bool,
+    signed: bool,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            window: 4,
+            number_of_points: 20,
+            complete: false,
+            single_term: false,
+            packed: false,
+            signed: false,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -29,8 +50,7 @@ struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
 #[derive(Debug, Default)]
 struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     _marker: PhantomData<(F, App)>,
-    window: usize,
-    number_of_points: usize,
+    params: Params,
 }
 
 impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
@@ -41,8 +61,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
     fn without_witnesses(&self) -> Self {
         Self {
             _marker: PhantomData,
-            window: self.window,
-            number_of_points: self.number_of_points,
+            params: self.params.clone(),
         }
     }
     fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
@@ -56,25 +75,33 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
         let a7 = meta.advice_column();
         let a8 = meta.advice_column();
         let constant = meta.fixed_column();
-        let range_table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
         let window = params.window;
-        let aux = App::CurveExt::random(OsRng).to_affine();
-        let msm_gate = VarMSMGateWide::configure(
+        let aux_generator = derive_aux_generator(DEFAULT_AUX_GENERATOR_TAG);
+        let configure = if params.packed {
+            VarMSMGateWide::configure_packed
+        } else {
+            VarMSMGateWide::configure
+        };
+        let mut msm_gate = configure(
             meta,
-            a0,
-            a1,
-            a2,
-            a3,
-            a4,
-            a5,
-            a6,
-            a7,
-            a8,
+            &[a0, a1, a2, a3, a4, a5, a6, a7, a8],
             range_table,
             constant,
+            instance,
             window,
-            aux,
+            aux_generator,
         );
+        if params.complete {
+            msm_gate.configure_complete_addition(meta);
+        }
+        if params.single_term {
+            msm_gate.configure_single_term(meta);
+        }
+        if params.signed {
+            msm_gate.configure_signed_digits(meta);
+        }
         Self::Config { msm_gate }
     }
     fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
@@ -89,75 +116,165 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
         let ly = &mut ly;
         let rand_scalar = || App::Scalar::random(OsRng);
         let rand_point = || App::CurveExt::random(OsRng);
-        let number_of_points = self.number_of_points;
-        let offset = ly.assign_region(
+        let number_of_points = self.params.number_of_points;
+        let mut memory = MemoryContext::new(&cfg.msm_gate);
+        let (offset, saved_rows) = memory.assign_region(
+            ly,
+            &mut cfg.msm_gate,
             || "app",
-            |region| {
-                cfg.msm_gate.unassign_constants();
-                cfg.msm_gate.clear_rw();
-                let ctx = &mut RegionCtx::new(region);
-                let points: Vec<_> = (0..number_of_points)
+            |ctx, msm_gate| {
+                let mut points: Vec<_> = (0..number_of_points)
                     .map(|_| rand_point())
                     .collect::<Vec<_>>();
                 let scalars = (0..number_of_points)
-                    .map(|_| rand_scalar())
+                    .map(|i| {
+                        // zero scalars and repeated bases are only safe with complete addition
+                        if self.params.complete && i % 2 == 0 {
+                            App::Scalar::ZERO
+                        } else {
+                            rand_scalar()
+                        }
+                    })
                     .collect::<Vec<_>>();
+                if self.params.complete && number_of_points > 1 {
+                    points[1] = points[0];
+                }
                 let res0 = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
-                let res0 = cfg.msm_gate.assign_point(ctx, &v!(res0))?;
+                let res0 = msm_gate.assign_point(ctx, &v!(res0))?;
                 let points: Vec<_> = points
                     .iter()
                     .map(|point| {
                         let point = v!(point.to_affine());
-                        cfg.msm_gate.assign_point(ctx, &point)
+                        msm_gate.assign_point(ctx, &point)
                     })
                     .collect::<Result<Vec<_>, Error>>()?;
                 let scalars = scalars
                     .into_iter()
-                    .map(|scalar| v!(scalar))
-                    .collect::<Vec<_>>();
-                let res1 = cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?;
-                cfg.msm_gate.equal(ctx, &res0, &res1)?;
+                    .map(|scalar| msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let res1 = if self.params.single_term {
+                    assert_eq!(number_of_points, 1);
+                    msm_gate.mul(ctx, &points[0], &scalars[0])?
+                } else {
+                    msm_gate.msm(ctx, &points[..], &scalars[..])?
+                };
+                msm_gate.equal(ctx, &res0, &res1)?;
+
+                if self.params.complete {
+                    let zero = msm_gate.assign_scalar(ctx, &v!(App::Scalar::ZERO))?;
+                    let zeros = vec![zero; points.len()];
+                    let res0 = msm_gate.get_constant_point(ctx, &App::identity())?;
+                    let res1 = msm_gate.msm(ctx, &points[..], &zeros[..])?;
+                    msm_gate.equal(ctx, &res0, &res1)?;
+                }
 
-                Ok(ctx.offset())
+                Ok((ctx.offset(), ctx.constants().saved_rows()))
             },
         )?;
 
         let row_cost = offset / number_of_points;
-        let mem_cost = cfg.msm_gate.memory.timestamp() / number_of_points;
+        let number_of_queries = memory.memory().timestamp();
+        let mem_cost = cfg.msm_gate.memory.sorted_rows(number_of_queries) / number_of_points;
         println!(
-                "wide bucket gate, window {}, # terms: {}, msm row cost: {}, mem row cost: {}, area cost: {}",
-                self.window,
-                self.number_of_points,
+                "wide bucket gate, single term: {}, packed: {}, window {}, # terms: {}, msm row cost: {}, mem row cost: {}, area cost: {}, constant rows saved: {}",
+                self.params.single_term,
+                self.params.packed,
+                self.params.window,
+                self.params.number_of_points,
                 row_cost,
                 mem_cost,
                 9 * (row_cost+mem_cost),
+                saved_rows,
             );
 
-        cfg.msm_gate.layout_range_table(ly)?;
-        cfg.msm_gate.layout_sorted_rw(ly)?;
+        memory.layout(ly, &mut cfg.msm_gate)?;
         Ok(())
     }
     fn params(&self) -> Self::Params {
-        Params {
-            window: self.window,
-        }
+        self.params.clone()
     }
 }
 
+fn run_test<F: PrimeField + Ord, App: CurveAffine<Base = F>>(k: u32, params: Params) {
+    let circuit = MyCircuit::<F, App> {
+        _marker: PhantomData::<(F, App)>,
+        params: params.clone(),
+    };
+    let prover = match MockProver::run(k, &circuit, vec![vec![]]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{params:?}: {e:#?}"),
+    };
+    prover.assert_satisfied();
+}
+
 #[test]
 fn test_bucket_wide_msm_var() {
     use halo2::halo2curves::pasta::{EqAffine, Fq};
-    const K: u32 = 21;
-    let window = 9;
-    let circuit = MyCircuit::<Fq, EqAffine> {
-        _marker: PhantomData::<(Fq, EqAffine)>,
-        window,
+    let params = Params {
+        window: 9,
         number_of_points: 10000,
+        ..Default::default()
     };
-    let public_inputs = vec![vec![]];
-    let prover = match MockProver::run(K, &circuit, public_inputs) {
-        Ok(prover) => prover,
-        Err(e) => panic!("{:#?}", e),
+    run_test::<Fq, EqAffine>(21, params);
+}
+
+#[test]
+fn test_bucket_wide_msm_var_modes() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    for packed in [false, true] {
+        for signed in [false, true] {
+            for complete in [false, true] {
+                let params = Params {
+                    complete,
+                    packed,
+                    signed,
+                    ..Default::default()
+                };
+                run_test::<Fq, EqAffine>(K, params);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_bucket_wide_msm_var_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    for (signed, complete) in [(false, false), (true, true)] {
+        let params = Params {
+            complete,
+            signed,
+            ..Default::default()
+        };
+        run_test::<Fp, Secp256r1Affine>(K, params);
+    }
+}
+
+#[test]
+fn test_bucket_wide_single_term() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let params = Params {
+        number_of_points: 1,
+        single_term: true,
+        ..Default::default()
     };
-    prover.assert_satisfied();
+    run_test::<Fq, EqAffine>(K, params.clone());
+    run_test::<Fp, Secp256r1Affine>(K, params);
+}
+
+#[test]
+fn test_bucket_wide_msm_var_packed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    for number_of_points in [19, 20] {
+        let params = Params {
+            number_of_points,
+            complete: true,
+            packed: true,
+            ..Default::default()
+        };
+        run_test::<Fq, EqAffine>(17, params);
+    }
 }
@@ -0,0 +1,460 @@
+use super::config::{EagenMSMGate, OFFSET_BITS};
+use super::instructions::{MSMGate, RandomPoint};
+use crate::{
+    error::{invert, transpose, MsmError},
+    memory::context::MemoryGate,
+    util::scalar_to_native,
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::{Field, PrimeField};
+use halo2::{
+    circuit::{Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::{Advice, Column, Error, Fixed, Instance},
+};
+use num_bigint::BigUint;
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> EagenMSMGate<F, App> {
+    fn empty_second_phase(&self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
+        for column in [self.c0, self.c1, self.c2, self.c3, self.c4] {
+            ctx.empty(|| "second phase:", column.into())?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MSMGate<F, App> for EagenMSMGate<F, App> {
+    fn advice_columns(&self) -> Vec<Column<Advice>> {
+        vec![
+            self.a0, self.a1, self.a2, self.a3, self.a4, self.c0, self.c1, self.c2, self.c3,
+            self.c4,
+        ]
+    }
+    fn fixed_colmns(&self) -> Vec<Column<Fixed>> {
+        vec![self.constant]
+    }
+    fn instance_column(&self) -> Column<Instance> {
+        self.instance
+    }
+    fn get_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        match ctx.constants_mut().get(&scalar) {
+            Some(constant) => Ok(constant),
+            _ => {
+                ctx.enable(self.s_assign_constant)?;
+                ctx.fixed(|| "get constant: fix", self.constant, scalar)?;
+                ctx.empty(|| "get constant:", self.a0.into())?;
+                ctx.empty(|| "get constant:", self.a1.into())?;
+                ctx.empty(|| "get constant:", self.a2.into())?;
+                ctx.empty(|| "get constant:", self.a3.into())?;
+                let constant =
+                    ctx.advice(|| "get constant: constant", self.a4, Value::known(scalar))?;
+                self.empty_second_phase(ctx)?;
+                ctx.next();
+                ctx.constants_mut().insert(scalar, constant.clone());
+                Ok(constant)
+            }
+        }
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (x, y) = transpose(point.map(|c| {
+            Option::from(c.coordinates())
+                .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+                .ok_or(MsmError::InvalidPoint {
+                    context: "assign point",
+                })
+        }))?
+        .unzip();
+        let x_square = x * x;
+        let x_cube = x * x * x;
+        ctx.enable(self.s_point)?;
+        let x = ctx.advice(|| "assign x", self.a0, x)?;
+        let y = ctx.advice(|| "assign y", self.a1, y)?;
+        ctx.advice(|| "assign x^2", self.a2, x_square)?;
+        ctx.advice(|| "assign x^3", self.a3, x_cube)?;
+        ctx.empty(|| "assign point:", self.a4.into())?;
+        ctx.empty(|| "assign point:", self.constant.into())?;
+        self.empty_second_phase(ctx)?;
+        ctx.next();
+        Ok(AssignedPoint::new(x, y))
+    }
+    fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let t = a.x.value().zip(b.x.value()).map(|(a_x, b_x)| *b_x - *a_x);
+        let t = t * t;
+        let inverse_t = invert(t, MsmError::ExceptionalAddition { operation: "add" })?;
+        let (out_x, out_y) = (a + b)?;
+        ctx.enable(self.s_add)?;
+        ctx.empty(|| "add:", self.a0.into())?;
+        ctx.copy(|| "add: a_x", self.a1, &a.x)?;
+        ctx.copy(|| "add: a_y", self.a2, &a.y)?;
+        ctx.copy(|| "add: b_x", self.a3, &b.x)?;
+        ctx.copy(|| "add: b_y", self.a4, &b.y)?;
+        ctx.empty(|| "add:", self.constant.into())?;
+        self.empty_second_phase(ctx)?;
+        ctx.next();
+        ctx.empty(|| "add:", self.a0.into())?;
+        let out_x = ctx.advice(|| "add: out_x", self.a1, out_x)?;
+        let out_y = ctx.advice(|| "add: out_y", self.a2, out_y)?;
+        ctx.advice(|| "add: t", self.a3, t)?;
+        ctx.advice(|| "add: inverse_t", self.a4, inverse_t)?;
+        ctx.empty(|| "add:", self.constant.into())?;
+        self.empty_second_phase(ctx)?;
+        ctx.next();
+        Ok(AssignedPoint::new(out_x, out_y))
+    }
+    fn dbl(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (x, y) = point.coords().unzip();
+        let x_square = x * x;
+        let x_square_square = x_square * x_square;
+        let y_square = y * y;
+        let (out_x, out_y) = point.dbl()?;
+        ctx.enable(self.s_double)?;
+        ctx.copy(|| "double: x", self.a0, &point.x)?;
+        ctx.copy(|| "double: y", self.a1, &point.y)?;
+        ctx.advice(|| "double: x^2", self.a2, x_square)?;
+        ctx.advice(|| "double: x^4", self.a3, x_square_square)?;
+        ctx.empty(|| "double:", self.a4.into())?;
+        ctx.empty(|| "double:", self.constant.into())?;
+        self.empty_second_phase(ctx)?;
+        ctx.next();
+        ctx.empty(|| "double:", self.a0.into())?;
+        ctx.advice(|| "double: t", self.a1, y_square)?;
+        let out_x = ctx.advice(|| "double: out_x", self.a2, out_x)?;
+        let out_y = ctx.advice(|| "double: out_y", self.a3, out_y)?;
+        ctx.empty(|| "double:", self.a4.into())?;
+        ctx.empty(|| "double:", self.constant.into())?;
+        self.empty_second_phase(ctx)?;
+        ctx.next();
+        Ok(AssignedPoint::new(out_x, out_y))
+    }
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let scalar = transpose(scalar.map(scalar_to_native::<F, App::Scalar>))?;
+        let scalar = ctx.advice(|| "assign scalar", self.a0, scalar)?;
+        ctx.empty(|| "assign scalar:", self.a1.into())?;
+        ctx.empty(|| "assign scalar:", self.a2.into())?;
+        ctx.empty(|| "assign scalar:", self.a3.into())?;
+        ctx.empty(|| "assign scalar:", self.a4.into())?;
+        ctx.empty(|| "assign scalar:", self.constant.into())?;
+        self.empty_second_phase(ctx)?;
+        ctx.next();
+        Ok(scalar)
+    }
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_bits: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        self.decomposition
+            .assign(ctx, value, max, number_of_bits, &zero)
+    }
+    fn random_point(&self, ctx: &mut RegionCtx<'_, F>) -> Result<RandomPoint<F>, Error> {
+        let error = MsmError::UnluckyRandomness {
+            context: "random point",
+        };
+        let witness = transpose(self.randomness.map(|randomness| {
+            let (offset, x_a, y_a) = (0..1u64 << OFFSET_BITS)
+                .find_map(|offset| {
+                    let x = randomness + F::from(offset);
+                    let y_square = x.square() * x + App::a() * x + App::b();
+                    Option::<F>::from(y_square.sqrt()).map(|y| (offset, x, y))
+                })
+                .ok_or(e!(error))?;
+            let inverse = Option::<F>::from(y_a.double().invert()).ok_or(e!(error))?;
+            let m = (x_a.square() * F::from(3) + App::a()) * inverse;
+            let x_b = m.square() - x_a.double();
+            let y_b = y_a + m * (x_b - x_a);
+            let kappa = x_a * F::from(6) - m.square().double();
+            let bits = (0..OFFSET_BITS)
+                .map(|i| F::from((offset >> i) & 1))
+                .collect::<Vec<_>>();
+            Ok(([x_a, y_a, m, x_b, y_b, kappa], bits))
+        }))?;
+        let (values, bits) = witness.unzip();
+        let [x_a, y_a, m, x_b, y_b, kappa] = values.transpose_array();
+        let bits = bits.transpose_vec(OFFSET_BITS);
+        let empty_first_phase = |ctx: &mut RegionCtx<'_, F>| -> Result<(), Error> {
+            for column in [self.a0, self.a1, self.a2, self.a3, self.a4] {
+                ctx.empty(|| "random point:", column.into())?;
+            }
+            ctx.empty(|| "random point:", self.constant.into())?;
+            Ok(())
+        };
+        ctx.enable(self.s_random)?;
+        empty_first_phase(ctx)?;
+        let x_a = ctx.advice(|| "random point: x_A", self.c0, x_a)?;
+        let y_a = ctx.advice(|| "random point: y_A", self.c1, y_a)?;
+        let m = ctx.advice(|| "random point: m", self.c2, m)?;
+        let x_b = ctx.advice(|| "random point: x_B", self.c3, x_b)?;
+        let y_b = ctx.advice(|| "random point: y_B", self.c4, y_b)?;
+        ctx.next();
+        empty_first_phase(ctx)?;
+        for (column, bit) in [self.c0, self.c1, self.c2, self.c3]
+            .iter()
+            .zip(bits[..4].iter())
+        {
+            ctx.advice(|| "random point: offset bit", *column, *bit)?;
+        }
+        let kappa = ctx.advice(|| "random point: kappa", self.c4, kappa)?;
+        ctx.next();
+        empty_first_phase(ctx)?;
+        for (column, bit) in [self.c0, self.c1, self.c2, self.c3]
+            .iter()
+            .zip(bits[4..].iter())
+        {
+            ctx.advice(|| "random point: offset bit", *column, *bit)?;
+        }
+        ctx.empty(|| "random point:", self.c4.into())?;
+        ctx.next();
+        Ok(RandomPoint {
+            x_a,
+            y_a,
+            m,
+            x_b,
+            y_b,
+            kappa,
+        })
+    }
+    fn tangent_term(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        random_point: &RandomPoint<F>,
+        point: &AssignedPoint<App>,
+        negate: bool,
+    ) -> Result<AssignedValue<F>, Error> {
+        let sign = if negate { -F::ONE } else { F::ONE };
+        let t = transpose(
+            point
+                .coords()
+                .zip(random_point.x_a.value().copied())
+                .zip(random_point.y_a.value().copied())
+                .zip(random_point.m.value().copied())
+                .map(|((((x, y), x_a), y_a), m)| {
+                    let dx = x - x_a;
+                    Option::<F>::from((sign * y - y_a - m * dx).invert())
+                        .map(|inverse| dx * inverse)
+                        .ok_or(MsmError::UnluckyRandomness {
+                            context: "tangent term",
+                        })
+                }),
+        )?;
+        ctx.enable(self.s_term)?;
+        ctx.copy(|| "tangent term: x", self.a0, &point.x)?;
+        ctx.copy(|| "tangent term: y", self.a1, &point.y)?;
+        ctx.empty(|| "tangent term:", self.a2.into())?;
+        ctx.empty(|| "tangent term:", self.a3.into())?;
+        ctx.empty(|| "tangent term:", self.a4.into())?;
+        let t = ctx.advice(|| "tangent term: t", self.c0, t)?;
+        ctx.copy(|| "tangent term: x_A", self.c1, &random_point.x_a)?;
+        ctx.copy(|| "tangent term: y_A", self.c2, &random_point.y_a)?;
+        ctx.copy(|| "tangent term: m", self.c3, &random_point.m)?;
+        ctx.empty(|| "tangent term:", self.c4.into())?;
+        ctx.fixed(|| "tangent term: sign", self.constant, sign)?;
+        ctx.next();
+        Ok(t)
+    }
+    fn inner_product(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bits: &[AssignedValue<F>],
+        terms: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        assert_eq!(bits.len(), terms.len());
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        // accumulator starts from zero
+        ctx.empty(|| "inner product:", self.a0.into())?;
+        ctx.empty(|| "inner product:", self.a1.into())?;
+        ctx.empty(|| "inner product:", self.a2.into())?;
+        ctx.empty(|| "inner product:", self.a3.into())?;
+        ctx.empty(|| "inner product:", self.a4.into())?;
+        ctx.empty(|| "inner product:", self.constant.into())?;
+        ctx.empty(|| "inner product:", self.c0.into())?;
+        ctx.empty(|| "inner product:", self.c1.into())?;
+        ctx.empty(|| "inner product:", self.c2.into())?;
+        ctx.empty(|| "inner product:", self.c3.into())?;
+        let mut acc = ctx.copy(|| "inner product: acc", self.c4, &zero)?;
+        ctx.next();
+        for (bits, terms) in bits.chunks(4).zip(terms.chunks(4)) {
+            ctx.enable(self.s_inner)?;
+            let mut value = acc.value().copied();
+            for (i, (bit_column, term_column)) in [
+                (self.a0, self.c0),
+                (self.a1, self.c1),
+                (self.a2, self.c2),
+                (self.a3, self.c3),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                match (bits.get(i), terms.get(i)) {
+                    (Some(bit), Some(term)) => {
+                        ctx.copy(|| "inner product: bit", bit_column, bit)?;
+                        ctx.copy(|| "inner product: term", term_column, term)?;
+                        value = value + bit.value().copied() * term.value().copied();
+                    }
+                    _ => {
+                        // padding bits are fixed to zero
+                        ctx.copy(|| "inner product: bit", bit_column, &zero)?;
+                        ctx.empty(|| "inner product:", term_column.into())?;
+                    }
+                }
+            }
+            ctx.empty(|| "inner product:", self.a4.into())?;
+            ctx.empty(|| "inner product:", self.constant.into())?;
+            acc = ctx.advice(|| "inner product: acc", self.c4, value)?;
+            ctx.next();
+        }
+        Ok(acc)
+    }
+    fn divisor_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        random_point: &RandomPoint<F>,
+        a: &[Value<F>],
+        b: &[Value<F>],
+        sum: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        assert_eq!(a.len(), b.len());
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        let x_a = random_point.x_a.value().copied();
+        let x_b = random_point.x_b.value().copied();
+        // evaluations at `x_A` and then at `x_B` sharing the coefficients
+        let mut coefficients: Vec<(AssignedValue<F>, AssignedValue<F>)> = vec![];
+        let mut evaluations = vec![];
+        for (x, x_value) in [(&random_point.x_a, x_a), (&random_point.x_b, x_b)] {
+            ctx.empty(|| "divisor:", self.a0.into())?;
+            ctx.empty(|| "divisor:", self.a1.into())?;
+            ctx.empty(|| "divisor:", self.a2.into())?;
+            ctx.empty(|| "divisor:", self.a3.into())?;
+            ctx.empty(|| "divisor:", self.a4.into())?;
+            ctx.empty(|| "divisor:", self.constant.into())?;
+            for column in [self.c0, self.c1, self.c2, self.c3] {
+                ctx.copy(|| "divisor: zero", column, &zero)?;
+            }
+            ctx.copy(|| "divisor: x", self.c4, x)?;
+            ctx.next();
+            let mut values = [Value::known(F::ZERO); 4];
+            let mut last = vec![];
+            for k in (0..a.len()).rev() {
+                let [value_a, derivative_a, value_b, derivative_b] = values;
+                values = [
+                    value_a * x_value + a[k],
+                    derivative_a * x_value + value_a,
+                    value_b * x_value + b[k],
+                    derivative_b * x_value + value_b,
+                ];
+                ctx.enable(self.s_horner)?;
+                if coefficients.len() < a.len() {
+                    let a = ctx.advice(|| "divisor: a_k", self.a0, a[k])?;
+                    let b = ctx.advice(|| "divisor: b_k", self.a1, b[k])?;
+                    coefficients.push((a, b));
+                } else {
+                    let (a, b) = &coefficients[a.len() - 1 - k];
+                    ctx.copy(|| "divisor: a_k", self.a0, a)?;
+                    ctx.copy(|| "divisor: b_k", self.a1, b)?;
+                }
+                ctx.empty(|| "divisor:", self.a2.into())?;
+                ctx.empty(|| "divisor:", self.a3.into())?;
+                ctx.empty(|| "divisor:", self.a4.into())?;
+                ctx.empty(|| "divisor:", self.constant.into())?;
+                last = [self.c0, self.c1, self.c2, self.c3]
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(column, value)| ctx.advice(|| "divisor: evaluation", *column, *value))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                ctx.advice(|| "divisor: x", self.c4, x_value)?;
+                if k == 0 && !evaluations.is_empty() {
+                    // last row of the evaluation at `x_B`
+                    ctx.enable(self.s_divisor)?;
+                }
+                ctx.next();
+            }
+            evaluations.push((values, last));
+        }
+        let ((values_a, cells_a), (values_b, _)) = (evaluations[0].clone(), evaluations[1].clone());
+        let (y_a, y_b) = (
+            random_point.y_a.value().copied(),
+            random_point.y_b.value().copied(),
+        );
+        let f_a = values_a[0] + y_a * values_a[2];
+        let f_b = values_b[0] + y_b * values_b[2];
+        let error = MsmError::UnluckyRandomness {
+            context: "divisor evaluation",
+        };
+        let inverse_f_a = invert(f_a, e!(error))?;
+        let inverse_f_b = invert(f_b, error)?;
+        // 2y * (a'(x) + y * b'(x)) + (3x^2 + a) * b(x)
+        let log_derivative = |[_, derivative, b, db]: [Value<F>; 4], x: Value<F>, y: Value<F>| {
+            let three_x_2 = x.map(|x| x.square() * F::from(3) + App::a());
+            let two_y = y.map(|y| y.double());
+            two_y * (derivative + y * db) + three_x_2 * b
+        };
+        let g_a = log_derivative(values_a, x_a, y_a) * inverse_f_a;
+        let g_b = log_derivative(values_b, x_b, y_b) * inverse_f_b;
+        let empty_first_phase = |ctx: &mut RegionCtx<'_, F>| -> Result<(), Error> {
+            for column in [self.a0, self.a1, self.a2, self.a3, self.a4] {
+                ctx.empty(|| "divisor:", column.into())?;
+            }
+            ctx.empty(|| "divisor:", self.constant.into())?;
+            Ok(())
+        };
+        empty_first_phase(ctx)?;
+        for (column, cell) in [self.c0, self.c1, self.c2, self.c3]
+            .iter()
+            .zip(cells_a.iter())
+        {
+            ctx.copy(|| "divisor: evaluation at x_A", *column, cell)?;
+        }
+        ctx.copy(|| "divisor: x_A", self.c4, &random_point.x_a)?;
+        ctx.next();
+        empty_first_phase(ctx)?;
+        ctx.copy(|| "divisor: y_A", self.c0, &random_point.y_a)?;
+        ctx.copy(|| "divisor: y_B", self.c1, &random_point.y_b)?;
+        ctx.advice(|| "divisor: 1/f(A)", self.c2, inverse_f_a)?;
+        ctx.advice(|| "divisor: 1/f(B)", self.c3, inverse_f_b)?;
+        ctx.copy(|| "divisor: kappa", self.c4, &random_point.kappa)?;
+        ctx.next();
+        empty_first_phase(ctx)?;
+        ctx.advice(|| "divisor: g(A)", self.c0, g_a)?;
+        ctx.advice(|| "divisor: g(B)", self.c1, g_b)?;
+        ctx.copy(|| "divisor: sum", self.c2, sum)?;
+        ctx.empty(|| "divisor:", self.c3.into())?;
+        ctx.empty(|| "divisor:", self.c4.into())?;
+        ctx.next();
+        Ok(())
+    }
+}
+
+/// The divisor check makes no memory queries and looks up no tables
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MemoryGate<F> for EagenMSMGate<F, App> {
+    type Memory = ();
+    fn new_memory(&self) {}
+    fn replace_memory(&mut self, _: ()) -> Result<(), Error> {
+        Ok(())
+    }
+    fn layout_memory(&self, _: &mut impl Layouter<F>) -> Result<(), Error> {
+        Ok(())
+    }
+}
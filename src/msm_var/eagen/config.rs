@@ -0,0 +1,395 @@
+use crate::decomposition::Decomposition;
+use crate::{derive_aux_generator, DEFAULT_AUX_GENERATOR_TAG};
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::{
+        Advice, Challenge, Column, ConstraintSystem, Constraints, Expression, FirstPhase, Fixed,
+        Instance, Selector,
+    },
+    poly::Rotation,
+};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+/// Number of bits of the offset added to the challenge to find a random point
+pub(crate) const OFFSET_BITS: usize = 8;
+
+/// MSM gate that proves each doubling round `2 * Q_prev + sum(b_i * P_i) = Q`
+/// by a logarithmic derivative check of the divisor of
+/// `sum(b_i * [P_i]) + [2 * Q_prev] + [-Q]` at a random point. Divisor
+/// coefficients, points and scalar bits live in the first phase columns
+/// `a0..a4` while evaluations at the random point live in the second phase
+/// columns `c0..c4`.
+#[derive(Clone, Debug)]
+pub struct EagenMSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    pub(crate) a0: Column<Advice>,
+    pub(crate) a1: Column<Advice>,
+    pub(crate) a2: Column<Advice>,
+    pub(crate) a3: Column<Advice>,
+    pub(crate) a4: Column<Advice>,
+    pub(crate) c0: Column<Advice>,
+    pub(crate) c1: Column<Advice>,
+    pub(crate) c2: Column<Advice>,
+    pub(crate) c3: Column<Advice>,
+    pub(crate) c4: Column<Advice>,
+    pub(crate) constant: Column<Fixed>,
+    pub(crate) instance: Column<Instance>,
+    pub(crate) challenge: Challenge,
+    pub(crate) s_point: Selector,
+    pub(crate) s_add: Selector,
+    pub(crate) s_double: Selector,
+    pub(crate) s_assign_constant: Selector,
+    pub(crate) decomposition: Decomposition<F>,
+    pub(crate) s_random: Selector,
+    pub(crate) s_term: Selector,
+    pub(crate) s_inner: Selector,
+    pub(crate) s_horner: Selector,
+    pub(crate) s_divisor: Selector,
+    pub(crate) aux_generator: App,
+    pub(crate) correction_points: BTreeMap<usize, App>,
+    pub(crate) randomness: Value<F>,
+    pub(crate) _marker: PhantomData<(F, App)>,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> EagenMSMGate<F, App> {
+    /// Loads the challenge that the random point is derived from. Must be
+    /// called before `msm` in each synthesis pass
+    pub fn load_challenge(&mut self, ly: &impl Layouter<F>) {
+        self.randomness = ly.get_challenge(self.challenge);
+    }
+
+    /// `configure` with the default aux generator. The random point comes
+    /// from the challenge so it is the only point fixed at key generation
+    pub fn configure_default(
+        meta: &mut ConstraintSystem<F>,
+        a: [Column<Advice>; 5],
+        c: [Column<Advice>; 5],
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> Self {
+        let aux_generator = derive_aux_generator(DEFAULT_AUX_GENERATOR_TAG);
+        Self::configure(meta, a, c, constant, instance, aux_generator)
+    }
+
+    /// Columns in `a` are expected to be in the first phase and columns in
+    /// `c` in the second phase
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a: [Column<Advice>; 5],
+        c: [Column<Advice>; 5],
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+        aux_generator: App,
+    ) -> Self {
+        let [a0, a1, a2, a3, a4] = a;
+        let [c0, c1, c2, c3, c4] = c;
+        for column in a.iter().chain(c.iter()) {
+            meta.enable_equality(*column);
+        }
+        let challenge = meta.challenge_usable_after(FirstPhase);
+        let s_add = meta.selector();
+        let s_double = meta.selector();
+        let s_point = meta.selector();
+        let s_assign_constant = meta.selector();
+        meta.enable_equality(instance);
+        meta.create_gate("assign point", |meta| {
+            let s = meta.query_selector(s_point);
+            let x = meta.query_advice(a0, Rotation::cur());
+            let y = meta.query_advice(a1, Rotation::cur());
+            let x_2 = meta.query_advice(a2, Rotation::cur());
+            let x_3 = meta.query_advice(a3, Rotation::cur());
+            let expr_x_square = e!(x) * e!(x) - e!(x_2);
+            let expr_x_cube = e!(x_2) * e!(x) - e!(x_3);
+            let a = App::a();
+            let a = Expression::Constant(a);
+            let b = App::b();
+            let b = Expression::Constant(b);
+            let expr_assign = (x_3 + a * e!(x) + b) - e!(y) * e!(y);
+            Constraints::with_selector(
+                s,
+                [
+                    ("assign_x_square", expr_x_square),
+                    ("assign_x_cube", expr_x_cube),
+                    ("assign_assign", expr_assign),
+                ],
+            )
+        });
+        meta.create_gate("incomplete addition", |meta| {
+            let s = meta.query_selector(s_add);
+            let out_x = meta.query_advice(a1, Rotation::next());
+            let out_y = meta.query_advice(a2, Rotation::next());
+            let t = meta.query_advice(a3, Rotation::next());
+            let inverse_t = meta.query_advice(a4, Rotation::next());
+            let a_x = meta.query_advice(a1, Rotation::cur());
+            let a_y = meta.query_advice(a2, Rotation::cur());
+            let b_x = meta.query_advice(a3, Rotation::cur());
+            let b_y = meta.query_advice(a4, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            // t = (b_x - a_x) ^ 2
+            let expr_t = (e!(b_x) - e!(a_x)).square() - e!(t);
+            // 1/t * t = 1
+            let expr_inverse_t = e!(t) * e!(inverse_t) - e!(one);
+            // out_x + a_x + b_x * t = (b_y - a_y) ^ 2
+            let expr_x = (e!(out_x) + e!(a_x) + e!(b_x)) * e!(t) - (e!(b_y) - e!(a_y)).square();
+            // (out_y + a_y) * (b_x - a_x) = (b_y - a_y) * (a_x - out_x)
+            let expr_y = (e!(out_y) + e!(a_y)) * (e!(b_x) - e!(a_x))
+                - (e!(b_y) - e!(a_y)) * (e!(a_x) - e!(out_x));
+            Constraints::with_selector(
+                s,
+                [
+                    ("add_t", expr_t),
+                    ("add_inverse_t", expr_inverse_t),
+                    ("add_x", expr_x),
+                    ("add_y", expr_y),
+                ],
+            )
+        });
+        meta.create_gate("incomplete doubling", |meta| {
+            let s = meta.query_selector(s_double);
+            let y_2 = meta.query_advice(a1, Rotation::next());
+            let out_x = meta.query_advice(a2, Rotation::next());
+            let out_y = meta.query_advice(a3, Rotation::next());
+            let x = meta.query_advice(a0, Rotation::cur());
+            let y = meta.query_advice(a1, Rotation::cur());
+            let x_2 = meta.query_advice(a2, Rotation::cur());
+            let x_4 = meta.query_advice(a3, Rotation::cur());
+            let expr_x_square = e!(x).square() - e!(x_2);
+            let expr_x_square_square = e!(x_2).square() - e!(x_4);
+            let expr_y_square = e!(y).square() - e!(y_2);
+            let four_y_2 = e!(y_2) * F::from(4);
+            let two_x = e!(x) * F::from(2);
+            let nine_x_4 = e!(x_4) * F::from(9);
+            let three_x_2 = e!(x_2) * F::from(3);
+            let two_y = e!(y) * F::from(2);
+            let a = App::a();
+            // (3x^2 + a)^2 = 9x^4 + 6ax^2 + a^2
+            let slope_square = nine_x_4 + e!(x_2) * (a * F::from(6)) + Expression::Constant(a * a);
+            let slope = three_x_2 + Expression::Constant(a);
+            let expr_out_x = four_y_2 * (e!(out_x) + two_x) - slope_square;
+            let expr_out_y = two_y * (e!(out_y) + y) - slope * (x - e!(out_x));
+            Constraints::with_selector(
+                s,
+                [
+                    ("double_x_square", expr_x_square),
+                    ("double_x_square_square", expr_x_square_square),
+                    ("double_y_square", expr_y_square),
+                    ("double_out_x", expr_out_x),
+                    ("double_out_y", expr_out_y),
+                ],
+            )
+        });
+        meta.create_gate("assign constant", |meta| {
+            let s = meta.query_selector(s_assign_constant);
+            let advice = meta.query_advice(a4, Rotation::cur());
+            let constant = meta.query_fixed(constant, Rotation::cur());
+            let expr = advice - constant;
+            Constraints::with_selector(s, [("expr", expr)])
+        });
+        let decomposition = Decomposition::configure(
+            meta,
+            [a0, a1, a2, a3],
+            constant,
+            [a4, c0, c1, c2, c3, c4]
+                .map(|column| column.into())
+                .to_vec(),
+            // bits are binary so no range check is needed
+            None,
+            1,
+        );
+        let s_random = meta.selector();
+        // random point `A` with `x_A = challenge + offset` where the offset
+        // is small so that the prover has only a few choices. `B = -2A` is the
+        // third intersection of the tangent at `A` and `kappa = dm / w` where
+        // `w = dx / 2y` is the invariant differential
+        //
+        // | c0  | c1  | c2 | c3  | c4    |
+        // | x_A | y_A | m  | x_B | y_B   |
+        // | o_0 | o_1 | o_2| o_3 | kappa |
+        // | o_4 | o_5 | o_6| o_7 |       |
+        meta.create_gate("random point", |meta| {
+            let s = meta.query_selector(s_random);
+            let challenge = meta.query_challenge(challenge);
+            let x_a = meta.query_advice(c0, Rotation::cur());
+            let y_a = meta.query_advice(c1, Rotation::cur());
+            let m = meta.query_advice(c2, Rotation::cur());
+            let x_b = meta.query_advice(c3, Rotation::cur());
+            let y_b = meta.query_advice(c4, Rotation::cur());
+            let kappa = meta.query_advice(c4, Rotation::next());
+            let bits = [c0, c1, c2, c3]
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::next()))
+                .chain(
+                    [c0, c1, c2, c3]
+                        .iter()
+                        .map(|column| meta.query_advice(*column, Rotation(2))),
+                )
+                .collect::<Vec<_>>();
+            let one = Expression::Constant(F::ONE);
+            let a = Expression::Constant(App::a());
+            let b = Expression::Constant(App::b());
+            let offset = bits
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::ZERO), |acc, bit| {
+                    acc * F::from(2) + e!(bit)
+                });
+            let mut constraints = bits
+                .iter()
+                .map(|bit| ("random_offset_bit", e!(bit) * (e!(one) - e!(bit))))
+                .collect::<Vec<_>>();
+            let expr_x = e!(x_a) - (challenge + offset);
+            let expr_on_curve =
+                e!(x_a) * e!(x_a) * e!(x_a) + e!(a) * e!(x_a) + b - e!(y_a) * e!(y_a);
+            // 2 * y_A * m = 3 * x_A^2 + a
+            let expr_slope = e!(y_a) * e!(m) * F::from(2) - (e!(x_a) * e!(x_a) * F::from(3) + a);
+            let expr_x_b = e!(x_b) - (e!(m) * e!(m) - e!(x_a) * F::from(2));
+            let expr_y_b = y_b - (e!(y_a) + e!(m) * (x_b - e!(x_a)));
+            // dm / w = 6 * x_A - 2 * m^2
+            let expr_kappa = kappa - (x_a * F::from(6) - e!(m) * m * F::from(2));
+            constraints.extend([
+                ("random_x", expr_x),
+                ("random_on_curve", expr_on_curve),
+                ("random_slope", expr_slope),
+                ("random_x_b", expr_x_b),
+                ("random_y_b", expr_y_b),
+                ("random_kappa", expr_kappa),
+            ]);
+            Constraints::with_selector(s, constraints)
+        });
+        let s_term = meta.selector();
+        // `t = (x - x_A) / (sign * y - y_A - m * (x - x_A))` which is the
+        // contribution of the point `(x, sign * y)` to the log derivative of
+        // the tangent line at `A`
+        //
+        // | a0 | a1 | c0 | c1  | c2  | c3 | fixed |
+        // | x  | y  | t  | x_A | y_A | m  | sign  |
+        meta.create_gate("tangent term", |meta| {
+            let s = meta.query_selector(s_term);
+            let x = meta.query_advice(a0, Rotation::cur());
+            let y = meta.query_advice(a1, Rotation::cur());
+            let t = meta.query_advice(c0, Rotation::cur());
+            let x_a = meta.query_advice(c1, Rotation::cur());
+            let y_a = meta.query_advice(c2, Rotation::cur());
+            let m = meta.query_advice(c3, Rotation::cur());
+            let sign = meta.query_fixed(constant, Rotation::cur());
+            let dx = x - x_a;
+            let expr = t * (sign * y - y_a - m * e!(dx)) - dx;
+            Constraints::with_selector(s, [("tangent_term", expr)])
+        });
+        let s_inner = meta.selector();
+        // acc = acc_prev + sum(b_i * t_i)
+        //
+        // | a0 | a1 | a2 | a3 | c0 | c1 | c2 | c3 | c4  |
+        // | b0 | b1 | b2 | b3 | t0 | t1 | t2 | t3 | acc |
+        meta.create_gate("inner product", |meta| {
+            let s = meta.query_selector(s_inner);
+            let acc = meta.query_advice(c4, Rotation::cur());
+            let acc_prev = meta.query_advice(c4, Rotation::prev());
+            let sum = [(a0, c0), (a1, c1), (a2, c2), (a3, c3)].iter().fold(
+                acc_prev,
+                |sum, (bit, term)| {
+                    let bit = meta.query_advice(*bit, Rotation::cur());
+                    let term = meta.query_advice(*term, Rotation::cur());
+                    sum + bit * term
+                },
+            );
+            let expr = acc - sum;
+            Constraints::with_selector(s, [("inner_product", expr)])
+        });
+        let s_horner = meta.selector();
+        // evaluates `a(x)`, `a'(x)`, `b(x)` and `b'(x)` most significant
+        // coefficient first
+        //
+        // | a0  | a1  | c0   | c1    | c2   | c3    | c4 |
+        // | a_k | b_k | a(x) | a'(x) | b(x) | b'(x) | x  |
+        meta.create_gate("horner", |meta| {
+            let s = meta.query_selector(s_horner);
+            let x = meta.query_advice(c4, Rotation::cur());
+            let x_prev = meta.query_advice(c4, Rotation::prev());
+            let mut constraints = vec![("horner_x", e!(x) - x_prev)];
+            for (coefficient, value, derivative) in [(a0, c0, c1), (a1, c2, c3)] {
+                let coefficient = meta.query_advice(coefficient, Rotation::cur());
+                let value_prev = meta.query_advice(value, Rotation::prev());
+                let value = meta.query_advice(value, Rotation::cur());
+                let derivative_prev = meta.query_advice(derivative, Rotation::prev());
+                let derivative = meta.query_advice(derivative, Rotation::cur());
+                constraints.push((
+                    "horner_value",
+                    value - (e!(value_prev) * e!(x) + coefficient),
+                ));
+                constraints.push((
+                    "horner_derivative",
+                    derivative - (derivative_prev * e!(x) + value_prev),
+                ));
+            }
+            Constraints::with_selector(s, constraints)
+        });
+        let s_divisor = meta.selector();
+        // for `f = a(x) + y * b(x)` and `g = df / (f * w)` checks
+        // `2 * g(A) - 2 * g(B) + kappa * sum = 0` where
+        // `g = (2y * (a'(x) + y * b'(x)) + (3x^2 + a) * b(x)) / f`. First row is
+        // the last row of the evaluation at `B`
+        //
+        // | c0      | c1      | c2       | c3       | c4    |
+        // | a(x_B)  | a'(x_B) | b(x_B)   | b'(x_B)  | x_B   |
+        // | a(x_A)  | a'(x_A) | b(x_A)   | b'(x_A)  | x_A   |
+        // | y_A     | y_B     | 1/f(A)   | 1/f(B)   | kappa |
+        // | g(A)    | g(B)    | sum      |          |       |
+        meta.create_gate("divisor", |meta| {
+            let s = meta.query_selector(s_divisor);
+            let mut rows = (0..4).map(|rotation| {
+                [c0, c1, c2, c3, c4].map(|column| meta.query_advice(column, Rotation(rotation)))
+            });
+            let [a_b, da_b, b_b, db_b, x_b] = rows.next().unwrap();
+            let [a_a, da_a, b_a, db_a, x_a] = rows.next().unwrap();
+            let [y_a, y_b, inverse_f_a, inverse_f_b, kappa] = rows.next().unwrap();
+            let [g_a, g_b, sum, _, _] = rows.next().unwrap();
+            let one = Expression::Constant(F::ONE);
+            let a = Expression::Constant(App::a());
+            let mut constraints = vec![];
+            for (value, derivative, b, db, x, y, inverse_f, g) in [
+                (a_a, da_a, b_a, db_a, x_a, y_a, inverse_f_a, e!(g_a)),
+                (a_b, da_b, b_b, db_b, x_b, y_b, inverse_f_b, e!(g_b)),
+            ] {
+                let f = value + e!(y) * e!(b);
+                let numerator = e!(y) * F::from(2) * (derivative + e!(y) * db)
+                    + (e!(x) * x * F::from(3) + e!(a)) * b;
+                constraints.push(("divisor_nonzero", e!(f) * inverse_f - e!(one)));
+                constraints.push(("divisor_log_derivative", g * f - numerator));
+            }
+            let expr = (g_a - g_b) * F::from(2) + kappa * sum;
+            constraints.push(("divisor_check", expr));
+            Constraints::with_selector(s, constraints)
+        });
+        Self {
+            a0,
+            a1,
+            a2,
+            a3,
+            a4,
+            c0,
+            c1,
+            c2,
+            c3,
+            c4,
+            constant,
+            instance,
+            challenge,
+            s_point,
+            s_add,
+            s_double,
+            s_assign_constant,
+            decomposition,
+            s_random,
+            s_term,
+            s_inner,
+            s_horner,
+            s_divisor,
+            aux_generator,
+            correction_points: BTreeMap::new(),
+            randomness: Value::unknown(),
+            _marker: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::point_coords;
+use ff::Field;
+use group::Curve;
+use halo2::halo2curves::CurveAffine;
+
+fn trim<F: Field>(mut a: Vec<F>) -> Vec<F> {
+    while a.last() == Some(&F::ZERO) {
+        a.pop();
+    }
+    a
+}
+
+fn add<F: Field>(a: &[F], b: &[F]) -> Vec<F> {
+    let len = std::cmp::max(a.len(), b.len());
+    (0..len)
+        .map(|i| *a.get(i).unwrap_or(&F::ZERO) + *b.get(i).unwrap_or(&F::ZERO))
+        .collect()
+}
+
+fn mul<F: Field>(a: &[F], b: &[F]) -> Vec<F> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut res = vec![F::ZERO; a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            res[i + j] += *a * b;
+        }
+    }
+    res
+}
+
+/// Exact division, panics if there is a remainder
+fn div<F: Field>(a: &[F], b: &[F]) -> Vec<F> {
+    let mut a = trim(a.to_vec());
+    let b = trim(b.to_vec());
+    if a.is_empty() {
+        return vec![];
+    }
+    assert!(a.len() >= b.len());
+    let inverse_lead = b.last().unwrap().invert().unwrap();
+    let mut quotient = vec![F::ZERO; a.len() - b.len() + 1];
+    for i in (0..quotient.len()).rev() {
+        let coefficient = a[i + b.len() - 1] * inverse_lead;
+        quotient[i] = coefficient;
+        for (j, b) in b.iter().enumerate() {
+            a[i + j] -= coefficient * b;
+        }
+    }
+    assert!(a.iter().all(|a| *a == F::ZERO), "inexact division");
+    quotient
+}
+
+/// Function `a(x) + y * b(x)` on the curve with coefficients in increasing
+/// degree
+#[derive(Clone, Debug)]
+pub(crate) struct Divisor<F: Field> {
+    pub(crate) a: Vec<F>,
+    pub(crate) b: Vec<F>,
+}
+
+impl<F: Field> Divisor<F> {
+    fn line(a: Vec<F>, b: Vec<F>) -> Self {
+        Divisor { a, b }
+    }
+    fn mul<App: CurveAffine<Base = F>>(&self, other: &Self) -> Self {
+        // y^2 = x^3 + ax + b
+        let curve = [App::b(), App::a(), F::ZERO, F::ONE];
+        let a = add(
+            &mul(&self.a, &other.a),
+            &mul(&mul(&self.b, &other.b), &curve),
+        );
+        let b = add(&mul(&self.a, &other.b), &mul(&self.b, &other.a));
+        Divisor { a, b }
+    }
+
+    /// Finds the function with divisor `sum([P_i]) - n * [O]` where points
+    /// sum up to identity. Lines through the running sum are multiplied
+    /// together as in Miller's algorithm and verticals are divided out at
+    /// the end. Result is padded to `size` coefficients for both `a` and `b`
+    pub(crate) fn new<App: CurveAffine<Base = F>>(points: &[App], size: usize) -> Self {
+        let mut sum = App::identity();
+        let mut numerator = Divisor::line(vec![F::ONE], vec![]);
+        let mut denominator = vec![F::ONE];
+        for point in points.iter() {
+            assert!(bool::from(!point.is_identity()));
+            if bool::from(sum.is_identity()) {
+                sum = *point;
+                continue;
+            }
+            let next = (sum + *point).to_affine();
+            let (x0, y0) = point_coords(sum);
+            let (x1, y1) = point_coords(*point);
+            let line = if bool::from(next.is_identity()) {
+                // vertical line x - x0
+                Divisor::line(vec![-x0, F::ONE], vec![])
+            } else {
+                let slope = if sum == *point {
+                    (x0.square() * F::from(3) + App::a()) * (y0 + y0).invert().unwrap()
+                } else {
+                    (y1 - y0) * (x1 - x0).invert().unwrap()
+                };
+                let (x_next, _) = point_coords(next);
+                denominator = mul(&denominator, &[-x_next, F::ONE]);
+                // y - slope * x - (y0 - slope * x0)
+                Divisor::line(vec![slope * x0 - y0, -slope], vec![F::ONE])
+            };
+            numerator = numerator.mul::<App>(&line);
+            sum = next;
+        }
+        assert!(
+            bool::from(sum.is_identity()),
+            "points don't sum up to identity"
+        );
+        let pad = |mut coefficients: Vec<F>| {
+            assert!(coefficients.len() <= size);
+            coefficients.resize(size, F::ZERO);
+            coefficients
+        };
+        Divisor {
+            a: pad(div(&numerator.a, &denominator)),
+            b: pad(div(&numerator.b, &denominator)),
+        }
+    }
+}
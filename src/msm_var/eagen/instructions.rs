@@ -0,0 +1,291 @@
+use super::divisor::Divisor;
+use crate::{
+    error::{check_limbs, check_terms, MsmError},
+    point_coords,
+    util::{number_of_windows, scalar_max, short_scalar_max},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::PrimeField;
+use group::{Curve, Group};
+use halo2::{
+    circuit::Value,
+    halo2curves::CurveAffine,
+    plonk::{Advice, Column, Error, Fixed, Instance},
+};
+use num_bigint::BigUint;
+
+/// Random point `A` on the curve, third intersection `B = -2A` of the
+/// tangent at `A` with slope `m` and `kappa = dm / w`
+#[derive(Debug, Clone)]
+pub struct RandomPoint<F: PrimeField> {
+    pub(crate) x_a: AssignedValue<F>,
+    pub(crate) y_a: AssignedValue<F>,
+    pub(crate) m: AssignedValue<F>,
+    pub(crate) x_b: AssignedValue<F>,
+    pub(crate) y_b: AssignedValue<F>,
+    pub(crate) kappa: AssignedValue<F>,
+}
+
+pub trait MSMHelper<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    /// Correction point of the aux generator doubled `number_of_rounds` times
+    fn correction_point(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        number_of_rounds: usize,
+    ) -> Result<AssignedPoint<App>, Error>;
+    fn aux_generator(&self) -> App;
+    /// Rounds are binary so there is one round per scalar bit
+    fn number_of_rounds(&self) -> usize {
+        number_of_windows::<F, App::Scalar>(1)
+    }
+    /// Number of coefficients of each of `a` and `b` in the divisor
+    /// `a(x) + y * b(x)` of a round with `number_of_points` terms
+    fn divisor_size(&self, number_of_points: usize) -> usize {
+        // divisor has at most `number_of_points + 2` zeros
+        (number_of_points + 2) / 2 + 1
+    }
+}
+
+pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F, App> {
+    fn msm(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        let number_of_rounds = self.number_of_rounds();
+        let bits = scalars
+            .iter()
+            .map(|scalar| self.decompose_scalar(ctx, scalar))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_bits(ctx, points, &bits, number_of_rounds)
+    }
+    /// MSM of scalars that are declared to fit in `number_of_bits` bits such
+    /// as transcript challenges. Scalars are range constrained to the bound
+    /// and only one round per bit is run
+    fn msm_short(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+        number_of_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        let max = short_scalar_max::<F, App::Scalar>(number_of_bits)?;
+        let bits = scalars
+            .iter()
+            .map(|scalar| self.decompose(ctx, scalar, &max, number_of_bits))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_bits(ctx, points, &bits, number_of_bits)
+    }
+    /// MSM of scalars given as little endian limbs of `limb_bits` bits such
+    /// as recomposed range checked integers. Limbs are copied into their own
+    /// bit decompositions
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), limbs.len())?;
+        // bits are windows of a single bit
+        let number_of_limbs = check_limbs(limbs, limb_bits, 1)?;
+        let max = short_scalar_max::<F, App::Scalar>(limb_bits)?;
+        let bits = limbs
+            .iter()
+            .map(|limbs| {
+                let mut bits = vec![];
+                for limb in limbs.iter().rev() {
+                    bits.extend(self.decompose(ctx, limb, &max, limb_bits)?);
+                }
+                Ok(bits)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_bits(ctx, points, &bits, number_of_limbs * limb_bits)
+    }
+    /// Runs a divisor check for each round where bits are given most
+    /// significant first
+    fn msm_bits(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        bits: &[Vec<AssignedValue<F>>],
+        number_of_rounds: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let number_of_points = points.len();
+        let size = self.divisor_size(number_of_points);
+        let random_point = self.random_point(ctx)?;
+        let mut terms = points
+            .iter()
+            .map(|point| self.tangent_term(ctx, &random_point, point, false))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let one = self.get_constant(ctx, F::ONE)?;
+        let aux_generator = self.aux_generator();
+        let mut acc = self.get_constant_point(ctx, &aux_generator)?;
+        for round in 0..number_of_rounds {
+            let mut round_bits = bits
+                .iter()
+                .map(|bits| bits[round].clone())
+                .collect::<Vec<_>>();
+            // divisor of `sum(b_i * [P_i]) + [2 * Q_prev] + [-Q]`
+            let doubled = self.dbl(ctx, &acc)?;
+            let point_values = points
+                .iter()
+                .map(|point| point.value())
+                .collect::<Result<Vec<_>, MsmError>>()?;
+            let zeros = Value::from_iter(
+                point_values
+                    .into_iter()
+                    .zip(round_bits.iter())
+                    .map(|(point, bit)| point.zip(bit.value().copied())),
+            )
+            .zip(doubled.value()?)
+            .map(|(terms, doubled): (Vec<(App, F)>, App)| {
+                let mut zeros = terms
+                    .into_iter()
+                    .filter(|(_, bit)| *bit == F::ONE)
+                    .map(|(point, _)| point)
+                    .collect::<Vec<_>>();
+                zeros.push(doubled);
+                zeros
+            });
+            let next_value = zeros.as_ref().map(|zeros| {
+                zeros
+                    .iter()
+                    .fold(App::Curve::identity(), |acc, point| acc + point)
+                    .to_affine()
+            });
+            let next = self.assign_point(ctx, &next_value)?;
+            let (a, b) = zeros
+                .zip(next_value)
+                .map(|(mut zeros, next)| {
+                    zeros.push(-next);
+                    let divisor = Divisor::new(&zeros[..], size);
+                    (divisor.a, divisor.b)
+                })
+                .unzip();
+            let doubled_term = self.tangent_term(ctx, &random_point, &doubled, false)?;
+            let next_term = self.tangent_term(ctx, &random_point, &next, true)?;
+            terms.push(doubled_term);
+            terms.push(next_term);
+            round_bits.push(one.clone());
+            round_bits.push(one.clone());
+            let sum = self.inner_product(ctx, &round_bits, &terms)?;
+            terms.truncate(number_of_points);
+            let (a, b) = (a.transpose_vec(size), b.transpose_vec(size));
+            self.divisor_check(ctx, &random_point, &a, &b, &sum)?;
+            acc = next;
+        }
+        let correction_point = self.correction_point(ctx, number_of_rounds)?;
+        self.add(ctx, &acc, &correction_point)
+    }
+    fn advice_columns(&self) -> Vec<Column<Advice>>;
+    fn fixed_colmns(&self) -> Vec<Column<Fixed>>;
+    fn instance_column(&self) -> Column<Instance>;
+    fn get_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: F,
+    ) -> Result<AssignedValue<F>, Error>;
+    fn get_constant_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &App,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (x, y) = point_coords(*point);
+        let x = self.get_constant(ctx, x)?;
+        let y = self.get_constant(ctx, y)?;
+        Ok(AssignedPoint::new(x, y))
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    fn equal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<(), Error> {
+        ctx.equal(a.x.cell(), b.x.cell())?;
+        ctx.equal(a.y.cell(), b.y.cell())
+    }
+    fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    fn dbl(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Decomposes the value into boolean bits most significant first such
+    /// that it is at most `max`
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_bits: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error>;
+    /// Decomposes the scalar into canonical bits
+    fn decompose_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let max = scalar_max::<F, App::Scalar>();
+        self.decompose(ctx, scalar, &max, self.number_of_rounds())
+    }
+    /// Assigns the random point derived from the loaded challenge
+    fn random_point(&self, ctx: &mut RegionCtx<'_, F>) -> Result<RandomPoint<F>, Error>;
+    /// Contribution of the point, or its negation, to the log derivative of
+    /// the tangent line at the random point
+    fn tangent_term(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        random_point: &RandomPoint<F>,
+        point: &AssignedPoint<App>,
+        negate: bool,
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Accumulates `sum(b_i * t_i)` where bits are expected to be booleans
+    fn inner_product(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bits: &[AssignedValue<F>],
+        terms: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Assigns the divisor `a(x) + y * b(x)` with coefficients in increasing
+    /// degree and checks that its log derivative at the random point matches
+    /// the sum of tangent terms
+    fn divisor_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        random_point: &RandomPoint<F>,
+        a: &[Value<F>],
+        b: &[Value<F>],
+        sum: &AssignedValue<F>,
+    ) -> Result<(), Error>;
+    fn all_zero(&self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
+        let advice_columns = self.advice_columns();
+        for column in advice_columns.into_iter() {
+            ctx.empty(|| "zero", column.into())?;
+        }
+        let fixed_columns = self.fixed_colmns();
+        for column in fixed_columns.into_iter() {
+            ctx.empty(|| "zero", column.into())?;
+        }
+        ctx.next();
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod assignments;
+pub mod config;
+pub(crate) mod divisor;
+pub mod instructions;
+pub mod msm;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,33 @@
+use super::config::EagenMSMGate;
+use super::instructions::{MSMGate, MSMHelper};
+use crate::{AssignedPoint, RegionCtx};
+use ff::PrimeField;
+use group::{Curve, Group};
+use halo2::{halo2curves::CurveAffine, plonk::Error};
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MSMHelper<F, App> for EagenMSMGate<F, App> {
+    fn aux_generator(&self) -> App {
+        self.aux_generator
+    }
+    fn correction_point(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        number_of_rounds: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let point = match self.correction_points.get(&number_of_rounds) {
+            Some(point) => *point,
+            None => {
+                // accumulator starts from the aux generator and is doubled
+                // once in each round
+                let aux_generator: App::CurveExt = self.aux_generator.into();
+                let correction_point = (0..number_of_rounds)
+                    .fold(aux_generator, |acc, _| acc.double())
+                    .to_affine();
+                self.correction_points
+                    .insert(number_of_rounds, -correction_point);
+                -correction_point
+            }
+        };
+        self.get_constant_point(ctx, &point)
+    }
+}
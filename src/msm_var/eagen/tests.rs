@@ -0,0 +1,169 @@
+use super::config::EagenMSMGate;
+use super::instructions::MSMGate;
+use crate::msm::Msm;
+use crate::util::multiexp_naive_var;
+use crate::{point_coords, RegionCtx};
+use ff::Field;
+use ff::PrimeField;
+use group::Curve;
+use group::Group;
+use halo2::circuit::floor_planner::V1;
+use halo2::dev::MockProver;
+use halo2::{
+    circuit::{Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::Error,
+    plonk::{Circuit, ConstraintSystem, SecondPhase},
+};
+use rand_core::{OsRng, RngCore};
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    msm_gate: EagenMSMGate<F, App>,
+}
+// witnesses are sampled once since synthesis is run again in each phase
+#[derive(Debug)]
+struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    _marker: PhantomData<(F, App)>,
+    points: Vec<Value<App>>,
+    scalars: Vec<Value<App::Scalar>>,
+    result: Value<App>,
+    number_of_bits: Option<usize>,
+    instance: Vec<F>,
+}
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MyCircuit<F, App> {
+    fn rand(number_of_points: usize, number_of_bits: Option<usize>) -> Self {
+        let points = (0..number_of_points)
+            .map(|_| App::CurveExt::random(OsRng))
+            .collect::<Vec<_>>();
+        let scalars = (0..number_of_points)
+            .map(|_| match number_of_bits {
+                Some(number_of_bits) => {
+                    let scalar = ((OsRng.next_u64() as u128) << 64) | OsRng.next_u64() as u128;
+                    App::Scalar::from_u128(scalar >> (128 - number_of_bits))
+                }
+                None => App::Scalar::random(OsRng),
+            })
+            .collect::<Vec<_>>();
+        let result = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+        let (x, y) = point_coords(result);
+        Self {
+            _marker: PhantomData,
+            points: points
+                .iter()
+                .map(|point| Value::known(point.to_affine()))
+                .collect(),
+            scalars: scalars.into_iter().map(Value::known).collect(),
+            result: Value::known(result),
+            number_of_bits,
+            instance: vec![x, y],
+        }
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+    type Config = TestConfig<F, App>;
+    type FloorPlanner = V1;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            _marker: PhantomData,
+            points: vec![Value::unknown(); self.points.len()],
+            scalars: vec![Value::unknown(); self.scalars.len()],
+            result: Value::unknown(),
+            number_of_bits: self.number_of_bits,
+            instance: vec![],
+        }
+    }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let a = [(); 5].map(|_| meta.advice_column());
+        let c = [(); 5].map(|_| meta.advice_column_in(SecondPhase));
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let msm_gate = EagenMSMGate::configure_default(meta, a, c, constant, instance);
+        Self::Config { msm_gate }
+    }
+    fn synthesize(&self, mut cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
+        let ly = &mut ly;
+        let number_of_points = self.points.len();
+        cfg.msm_gate.load_challenge(ly);
+        let (offset, saved_rows, res) = ly.assign_region(
+            || "app",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let res0 = cfg.msm_gate.assign_point(ctx, &self.result)?;
+                let points: Vec<_> = self
+                    .points
+                    .iter()
+                    .map(|point| cfg.msm_gate.assign_point(ctx, point))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let scalars = self
+                    .scalars
+                    .iter()
+                    .map(|scalar| cfg.msm_gate.assign_scalar(ctx, scalar))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let res1 = match self.number_of_bits {
+                    Some(number_of_bits) => {
+                        cfg.msm_gate
+                            .msm_short(ctx, &points[..], &scalars[..], number_of_bits)?
+                    }
+                    None => cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?,
+                };
+                cfg.msm_gate.equal(ctx, &res0, &res1)?;
+                Ok((ctx.offset(), ctx.constants().saved_rows(), res1))
+            },
+        )?;
+        Msm::expose_point(&cfg.msm_gate, ly, &res, 0)?;
+
+        let row_cost = offset / number_of_points;
+        println!(
+            "eagen gate, # terms: {}, msm row cost: {}, area cost: {}, constant rows saved: {}",
+            number_of_points,
+            row_cost,
+            10 * row_cost,
+            saved_rows,
+        );
+        Ok(())
+    }
+}
+
+#[test]
+fn test_eagen_msm_var() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let circuit = MyCircuit::<Fq, EqAffine>::rand(100, None);
+    let public_inputs = vec![circuit.instance.clone()];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_eagen_msm_var_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let circuit = MyCircuit::<Fp, Secp256r1Affine>::rand(20, None);
+    let public_inputs = vec![circuit.instance.clone()];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_eagen_msm_var_short() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let circuit = MyCircuit::<Fq, EqAffine>::rand(20, Some(128));
+    let public_inputs = vec![circuit.instance.clone()];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
@@ -0,0 +1,282 @@
+use crate::{
+    point_coords,
+    util::{big_to_fe, decompose_u64, fe_to_big, modulus, number_of_windows},
+};
+use ff::{Field, PrimeField, WithSmallOrderMulGroup};
+use group::Curve;
+use halo2::{halo2curves::CurveAffine, plonk::Expression};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+/// One step of the extended euclidean algorithm on `(r_i, r_{i+1})` and
+/// `(t_i, t_{i+1})`
+fn euclid_step(r: &mut (BigInt, BigInt), t: &mut (BigInt, BigInt)) {
+    let q = &r.0 / &r.1;
+    let r_next = &r.0 - &q * &r.1;
+    let t_next = &t.0 - &q * &t.1;
+    r.0 = std::mem::replace(&mut r.1, r_next);
+    t.0 = std::mem::replace(&mut t.1, t_next);
+}
+
+/// Short basis `[(a_1, b_1), (a_2, b_2)]` of the lattice of `(a, b)` with
+/// `a + b * lambda = 0 mod n`, found with the extended euclidean algorithm
+fn lattice_basis(n: &BigUint, lambda: &BigUint) -> [(BigInt, BigInt); 2] {
+    let sqrt = BigInt::from(n.sqrt());
+    // r_i = s_i * n + t_i * lambda
+    let mut r = (BigInt::from(n.clone()), BigInt::from(lambda.clone()));
+    let mut t = (BigInt::zero(), BigInt::one());
+    while r.1 >= sqrt {
+        euclid_step(&mut r, &mut t);
+    }
+    // r.0 is the last remainder above sqrt(n)
+    let v1 = (r.1.clone(), -t.1.clone());
+    let v2 = (r.0.clone(), -t.0.clone());
+    euclid_step(&mut r, &mut t);
+    let v3 = (r.1, -t.1);
+    let norm = |(a, b): &(BigInt, BigInt)| a * a + b * b;
+    let v2 = if norm(&v2) <= norm(&v3) { v2 } else { v3 };
+    [v1, v2]
+}
+
+/// Constants of the GLV decomposition of a scalar as
+/// `k = (-1)^s1 * k1 + (-1)^s2 * lambda * k2 mod n` where `k1, k2` are about
+/// half size and `lambda * (x, y) = (zeta * x, y)`.
+///
+/// In circuit the relation is proven over integers as
+/// `k - (-1)^s1 * k1 - (-1)^s2 * lambda * k2 - (quotient - 2^(h+1)) * n = 0`
+/// by checking it both in the native field and modulo `2^low_bits`. Products
+/// with `lambda` and `n` are reduced modulo `2^low_bits` window by window.
+/// Layout of the check:
+///
+/// | a0 | a1 | a2 | a3       | a4    |
+/// | k  | k1 | k2 | quotient | s1    |
+/// | s2 | u  | v  | k_lo     | carry |
+///
+/// where `u`, `v` and `k_lo` are weighted sums of windows of `k2`,
+/// `quotient` and `k` accumulated as
+///
+/// | a0     | a1  | fixed       |
+/// | window | acc | coefficient |
+#[derive(Clone, Debug)]
+pub struct Glv<F: PrimeField> {
+    pub(crate) zeta: F,
+    pub(crate) lambda: BigUint,
+    pub(crate) modulus: BigUint,
+    pub(crate) basis: [(BigInt, BigInt); 2],
+    pub(crate) window: usize,
+    pub(crate) half_bits: usize,
+    pub(crate) scalar_windows: usize,
+    pub(crate) low_bits: usize,
+    pub(crate) carry_offset: BigUint,
+}
+
+impl<F: PrimeField> Glv<F> {
+    pub(crate) fn new<App: CurveAffine<Base = F>>(window: usize) -> Self
+    where
+        F: WithSmallOrderMulGroup<3>,
+        App::Scalar: WithSmallOrderMulGroup<3>,
+    {
+        assert!(
+            bool::from(App::a().is_zero()),
+            "endomorphism requires a = 0"
+        );
+        let zeta = F::ZETA;
+        let generator = App::generator();
+        let (x, y) = point_coords(generator);
+        let image = App::from_xy(zeta * x, y).unwrap();
+        // pick the cube root of unity in the scalar field that zeta acts as
+        let lambda = [App::Scalar::ZETA, App::Scalar::ZETA.square()]
+            .into_iter()
+            .find(|lambda| (generator * *lambda).to_affine() == image)
+            .expect("zeta is not an endomorphism of the curve");
+        let lambda = fe_to_big(lambda);
+        let native_bits = modulus::<F>().bits() as usize;
+        let modulus = modulus::<App::Scalar>();
+        let basis = lattice_basis(&modulus, &lambda);
+        let [(a1, b1), (a2, b2)] = &basis;
+        let half_bits =
+            std::cmp::max((a1.abs() + a2.abs()).bits(), (b1.abs() + b2.abs()).bits()) as usize;
+        let scalar_bits = modulus.bits() as usize;
+        // rounds with signed digits
+        let number_of_rounds = div_ceil!(half_bits + 1, window);
+        let quotient_windows = div_ceil!(half_bits + 2, window);
+        let scalar_windows = number_of_windows::<F, App::Scalar>(window);
+        // integer relation is bounded by 2^(scalar_bits + w * quotient_windows + 3)
+        // so it holds if it holds modulo 2^(low_bits) * native
+        let low_bits = scalar_bits + window * quotient_windows + 5 - native_bits;
+        assert!(window * number_of_rounds <= low_bits);
+        // each weighted sum is bounded by number of windows * 2^(w+1) * 2^low_bits
+        let carry_offset = (BigUint::from(number_of_rounds + quotient_windows + scalar_windows)
+            << (window + 1))
+            + 3usize;
+        let carry_windows = div_ceil!((&carry_offset * 2usize).bits() as usize, window);
+        assert!(
+            low_bits + window * carry_windows + 3 < native_bits,
+            "low limb check wraps around the native field"
+        );
+        Glv {
+            zeta,
+            lambda,
+            modulus,
+            basis,
+            window,
+            half_bits,
+            scalar_windows,
+            low_bits,
+            carry_offset,
+        }
+    }
+
+    /// Number of windows of `k1` and `k2`. Signed digits reserve one more bit
+    pub(crate) fn number_of_rounds(&self, signed: bool) -> usize {
+        let number_of_bits = if signed {
+            self.half_bits + 1
+        } else {
+            self.half_bits
+        };
+        div_ceil!(number_of_bits, self.window)
+    }
+
+    /// Upper bound of `k1` and `k2`
+    pub(crate) fn half_max(&self) -> BigUint {
+        (BigUint::one() << self.half_bits) - 1usize
+    }
+
+    fn quotient_offset(&self) -> BigUint {
+        BigUint::one() << (self.half_bits + 1)
+    }
+
+    pub(crate) fn quotient_max(&self) -> BigUint {
+        (BigUint::one() << (self.half_bits + 2)) - 1usize
+    }
+
+    pub(crate) fn quotient_windows(&self) -> usize {
+        div_ceil!(self.half_bits + 2, self.window)
+    }
+
+    pub(crate) fn carry_max(&self) -> BigUint {
+        &self.carry_offset * 2usize
+    }
+
+    pub(crate) fn carry_windows(&self) -> usize {
+        div_ceil!(self.carry_max().bits() as usize, self.window)
+    }
+
+    /// Weights `base * 2^(w * i) mod 2^low_bits` of windows most significant
+    /// first
+    pub(crate) fn coefficients(&self, base: &BigUint, number_of_windows: usize) -> Vec<F> {
+        let mask = (BigUint::one() << self.low_bits) - 1usize;
+        (0..number_of_windows)
+            .rev()
+            .map(|i| big_to_fe((base << (self.window * i)) & &mask))
+            .collect()
+    }
+
+    fn weighted_sum(&self, e: &BigUint, base: &BigUint, number_of_windows: usize) -> BigUint {
+        let mask = (BigUint::one() << self.low_bits) - 1usize;
+        decompose_u64(e, number_of_windows, self.window)
+            .into_iter()
+            .enumerate()
+            .map(|(i, window)| ((base << (self.window * i)) & &mask) * window)
+            .sum()
+    }
+
+    /// Witness of the decomposition as
+    /// `[k1, k2, quotient, s1, s2, u, v, k_lo, carry]`
+    pub(crate) fn split(&self, k: F, number_of_windows: usize) -> [F; 9] {
+        let k_big = fe_to_big(k);
+        let k = BigInt::from(k_big.clone());
+        let n = BigInt::from(self.modulus.clone());
+        let [(a1, b1), (a2, b2)] = &self.basis;
+        let det = a1 * b2 - a2 * b1;
+        let round = |numerator: BigInt| {
+            let (numerator, det) = if det.is_negative() {
+                (-numerator, -det.clone())
+            } else {
+                (numerator, det.clone())
+            };
+            (numerator * 2 + &det).div_floor(&(det * 2))
+        };
+        let c1 = round(b2 * &k);
+        let c2 = round(-(b1 * &k));
+        let k1 = &k - &c1 * a1 - &c2 * a2;
+        let k2 = -(&c1 * b1) - &c2 * b2;
+        let (quotient, rem) = (&k - &k1 - BigInt::from(self.lambda.clone()) * &k2).div_rem(&n);
+        assert!(rem.is_zero());
+        let quotient = (quotient + BigInt::from(self.quotient_offset()))
+            .to_biguint()
+            .unwrap();
+        let (s1, k1) = (k1.is_negative(), k1.magnitude().clone());
+        let (s2, k2) = (k2.is_negative(), k2.magnitude().clone());
+        assert!(k1 <= self.half_max() && k2 <= self.half_max());
+        let u = self.weighted_sum(&k2, &self.lambda, number_of_windows);
+        let v = self.weighted_sum(&quotient, &self.modulus, self.quotient_windows());
+        let k_lo = self.weighted_sum(&k_big, &BigUint::one(), self.scalar_windows);
+        let e = (self.quotient_offset() * &self.modulus) % (BigUint::one() << self.low_bits);
+        let signed = |e: &BigUint, sign: bool| {
+            let e = BigInt::from(e.clone());
+            if sign {
+                -e
+            } else {
+                e
+            }
+        };
+        let low = signed(&k1, s1) + signed(&u, s2) + BigInt::from(v.clone())
+            - BigInt::from(e)
+            - BigInt::from(k_lo.clone());
+        let (carry, rem) = low.div_rem(&(BigInt::one() << self.low_bits));
+        assert!(rem.is_zero());
+        let carry = (carry + BigInt::from(self.carry_offset.clone()))
+            .to_biguint()
+            .unwrap();
+        let bit = |sign: bool| if sign { F::ONE } else { F::ZERO };
+        [
+            big_to_fe(k1),
+            big_to_fe(k2),
+            big_to_fe(quotient),
+            bit(s1),
+            bit(s2),
+            big_to_fe(u),
+            big_to_fe(v),
+            big_to_fe(k_lo),
+            big_to_fe(carry),
+        ]
+    }
+
+    /// Constraints of the decomposition check over two rows
+    /// `[k, k1, k2, quotient, s1]` and `[s2, u, v, k_lo, carry]`
+    pub(crate) fn constraints(
+        &self,
+        row0: [Expression<F>; 5],
+        row1: [Expression<F>; 5],
+    ) -> Vec<(&'static str, Expression<F>)> {
+        let [k, k1, k2, quotient, s1] = row0;
+        let [s2, u, v, k_lo, carry] = row1;
+        let one = Expression::Constant(F::ONE);
+        let lambda = Expression::Constant(big_to_fe::<F>(self.lambda.clone()));
+        let modulus = Expression::Constant(big_to_fe::<F>(self.modulus.clone()));
+        let quotient_offset = Expression::Constant(big_to_fe::<F>(self.quotient_offset()));
+        let e = (self.quotient_offset() * &self.modulus) % (BigUint::one() << self.low_bits);
+        let e = Expression::Constant(big_to_fe::<F>(e));
+        let carry_offset = Expression::Constant(big_to_fe::<F>(self.carry_offset.clone()));
+        let shift = Expression::Constant(big_to_fe::<F>(BigUint::one() << self.low_bits));
+        let sign1 = e!(one) - e!(s1) * F::from(2);
+        let sign2 = e!(one) - e!(s2) * F::from(2);
+        let expr_s1 = e!(s1) * (e!(one) - e!(s1));
+        let expr_s2 = e!(s2) * (e!(one) - e!(s2));
+        // k = (-1)^s1 * k1 + (-1)^s2 * lambda * k2 + (quotient - offset) * n
+        let expr_native = k
+            - e!(sign1) * e!(k1)
+            - e!(sign2) * lambda * k2
+            - (e!(quotient) - quotient_offset) * modulus;
+        // same relation modulo 2^low_bits
+        let expr_low = sign1 * k1 + sign2 * u + v - e - k_lo - (carry - carry_offset) * shift;
+        vec![
+            ("glv_sign_1", expr_s1),
+            ("glv_sign_2", expr_s2),
+            ("glv_native", expr_native),
+            ("glv_low", expr_low),
+        ]
+    }
+}
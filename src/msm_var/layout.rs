@@ -0,0 +1,84 @@
+use ff::PrimeField;
+use halo2::{
+    plonk::{Advice, Column, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+/// Cells of the point assignment: `x, y, x^2, x^3`
+pub(crate) const POINT_CELLS: usize = 4;
+/// Cells of the incomplete addition which doubles as a memory query:
+/// `address, a_x, a_y, out_x, out_y, b_x, b_y, t, 1/t`
+pub(crate) const ADD_CELLS: usize = 9;
+/// Cells of the incomplete doubling: `x, y, x^2, x^4, y^2, out_x, out_y`
+pub(crate) const DOUBLE_CELLS: usize = 7;
+/// Cells of the complete addition:
+/// `x_p, y_p, x_q, y_q, lambda, alpha, beta, gamma, delta, x_r, y_r`
+pub(crate) const COMPLETE_ADD_CELLS: usize = 11;
+/// Cells of a signed digit: `window, sign, carry, magnitude`
+pub(crate) const SIGNED_CELLS: usize = 4;
+/// Cells of a signed digit of the sliding window gates which also read the
+/// digit from a table: `window, sign, carry, magnitude, index`
+pub(crate) const SIGNED_INDEX_CELLS: usize = 5;
+/// Cells of a memory query of the bucket gates:
+/// `address, x_read, y_read, x_write, y_write`
+pub(crate) const QUERY_CELLS: usize = 5;
+/// Cells of a point table entry of the sliding window gates and of a read
+/// from it: `address, x, y`
+pub(crate) const TABLE_CELLS: usize = 3;
+/// Cells of a double and add step:
+/// `x_acc, y_acc, bit, lambda1, lambda2, inverse, x_shifted, y_shifted, acc_w`
+pub(crate) const DOUBLE_ADD_CELLS: usize = 9;
+/// Cells of the GLV decomposition check:
+/// `k, k1, k2, quotient, s1, s2, u, v, k_lo, carry`
+pub(crate) const GLV_CELLS: usize = 10;
+/// Cells of a sorted memory query:
+/// `address, x_read, y_read, x_write, y_write, timestamp`
+pub(crate) const SORTED_CELLS: usize = 6;
+
+/// Row major placement of gate cells over any number of advice columns. The
+/// `i`th cell of a gate lands on column `i % width` and `i / width` rows
+/// below the row its selector is enabled at. For example an addition takes
+/// two rows with 5 or 7 columns and a single row with 9 or more. Memory
+/// queries and table reads always sit on the first row of an addition next
+/// to the fixed timestamp or table offset so at least 5 columns are required
+#[derive(Clone, Debug)]
+pub struct Layout {
+    columns: Vec<Column<Advice>>,
+}
+
+impl Layout {
+    pub fn new(columns: &[Column<Advice>]) -> Self {
+        assert!(columns.len() >= 5, "at least 5 advice columns are required");
+        Self {
+            columns: columns.to_vec(),
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+    pub fn columns(&self) -> &[Column<Advice>] {
+        &self.columns
+    }
+    /// Column of the `i`th cell
+    pub fn column(&self, i: usize) -> Column<Advice> {
+        self.columns[i % self.width()]
+    }
+    /// Row offset of the `i`th cell
+    pub fn row(&self, i: usize) -> usize {
+        i / self.width()
+    }
+    /// Number of rows a gate with `number_of_cells` cells spans
+    pub fn rows(&self, number_of_cells: usize) -> usize {
+        div_ceil!(number_of_cells, self.width())
+    }
+    /// Queries the `i`th cell of the gate enabled `rotation` rows away
+    pub(crate) fn query<F: PrimeField>(
+        &self,
+        meta: &mut VirtualCells<'_, F>,
+        i: usize,
+        rotation: i32,
+    ) -> Expression<F> {
+        let rotation = rotation + self.row(i) as i32;
+        meta.query_advice(self.column(i), Rotation(rotation))
+    }
+}
@@ -1,2 +1,5 @@
 pub mod bucket;
+pub mod eagen;
+pub mod glv;
+pub mod layout;
 pub mod sliding;
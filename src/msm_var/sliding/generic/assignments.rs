@@ -0,0 +1,465 @@
+use super::config::VarMSMGateGeneric;
+use crate::msm_var::layout::{
+    ADD_CELLS, COMPLETE_ADD_CELLS, DOUBLE_CELLS, GLV_CELLS, POINT_CELLS, SIGNED_INDEX_CELLS,
+    TABLE_CELLS,
+};
+use crate::msm_var::sliding::rw::Memory;
+use crate::{
+    complete::complete_add,
+    coords,
+    error::{invert, transpose, MsmError},
+    memory::context::MemoryGate,
+    msm_var::sliding::instructions::MSMGate,
+    range::Tag,
+    util::{scalar_to_native, signed_recoding},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    halo2curves::CurveAffine,
+    plonk::{Advice, Column, Error, Fixed, Instance},
+};
+use num_bigint::BigUint;
+
+/// Content of a gate cell
+enum Witness<'a, F: PrimeField> {
+    Empty,
+    Advice(Value<F>),
+    Copy(&'a AssignedValue<F>),
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> VarMSMGateGeneric<F, App> {
+    /// Assigns the cells of a gate row by row as placed by the layout and
+    /// fills the rest of the last row with zeros. `fixed` values go to the
+    /// first rows of the block
+    fn assign_cells(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        annotation: &str,
+        mut cells: Vec<Witness<'_, F>>,
+        fixed: &[F],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let width = self.layout.width();
+        let number_of_rows = self.layout.rows(cells.len());
+        cells.resize_with(number_of_rows * width, || Witness::Empty);
+        let mut assigned = vec![];
+        for (i, cell) in cells.into_iter().enumerate() {
+            let column = self.layout.column(i);
+            assigned.push(match cell {
+                Witness::Empty => ctx.empty(|| annotation, column.into())?,
+                Witness::Advice(value) => ctx.advice(|| annotation, column, value)?,
+                Witness::Copy(value) => ctx.copy(|| annotation, column, value)?,
+            });
+            if (i + 1) % width == 0 {
+                match fixed.get(self.layout.row(i)) {
+                    Some(value) => ctx.fixed(|| annotation, self.constant, *value)?,
+                    None => ctx.empty(|| annotation, self.constant.into())?,
+                };
+                ctx.next();
+            }
+        }
+        Ok(assigned)
+    }
+    /// Enables the table read at `address + offset` and bounds the address
+    /// unless it is a signed index, which the recoding bounds
+    fn enable_read(&self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
+        ctx.enable(self.s_query)?;
+        if self.s_signed.is_none() {
+            ctx.enable(self.s_range)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MSMGate<F, App>
+    for VarMSMGateGeneric<F, App>
+{
+    fn advice_columns(&self) -> Vec<Column<Advice>> {
+        self.layout.columns().to_vec()
+    }
+    fn fixed_colmns(&self) -> Vec<Column<Fixed>> {
+        vec![self.constant]
+    }
+    fn instance_column(&self) -> Column<Instance> {
+        self.instance
+    }
+    fn get_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        match ctx.constants_mut().get(&scalar) {
+            Some(constant) => Ok(constant),
+            _ => {
+                ctx.enable(self.s_assign_constant)?;
+                let cells = vec![Witness::Advice(Value::known(scalar))];
+                let constant = self.assign_cells(ctx, "get constant", cells, &[scalar])?;
+                let constant = constant[0].clone();
+                ctx.constants_mut().insert(scalar, constant.clone());
+                Ok(constant)
+            }
+        }
+    }
+    fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &Value<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        // identity is assigned as `(0, 0)` once complete addition is configured
+        let (x, y) = match self.s_point_complete {
+            Some(_) => coords(*point).unzip(),
+            None => transpose(point.map(|c| {
+                Option::from(c.coordinates())
+                    .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+                    .ok_or(MsmError::InvalidPoint {
+                        context: "assign point",
+                    })
+            }))?
+            .unzip(),
+        };
+        let x_square = x * x;
+        let x_cube = x * x * x;
+        ctx.enable(self.s_point_complete.unwrap_or(self.s_point))?;
+        let cells = vec![
+            Witness::Advice(x),
+            Witness::Advice(y),
+            Witness::Advice(x_square),
+            Witness::Advice(x_cube),
+        ];
+        debug_assert_eq!(cells.len(), POINT_CELLS);
+        let assigned = self.assign_cells(ctx, "assign point", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[0].clone(), assigned[1].clone()))
+    }
+    fn read_point(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        offset: F,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let a: Value<App> = self.memory.read(&address.value().copied(), offset)?;
+        let (a_x, a_y) = coords(a).unzip();
+        self.enable_read(ctx)?;
+        let cells = vec![
+            Witness::Copy(address),
+            Witness::Advice(a_x),
+            Witness::Advice(a_y),
+        ];
+        debug_assert_eq!(cells.len(), TABLE_CELLS);
+        let assigned = self.assign_cells(ctx, "read point", cells, &[offset])?;
+        Ok(AssignedPoint::new(assigned[1].clone(), assigned[2].clone()))
+    }
+    fn write_point(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: F,
+        offset: F,
+        point: &AssignedPoint<App>,
+    ) -> Result<(), Error> {
+        let coords = point.coords();
+        self.memory.write(address, offset, &coords)?;
+        let (x, y) = coords.unzip();
+        ctx.enable(self.s_table)?;
+        let cells = vec![Witness::Empty, Witness::Advice(x), Witness::Advice(y)];
+        debug_assert_eq!(cells.len(), TABLE_CELLS);
+        self.assign_cells(ctx, "write point", cells, &[address + offset])?;
+        Ok(())
+    }
+    fn add_complete(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let s_add_complete = self.s_add_complete.ok_or(MsmError::UnsupportedMode {
+            operation: "complete addition",
+        })?;
+        let witness = transpose(
+            a.coords()
+                .zip(b.coords())
+                .map(|(a, b)| complete_add::<App>(a, b)),
+        )?;
+        let aux = |i: usize| witness.map(|witness| witness[i]);
+        ctx.enable(s_add_complete)?;
+        let mut cells = vec![
+            Witness::Copy(&a.x),
+            Witness::Copy(&a.y),
+            Witness::Copy(&b.x),
+            Witness::Copy(&b.y),
+        ];
+        cells.extend((0..7).map(|i| Witness::Advice(aux(i))));
+        debug_assert_eq!(cells.len(), COMPLETE_ADD_CELLS);
+        let assigned = self.assign_cells(ctx, "add complete", cells, &[])?;
+        Ok(AssignedPoint::new(
+            assigned[9].clone(),
+            assigned[10].clone(),
+        ))
+    }
+    fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.add_complete(ctx, a, b);
+        }
+        let t = a.x.value().zip(b.x.value()).map(|(a_x, b_x)| *b_x - *a_x);
+        let t = t * t;
+        let inverse_t = invert(t, MsmError::ExceptionalAddition { operation: "add" })?;
+        let (out_x, out_y) = (a + b)?;
+        ctx.enable(self.s_add)?;
+        let cells = vec![
+            Witness::Empty,
+            Witness::Copy(&a.x),
+            Witness::Copy(&a.y),
+            Witness::Advice(out_x),
+            Witness::Advice(out_y),
+            Witness::Copy(&b.x),
+            Witness::Copy(&b.y),
+            Witness::Advice(t),
+            Witness::Advice(inverse_t),
+        ];
+        debug_assert_eq!(cells.len(), ADD_CELLS);
+        let assigned = self.assign_cells(ctx, "add", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[3].clone(), assigned[4].clone()))
+    }
+    fn read_add(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        address: &AssignedValue<F>,
+        offset: F,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            let a = self.read_point(ctx, address, offset)?;
+            return self.add_complete(ctx, b, &a);
+        }
+        let a: Value<App> = self.memory.read(&address.value().copied(), offset)?;
+        let out = (b + &a)?;
+        let (a_x, a_y) = coords(a).unzip();
+        let (out_x, out_y) = coords(out).unzip();
+        let t = b.x.value().map(|v| *v) - a_x;
+        let t = t * t;
+        let inverse_t = invert(
+            t,
+            MsmError::ExceptionalAddition {
+                operation: "read_add",
+            },
+        )?;
+        ctx.enable(self.s_add)?;
+        self.enable_read(ctx)?;
+        let cells = vec![
+            Witness::Copy(address),
+            Witness::Advice(a_x),
+            Witness::Advice(a_y),
+            Witness::Advice(out_x),
+            Witness::Advice(out_y),
+            Witness::Copy(&b.x),
+            Witness::Copy(&b.y),
+            Witness::Advice(t),
+            Witness::Advice(inverse_t),
+        ];
+        debug_assert_eq!(cells.len(), ADD_CELLS);
+        let assigned = self.assign_cells(ctx, "read add", cells, &[offset])?;
+        Ok(AssignedPoint::new(assigned[3].clone(), assigned[4].clone()))
+    }
+    fn dbl(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        if self.s_add_complete.is_some() {
+            return self.add_complete(ctx, point, point);
+        }
+        let (x, y) = point.coords().unzip();
+        let x_square = x * x;
+        let x_square_square = x_square * x_square;
+        let y_square = y * y;
+        let (out_x, out_y) = point.dbl()?;
+        ctx.enable(self.s_double)?;
+        let cells = vec![
+            Witness::Copy(&point.x),
+            Witness::Copy(&point.y),
+            Witness::Advice(x_square),
+            Witness::Advice(x_square_square),
+            Witness::Advice(y_square),
+            Witness::Advice(out_x),
+            Witness::Advice(out_y),
+        ];
+        debug_assert_eq!(cells.len(), DOUBLE_CELLS);
+        let assigned = self.assign_cells(ctx, "double", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[5].clone(), assigned[6].clone()))
+    }
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let scalar = transpose(scalar.map(scalar_to_native::<F, App::Scalar>))?;
+        let assigned =
+            self.assign_cells(ctx, "assign scalar", vec![Witness::Advice(scalar)], &[])?;
+        Ok(assigned[0].clone())
+    }
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_windows: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        self.decomposition
+            .assign(ctx, scalar, max, number_of_windows, &zero)
+    }
+    fn conditional_negate(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+        sign: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let s_negate = self.s_negate.ok_or(MsmError::UnsupportedMode {
+            operation: "conditional negation",
+        })?;
+        let y = point
+            .y
+            .value()
+            .zip(sign.value())
+            .map(|(y, sign)| if *sign == F::ONE { -*y } else { *y });
+        ctx.enable(s_negate)?;
+        let cells = vec![
+            Witness::Copy(&point.y),
+            Witness::Copy(sign),
+            Witness::Advice(y),
+        ];
+        let assigned = self.assign_cells(ctx, "negate", cells, &[])?;
+        Ok(AssignedPoint::new(point.x.clone(), assigned[2].clone()))
+    }
+    fn recode_signed(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let s_signed = self.s_signed.ok_or(MsmError::UnsupportedMode {
+            operation: "signed digits",
+        })?;
+        let half = F::from(1 << (self.window - 1));
+        let rows = Value::from_iter(windows.iter().map(|window| window.value().copied()))
+            .map(|windows: Vec<F>| signed_recoding::<F>(&windows[..], self.window))
+            .transpose_vec(windows.len());
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        let mut indices = vec![];
+        for (i, (window, row)) in windows.iter().zip(rows.iter()).enumerate() {
+            let index =
+                row.map(|[sign, _, magnitude]| half + magnitude - sign * magnitude.double());
+            ctx.enable(s_signed)?;
+            ctx.enable(self.s_range)?;
+            // the most significant digit doesn't carry out
+            let carry = if i == 0 {
+                Witness::Copy(&zero)
+            } else {
+                Witness::Advice(row.map(|row| row[1]))
+            };
+            let cells = vec![
+                Witness::Copy(window),
+                Witness::Advice(row.map(|row| row[0])),
+                carry,
+                Witness::Advice(row.map(|row| row[2])),
+                Witness::Advice(index),
+            ];
+            debug_assert_eq!(cells.len(), SIGNED_INDEX_CELLS);
+            let assigned = self.assign_cells(ctx, "recode signed", cells, &[])?;
+            indices.push(assigned[4].clone());
+        }
+        // no carry into the least significant digit
+        let cells = vec![Witness::Empty, Witness::Empty, Witness::Copy(&zero)];
+        self.assign_cells(ctx, "recode signed", cells, &[])?;
+        Ok(indices)
+    }
+    fn glv_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        split: Value<[F; 9]>,
+    ) -> Result<[AssignedValue<F>; 9], Error> {
+        let s_glv = self
+            .s_glv
+            .ok_or(MsmError::UnsupportedMode { operation: "glv" })?;
+        ctx.enable(s_glv)?;
+        let mut cells = vec![Witness::Copy(scalar)];
+        cells.extend(
+            split
+                .transpose_array()
+                .into_iter()
+                .map(|value: Value<F>| Witness::Advice(value)),
+        );
+        debug_assert_eq!(cells.len(), GLV_CELLS);
+        let assigned = self.assign_cells(ctx, "glv", cells, &[])?;
+        let [k1, k2, quotient, s1, s2, u, v, k_lo, carry] =
+            [1, 2, 3, 4, 5, 6, 7, 8, 9].map(|i| assigned[i].clone());
+        Ok([k1, k2, quotient, s1, s2, u, v, k_lo, carry])
+    }
+    fn weighted_sum(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+        coefficients: &[F],
+    ) -> Result<AssignedValue<F>, Error> {
+        let s_weighted = self
+            .s_weighted
+            .ok_or(MsmError::UnsupportedMode { operation: "glv" })?;
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        // accumulator starts from zero
+        self.assign_cells(
+            ctx,
+            "weighted sum",
+            vec![Witness::Empty, Witness::Copy(&zero)],
+            &[],
+        )?;
+        let mut acc = zero;
+        for (window, coefficient) in windows.iter().zip(coefficients.iter()) {
+            let value = acc
+                .value()
+                .zip(window.value())
+                .map(|(acc, window)| *acc + *coefficient * window);
+            ctx.enable(s_weighted)?;
+            ctx.enable(self.s_range)?;
+            let cells = vec![Witness::Copy(window), Witness::Advice(value)];
+            let assigned = self.assign_cells(ctx, "weighted sum", cells, &[*coefficient])?;
+            acc = assigned[1].clone();
+        }
+        Ok(acc)
+    }
+    fn endomorphism(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (s_endo, glv) = match (self.s_endo, self.glv.as_ref()) {
+            (Some(s_endo), Some(glv)) => (s_endo, glv),
+            _ => return Err(MsmError::UnsupportedMode { operation: "glv" }.into()),
+        };
+        let x = point.x.value().map(|x| *x * glv.zeta);
+        ctx.enable(s_endo)?;
+        let cells = vec![Witness::Copy(&point.x), Witness::Advice(x)];
+        let assigned = self.assign_cells(ctx, "endomorphism", cells, &[])?;
+        Ok(AssignedPoint::new(assigned[1].clone(), point.y.clone()))
+    }
+    fn layout_range_table(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range_table
+            .layout(ly, &[(Tag::Window, 1 << self.window)])
+    }
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MemoryGate<F> for VarMSMGateGeneric<F, App> {
+    type Memory = Memory<F>;
+    fn new_memory(&self) -> Memory<F> {
+        Memory::default()
+    }
+    fn replace_memory(&mut self, memory: Memory<F>) -> Result<Memory<F>, Error> {
+        Ok(std::mem::replace(&mut self.memory, memory))
+    }
+    fn layout_memory(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.layout_range_table(ly)
+    }
+}
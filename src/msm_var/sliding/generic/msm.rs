@@ -0,0 +1,34 @@
+use super::config::VarMSMGateGeneric;
+use crate::msm_var::{
+    glv::Glv,
+    sliding::{instructions::MSMHelper, rw::Memory},
+};
+use ff::PrimeField;
+use halo2::halo2curves::CurveAffine;
+use std::collections::BTreeMap;
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> MSMHelper<F, App>
+    for VarMSMGateGeneric<F, App>
+{
+    fn window(&self) -> usize {
+        self.window
+    }
+    fn signed(&self) -> bool {
+        self.s_signed.is_some()
+    }
+    fn complete(&self) -> bool {
+        self.s_add_complete.is_some()
+    }
+    fn glv(&self) -> Option<&Glv<F>> {
+        self.glv.as_ref()
+    }
+    fn aux_generator(&self) -> App {
+        self.aux_generator
+    }
+    fn memory_mut(&mut self) -> &mut Memory<F> {
+        &mut self.memory
+    }
+    fn correction_points_mut(&mut self) -> &mut BTreeMap<(usize, usize), App> {
+        &mut self.correction_points
+    }
+}
@@ -0,0 +1,193 @@
+use super::config::VarMSMGateGeneric;
+use crate::msm_var::sliding::instructions::MSMGate;
+use crate::range::RangeTable;
+use crate::util::multiexp_naive_var;
+use crate::RegionCtx;
+use ff::Field;
+use ff::PrimeField;
+use group::Curve;
+use group::Group;
+use halo2::dev::MockProver;
+use halo2::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::CurveAffine,
+    plonk::Error,
+    plonk::{Circuit, ConstraintSystem},
+};
+use rand_core::OsRng;
+use std::marker::PhantomData;
+
+#[derive(Default, Clone, Debug)]
+struct Params {
+    window: usize,
+    number_of_columns: usize,
+    signed: bool,
+    complete: bool,
+}
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    msm_gate: VarMSMGateGeneric<F, App>,
+}
+#[derive(Debug, Default)]
+struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    _marker: PhantomData<(F, App)>,
+    window: usize,
+    number_of_points: usize,
+    number_of_columns: usize,
+    signed: bool,
+    complete: bool,
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+    type Config = TestConfig<F, App>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = Params;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            _marker: PhantomData,
+            window: self.window,
+            number_of_points: self.number_of_points,
+            number_of_columns: self.number_of_columns,
+            signed: self.signed,
+            complete: self.complete,
+        }
+    }
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        let advice = (0..params.number_of_columns)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
+        let mut msm_gate = VarMSMGateGeneric::configure_default(
+            meta,
+            &advice[..],
+            range_table,
+            constant,
+            instance,
+            params.window,
+        );
+        if params.signed {
+            msm_gate.configure_signed_digits(meta);
+        }
+        if params.complete {
+            msm_gate.configure_complete_addition(meta);
+        }
+        Self::Config { msm_gate }
+    }
+    fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!();
+    }
+    fn synthesize(&self, mut cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
+        macro_rules! v {
+            ($e:expr) => {
+                Value::known($e)
+            };
+        }
+        let ly = &mut ly;
+        let rand_scalar = || App::Scalar::random(OsRng);
+        let rand_point = || App::CurveExt::random(OsRng);
+        let number_of_points = self.number_of_points;
+        let (offset, saved_rows) = ly.assign_region(
+            || "app",
+            |region| {
+                let ctx = &mut RegionCtx::new(region);
+                let points: Vec<_> = (0..number_of_points)
+                    .map(|_| rand_point())
+                    .collect::<Vec<_>>();
+                let scalars = (0..number_of_points)
+                    .map(|_| rand_scalar())
+                    .collect::<Vec<_>>();
+                let res0 = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+                let res0 = cfg.msm_gate.assign_point(ctx, &v!(res0))?;
+                let points: Vec<_> = points
+                    .iter()
+                    .map(|point| {
+                        let point = v!(point.to_affine());
+                        cfg.msm_gate.assign_point(ctx, &point)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let scalars = scalars
+                    .into_iter()
+                    .map(|scalar| cfg.msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let res1 = cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?;
+                cfg.msm_gate.equal(ctx, &res0, &res1)?;
+                Ok((ctx.offset(), ctx.constants().saved_rows()))
+            },
+        )?;
+        println!(
+            "generic sliding gate, columns {}, window {}, # terms: {}, row cost: {}, area cost: {}, constant rows saved: {}",
+            self.number_of_columns,
+            self.window,
+            self.number_of_points,
+            offset / number_of_points,
+            self.number_of_columns * offset / number_of_points,
+            saved_rows,
+        );
+        cfg.msm_gate.layout_range_table(ly)?;
+        Ok(())
+    }
+    fn params(&self) -> Self::Params {
+        Params {
+            window: self.window,
+            number_of_columns: self.number_of_columns,
+            signed: self.signed,
+            complete: self.complete,
+        }
+    }
+}
+
+fn run_test<F: PrimeField + Ord, App: CurveAffine<Base = F>>(
+    number_of_columns: usize,
+    signed: bool,
+    complete: bool,
+) {
+    const K: u32 = 17;
+    let circuit = MyCircuit::<F, App> {
+        _marker: PhantomData::<(F, App)>,
+        window: 4,
+        number_of_points: 20,
+        number_of_columns,
+        signed,
+        complete,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_generic_msm_var() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    for number_of_columns in [5, 7, 9, 12] {
+        run_test::<Fq, EqAffine>(number_of_columns, false, false);
+    }
+}
+
+#[test]
+fn test_sliding_generic_msm_var_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    run_test::<Fp, Secp256r1Affine>(7, false, false);
+}
+
+#[test]
+fn test_sliding_generic_msm_var_signed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    for number_of_columns in [5, 7, 9] {
+        run_test::<Fq, EqAffine>(number_of_columns, true, false);
+    }
+}
+
+#[test]
+fn test_sliding_generic_complete() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    for number_of_columns in [5, 7, 12] {
+        run_test::<Fq, EqAffine>(number_of_columns, false, true);
+    }
+}
@@ -1,53 +1,241 @@
-use crate::{util::decompose, AssignedPoint, AssignedValue, RegionCtx};
+use crate::{
+    error::{check_limbs, check_mixed, check_terms, MsmError},
+    msm_fix::config::FixMSMGate,
+    msm_var::{glv::Glv, sliding::rw::Memory},
+    point_coords,
+    util::{big_to_fe, number_of_signed_windows, number_of_windows, scalar_max, short_scalar_max},
+    AssignedPoint, AssignedValue, RegionCtx,
+};
 use ff::PrimeField;
+use group::{Curve, Group};
 use halo2::{
     circuit::{Layouter, Value},
     halo2curves::CurveAffine,
-    plonk::{Advice, Column, Error, Fixed},
+    plonk::{Advice, Column, Error, Fixed, Instance},
 };
+use num_bigint::BigUint;
+use num_traits::One;
+use std::collections::BTreeMap;
 
 pub trait MSMHelper<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     fn window(&self) -> usize;
-    fn correction_point(
+    /// Whether windows are recoded into signed digits
+    fn signed(&self) -> bool;
+    /// Whether table reads are added with complete formulas
+    fn complete(&self) -> bool;
+    /// Endomorphism decomposition if configured
+    fn glv(&self) -> Option<&Glv<F>>;
+    fn aux_generator(&self) -> App;
+    /// Tables written so far
+    fn memory_mut(&mut self) -> &mut Memory<F>;
+    /// Correction points derived so far keyed by the number of rounds and
+    /// the number of tables
+    fn correction_points_mut(&mut self) -> &mut BTreeMap<(usize, usize), App>;
+    /// Negation of the aux generator offsets of `number_of_points` tables
+    /// accumulated over `number_of_rounds`. Tables have no offsets with
+    /// complete additions
+    fn correction(&mut self, number_of_rounds: usize, number_of_points: usize) -> App {
+        if self.complete() {
+            return App::CurveExt::identity().to_affine();
+        }
+        let key = (number_of_rounds, number_of_points);
+        if let Some(point) = self.correction_points_mut().get(&key) {
+            return *point;
+        }
+        // table `i` is offset by `2^i * aux` and each round adds one entry of
+        // every table after shifting the accumulator by a window
+        let one = BigUint::one();
+        let k0 =
+            (0..number_of_rounds).fold(BigUint::one(), |k0, i| k0 | (&one << (i * self.window())));
+        let k1 = (&one << number_of_points) - 1usize;
+        let correction_point =
+            (-self.aux_generator() * big_to_fe::<App::Scalar>(k0 * k1)).to_affine();
+        self.correction_points_mut().insert(key, correction_point);
+        correction_point
+    }
+    /// Entries of a point table. Signed tables hold digits in
+    /// `[-2^(w-1), 2^(w-1)]`
+    fn table_size(&self) -> usize {
+        (1 << self.window()) + usize::from(self.signed())
+    }
+    fn number_of_rounds(&self) -> usize {
+        match self.glv() {
+            Some(glv) => glv.number_of_rounds(self.signed()),
+            None if self.signed() => number_of_signed_windows::<F, App::Scalar>(self.window()),
+            None => number_of_windows::<F, App::Scalar>(self.window()),
+        }
+    }
+    /// Number of rounds for scalars of at most `number_of_bits` bits
+    fn number_of_short_rounds(&self, number_of_bits: usize) -> usize {
+        if self.signed() {
+            div_ceil!(number_of_bits + 1, self.window())
+        } else {
+            div_ceil!(number_of_bits, self.window())
+        }
+    }
+}
+
+pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F, App> {
+    fn msm(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        number_of_points: usize,
-    ) -> Result<AssignedPoint<App>, Error>;
-    fn assign_table(
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        // with the endomorphism each term is split into two half size terms
+        // where signs are applied to the points before the table is built
+        let mut terms = vec![];
+        for (point, scalar) in points.iter().zip(scalars.iter()) {
+            if self.glv().is_some() {
+                let [(k1, s1), (k2, s2)] = self.decompose_glv(ctx, scalar)?;
+                let image = self.endomorphism(ctx, point)?;
+                terms.push((self.conditional_negate(ctx, point, &s1)?, k1));
+                terms.push((self.conditional_negate(ctx, &image, &s2)?, k2));
+            } else {
+                terms.push((point.clone(), self.decompose_scalar(ctx, scalar)?));
+            }
+        }
+        let number_of_rounds = self.number_of_rounds();
+        self.msm_windows(ctx, terms, None, number_of_rounds)
+    }
+    /// MSM of scalars that are declared to fit in `number_of_bits` bits such
+    /// as transcript challenges. Scalars are range constrained to the bound
+    /// and only the rounds that cover it are run
+    fn msm_short(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        points: &[Value<App>],
-    ) -> Result<Vec<AssignedPoint<App>>, Error>;
-    fn decompose_scalars(&self, scalars: &[Value<App::Scalar>]) -> Vec<Vec<Value<F>>> {
-        let number_of_rounds = div_ceil!(F::NUM_BITS as usize, self.window());
-        let scalars = scalars
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
+        number_of_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        if self.glv().is_some() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "short scalar msm with glv",
+            }
+            .into());
+        }
+        let max = short_scalar_max::<F, App::Scalar>(number_of_bits)?;
+        let number_of_rounds = self.number_of_short_rounds(number_of_bits);
+        let terms = points
             .iter()
-            .map(|scalar| {
-                let decomposed = scalar.map(|scalar| {
-                    let mut decomposed: Vec<F> = decompose(scalar, number_of_rounds, self.window());
-                    decomposed.reverse();
-                    decomposed
-                });
-                decomposed.transpose_vec(number_of_rounds)
+            .zip(scalars.iter())
+            .map(|(point, scalar)| {
+                let windows = self.decompose(ctx, scalar, &max, number_of_rounds)?;
+                Ok((point.clone(), windows))
             })
-            .collect::<Vec<_>>();
-        scalars
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(ctx, terms, None, number_of_rounds)
     }
-}
-
-pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F, App> {
-    fn msm(
+    /// MSM of scalars given as little endian limbs of `limb_bits` bits such
+    /// as recomposed range checked integers. Limbs are copied into their own
+    /// window decompositions so `limb_bits` must be a multiple of the window
+    fn msm_limbs(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+        limbs: &[Vec<AssignedValue<F>>],
+        limb_bits: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), limbs.len())?;
+        if self.glv().is_some() {
+            return Err(MsmError::UnsupportedMode {
+                operation: "limb scalar msm with glv",
+            }
+            .into());
+        }
+        let number_of_limbs = check_limbs(limbs, limb_bits, self.window())?;
+        let max = short_scalar_max::<F, App::Scalar>(limb_bits)?;
+        let windows_per_limb = limb_bits / self.window();
+        let mut number_of_rounds = number_of_limbs * windows_per_limb;
+        // zero window on top takes the carry of the signed recoding
+        let zero = if self.signed() {
+            number_of_rounds += 1;
+            Some(self.get_constant(ctx, F::ZERO)?)
+        } else {
+            None
+        };
+        let terms = points
+            .iter()
+            .zip(limbs.iter())
+            .map(|(point, limbs)| {
+                let mut windows = zero.iter().cloned().collect::<Vec<_>>();
+                for limb in limbs.iter().rev() {
+                    windows.extend(self.decompose(ctx, limb, &max, windows_per_limb)?);
+                }
+                Ok((point.clone(), windows))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(ctx, terms, None, number_of_rounds)
+    }
+    /// Computes `sum a_i * G_i + sum b_j * P_j` where `G_i` are the bases of
+    /// the fixed base gate and `P_j` are variable points. Both sides share a
+    /// single doubling chain and a merged correction point so the gates
+    /// must be configured with the same window. Fixed scalars are decomposed
+    /// into unsigned windows over as many rounds as the variable side runs
+    fn msm_mixed(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        points: &[Value<App>],
-        scalars: &[Value<App::Scalar>],
+        fixed: &FixMSMGate<F, App>,
+        fixed_scalars: &[AssignedValue<F>],
+        points: &[AssignedPoint<App>],
+        scalars: &[AssignedValue<F>],
     ) -> Result<AssignedPoint<App>, Error> {
+        check_terms(points.len(), scalars.len())?;
+        check_mixed(self.window(), fixed.window, self.glv().is_some())?;
+        let table_size = fixed_scalars.len() << self.window();
+        if fixed.memory.len() != table_size {
+            return Err(MsmError::TableMismatch {
+                expected: table_size,
+                actual: fixed.memory.len(),
+            }
+            .into());
+        }
+        let number_of_rounds = self.number_of_rounds();
+        let terms = points
+            .iter()
+            .zip(scalars.iter())
+            .map(|(point, scalar)| Ok((point.clone(), self.decompose_scalar(ctx, scalar)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let max = scalar_max::<F, App::Scalar>();
+        let fixed_windows = fixed_scalars
+            .iter()
+            .map(|scalar| fixed.decompose(ctx, scalar, &max, number_of_rounds))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.msm_windows(
+            ctx,
+            terms,
+            Some((fixed, &fixed_windows[..])),
+            number_of_rounds,
+        )
+    }
+    /// Builds the tables of the points and accumulates them with their
+    /// windows most significant first, which are recoded into signed table
+    /// indices in the signed mode. Windows of the fixed bases if given are
+    /// read from the fixed table into the same accumulator
+    fn msm_windows(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: Vec<(AssignedPoint<App>, Vec<AssignedValue<F>>)>,
+        fixed: Option<(&FixMSMGate<F, App>, &[Vec<AssignedValue<F>>])>,
+        number_of_rounds: usize,
+    ) -> Result<AssignedPoint<App>, Error> {
+        let (points, scalars): (Vec<_>, Vec<_>) = terms
+            .into_iter()
+            .map(|(point, windows)| {
+                let indices = if self.signed() {
+                    self.recode_signed(ctx, &windows)?
+                } else {
+                    windows
+                };
+                Ok((point, indices))
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
         let number_of_points = points.len();
-        assert!(number_of_points > 0);
-        assert_eq!(number_of_points, scalars.len());
-        let _ = self.assign_table(ctx, points)?;
-        let scalars = self.decompose_scalars(scalars);
-        let number_of_rounds = div_ceil!(F::NUM_BITS as usize, self.window());
+        let table_offset = self.assign_table(ctx, &points)?;
         let mut acc = None;
         for round in 0..number_of_rounds {
             if round != 0 {
@@ -55,26 +243,38 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
                     acc = Some(self.dbl(ctx, &acc.unwrap())?)
                 }
             }
-            let mut offset = 0;
+            let mut offset = table_offset;
             for scalar in scalars.iter() {
                 acc = match &acc {
                     Some(acc) => {
                         Some(self.read_add(ctx, &scalar[round], F::from(offset as u64), &acc)?)
                     }
                     None => {
-                        assert!(offset == 0 && round == 0);
+                        assert!(offset == table_offset && round == 0);
                         Some(self.read_point(ctx, &scalar[round], F::from(offset as u64))?)
                     }
                 };
-                offset += 1 << self.window();
+                offset += self.table_size();
             }
+            if let Some((fixed, fixed_windows)) = fixed {
+                for (point_idx, windows) in fixed_windows.iter().enumerate() {
+                    acc = Some(fixed.read_add(ctx, point_idx, &windows[round], &acc.unwrap())?);
+                }
+            }
+        }
+        let mut correction = self
+            .correction(number_of_rounds, number_of_points)
+            .to_curve();
+        if let Some((fixed, _)) = fixed {
+            correction += fixed.correction(number_of_rounds)?;
         }
-        let correction_point = self.correction_point(ctx, number_of_points)?;
+        let correction_point = self.get_constant_point(ctx, &correction.to_affine())?;
         let res = self.add(ctx, &acc.unwrap(), &correction_point)?;
         Ok(res)
     }
     fn advice_columns(&self) -> Vec<Column<Advice>>;
     fn fixed_colmns(&self) -> Vec<Column<Fixed>>;
+    fn instance_column(&self) -> Column<Instance>;
     fn get_constant(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -85,9 +285,7 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         ctx: &mut RegionCtx<'_, F>,
         point: &App,
     ) -> Result<AssignedPoint<App>, Error> {
-        let coordianates = point.coordinates().unwrap();
-        let x = coordianates.x().clone();
-        let y = coordianates.y().clone();
+        let (x, y) = point_coords(*point);
         let x = self.get_constant(ctx, x)?;
         let y = self.get_constant(ctx, y)?;
         Ok(AssignedPoint::new(x, y))
@@ -106,16 +304,72 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         ctx.equal(a.x.cell(), b.x.cell())?;
         ctx.equal(a.y.cell(), b.y.cell())
     }
+    /// Adds points using incomplete formulas unless complete addition is
+    /// configured
     fn add(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         a: &AssignedPoint<App>,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error>;
+    /// Adds points with complete formulas. Identity is encoded as `(0, 0)`
+    fn add_complete(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedPoint<App>,
+        b: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    /// Writes `aux_i + j * P_i` for each point, or `j * P_i` with complete
+    /// additions. Signed tables hold `aux_i + d * P_i` at `2^(w-1) + d` rather
+    /// than negated entries so that every entry keeps its aux offset and the
+    /// correction point doesn't depend on the digits. Returns the address of
+    /// the first table
+    fn assign_table(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        points: &[AssignedPoint<App>],
+    ) -> Result<usize, Error> {
+        let table_size = self.table_size();
+        let table_offset = self.memory_mut().reserve(points.len() * table_size);
+        let middle = if self.signed() {
+            1 << (self.window() - 1)
+        } else {
+            0
+        };
+        let mut running_aux = if self.complete() {
+            App::CurveExt::identity().to_affine()
+        } else {
+            self.aux_generator()
+        };
+        for (i, point) in points.iter().enumerate() {
+            let aux: AssignedPoint<App> = self.get_constant_point(ctx, &running_aux)?;
+            let offset = F::from((table_offset + i * table_size) as u64);
+            let mut acc = aux.clone();
+            for j in middle..table_size {
+                let address = F::from(j as u64);
+                self.write_point(ctx, address, offset, &acc)?;
+                if j != table_size - 1 {
+                    acc = self.add(ctx, &acc, point)?;
+                }
+            }
+            // negative digits are written downwards from the aux entry
+            if middle != 0 {
+                let one = self.get_constant(ctx, F::ONE)?;
+                let negated = self.conditional_negate(ctx, point, &one)?;
+                let mut acc = aux;
+                for j in (0..middle).rev() {
+                    acc = self.add(ctx, &acc, &negated)?;
+                    self.write_point(ctx, F::from(j as u64), offset, &acc)?;
+                }
+            }
+            running_aux = running_aux.to_curve().double().to_affine();
+        }
+        Ok(table_offset)
+    }
     fn read_add(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        address: &Value<F>,
+        address: &AssignedValue<F>,
         offset: F,
         b: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error>;
@@ -129,7 +383,7 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
     fn read_point(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        address: &Value<F>,
+        address: &AssignedValue<F>,
         offset: F,
     ) -> Result<AssignedPoint<App>, Error>;
     fn dbl(
@@ -137,6 +391,102 @@ pub trait MSMGate<F: PrimeField + Ord, App: CurveAffine<Base = F>>: MSMHelper<F,
         ctx: &mut RegionCtx<'_, F>,
         point: &AssignedPoint<App>,
     ) -> Result<AssignedPoint<App>, Error>;
+    fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &Value<App::Scalar>,
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Decomposes the value into windows most significant first such that it
+    /// is at most `max`. Recomposition and the bound are constrained here while
+    /// windows themselves are range checked where they are consumed
+    fn decompose(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: &AssignedValue<F>,
+        max: &BigUint,
+        number_of_windows: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error>;
+    /// Decomposes the scalar into canonical windows which are range checked
+    /// where they are consumed as table addresses
+    fn decompose_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let max = scalar_max::<F, App::Scalar>();
+        self.decompose(ctx, scalar, &max, self.number_of_rounds())
+    }
+    /// Splits the scalar as `k = (-1)^s1 * k1 + (-1)^s2 * lambda * k2 mod n`
+    /// and returns windows of `k1` and `k2` along with the signs. Windows of
+    /// `k` and of the quotient are range checked here
+    fn decompose_glv(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<[(Vec<AssignedValue<F>>, AssignedValue<F>); 2], Error> {
+        let glv = self.glv().expect("glv is not configured");
+        let number_of_rounds = self.number_of_rounds();
+        let split = scalar
+            .value()
+            .map(|scalar| glv.split(*scalar, number_of_rounds));
+        let [k1, k2, quotient, s1, s2, u, v, k_lo, carry] = self.glv_check(ctx, scalar, split)?;
+        let max = scalar_max::<F, App::Scalar>();
+        let scalar_windows = self.decompose(ctx, scalar, &max, glv.scalar_windows)?;
+        let k1_windows = self.decompose(ctx, &k1, &glv.half_max(), number_of_rounds)?;
+        let k2_windows = self.decompose(ctx, &k2, &glv.half_max(), number_of_rounds)?;
+        let quotient_windows =
+            self.decompose(ctx, &quotient, &glv.quotient_max(), glv.quotient_windows())?;
+        // the carry only needs to be small which already follows from the bound
+        self.decompose(ctx, &carry, &glv.carry_max(), glv.carry_windows())?;
+        for (windows, base, sum) in [
+            (&k2_windows, &glv.lambda, u),
+            (&quotient_windows, &glv.modulus, v),
+            (&scalar_windows, &BigUint::one(), k_lo),
+        ] {
+            let coefficients = glv.coefficients(base, windows.len());
+            let acc = self.weighted_sum(ctx, windows, &coefficients)?;
+            ctx.equal(acc.cell(), sum.cell())?;
+        }
+        Ok([(k1_windows, s1), (k2_windows, s2)])
+    }
+    /// Assigns the two rows of the decomposition check and returns
+    /// `[k1, k2, quotient, s1, s2, u, v, k_lo, carry]`
+    fn glv_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: &AssignedValue<F>,
+        split: Value<[F; 9]>,
+    ) -> Result<[AssignedValue<F>; 9], Error>;
+    /// Accumulates `sum(coefficient_i * window_i)` while range checking the
+    /// windows
+    fn weighted_sum(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+        coefficients: &[F],
+    ) -> Result<AssignedValue<F>, Error>;
+    /// Maps the point to `(zeta * x, y)`
+    fn endomorphism(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+    ) -> Result<AssignedPoint<App>, Error>;
+    /// Recodes unsigned windows into signed digits `d` in `[-2^(w-1), 2^(w-1)]`
+    /// and returns the table indices `2^(w-1) + d` most significant first.
+    /// Windows are range checked here and indices are bounded by the
+    /// recoding
+    fn recode_signed(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        windows: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error>;
+    /// Negates the point if the boolean `sign` is set
+    fn conditional_negate(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        point: &AssignedPoint<App>,
+        sign: &AssignedValue<F>,
+    ) -> Result<AssignedPoint<App>, Error>;
     fn all_zero(&self, ctx: &mut RegionCtx<'_, F>) -> Result<(), Error> {
         let advice_columns = self.advice_columns();
         for column in advice_columns.into_iter() {
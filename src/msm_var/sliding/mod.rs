@@ -1,3 +1,5 @@
+pub mod generic;
 pub mod instructions;
 pub mod narrow;
+pub mod rw;
 pub mod wide;
@@ -1,6 +1,3 @@
-pub mod assignments;
 pub mod config;
-pub mod msm;
-pub(crate) mod rw;
 #[cfg(test)]
 mod tests;
@@ -1,8 +1,15 @@
+use crate::error::{check_mixed, MsmError};
+use crate::msm_fix::{
+    config::FixMSMGate,
+    tests::{configure_fixed_gate, fixed_bases},
+};
 use crate::msm_var::sliding::instructions::MSMGate;
+use crate::range::RangeTable;
 use crate::util::multiexp_naive_var;
 use crate::RegionCtx;
 use ff::Field;
 use ff::PrimeField;
+use ff::WithSmallOrderMulGroup;
 use group::Curve;
 use group::Group;
 use halo2::dev::MockProver;
@@ -12,7 +19,7 @@ use halo2::{
     plonk::Error,
     plonk::{Circuit, ConstraintSystem},
 };
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 use std::marker::PhantomData;
 
 use super::config::VarMSMGateNarrow;
@@ -20,19 +27,35 @@ use super::config::VarMSMGateNarrow;
 #[derive(Default, Clone, Debug)]
 struct Params {
     window: usize,
+    number_of_fixed: usize,
+    fixed_window: usize,
+    glv: bool,
+    signed: bool,
+    complete: bool,
 }
 #[derive(Clone, Debug)]
 struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     msm_gate: VarMSMGateNarrow<F, App>,
+    fixed_gate: Option<FixMSMGate<F, App>>,
 }
 #[derive(Debug, Default)]
 struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     _marker: PhantomData<(F, App)>,
     window: usize,
     number_of_points: usize,
+    glv: bool,
+    signed: bool,
+    complete: bool,
+    number_of_bits: Option<usize>,
+    number_of_fixed: usize,
+    fixed_window: usize,
 }
 
-impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+impl<F: PrimeField + Ord + WithSmallOrderMulGroup<3>, App: CurveAffine<Base = F>> Circuit<F>
+    for MyCircuit<F, App>
+where
+    App::Scalar: WithSmallOrderMulGroup<3>,
+{
     type Config = TestConfig<F, App>;
     type FloorPlanner = SimpleFloorPlanner;
     type Params = Params;
@@ -41,31 +64,48 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
             _marker: PhantomData,
             window: self.window,
             number_of_points: self.number_of_points,
+            glv: self.glv,
+            signed: self.signed,
+            complete: self.complete,
+            number_of_bits: self.number_of_bits,
+            number_of_fixed: self.number_of_fixed,
+            fixed_window: self.fixed_window,
         }
     }
     fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
-        let a0 = meta.advice_column();
-        let a1 = meta.advice_column();
-        let a2 = meta.advice_column();
-        let a3 = meta.advice_column();
-        let a4 = meta.advice_column();
+        let a = [(); 5].map(|_| meta.advice_column());
         let constant = meta.fixed_column();
-        let range_table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
         let window = params.window;
-        let aux = App::CurveExt::random(OsRng).to_affine();
-        let msm_gate = VarMSMGateNarrow::configure(
-            meta,
-            a0,
-            a1,
-            a2,
-            a3,
-            a4,
-            range_table,
-            constant,
-            window,
-            aux,
-        );
-        Self::Config { msm_gate }
+        let mut msm_gate =
+            VarMSMGateNarrow::configure_default(meta, &a, range_table, constant, instance, window);
+        if params.signed {
+            msm_gate.configure_signed_digits(meta);
+        }
+        if params.glv {
+            msm_gate.configure_glv(meta);
+        }
+        if params.complete {
+            msm_gate.configure_complete_addition(meta);
+        }
+        // fixed base gate shares the columns and the range table
+        let fixed_gate = (params.number_of_fixed > 0).then(|| {
+            configure_fixed_gate(
+                meta,
+                a,
+                range_table,
+                constant,
+                instance,
+                params.fixed_window,
+                &fixed_bases::<App>(params.number_of_fixed),
+                App::CurveExt::generator().to_affine(),
+            )
+        });
+        Self::Config {
+            msm_gate,
+            fixed_gate,
+        }
     }
     fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
         unreachable!();
@@ -77,48 +117,123 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
             };
         }
         let ly = &mut ly;
-        let rand_scalar = || App::Scalar::random(OsRng);
+        let rand_scalar = || match self.number_of_bits {
+            Some(number_of_bits) => {
+                let scalar = ((OsRng.next_u64() as u128) << 64) | OsRng.next_u64() as u128;
+                App::Scalar::from_u128(scalar >> (128 - number_of_bits))
+            }
+            None => App::Scalar::random(OsRng),
+        };
         let rand_point = || App::CurveExt::random(OsRng);
         let number_of_points = self.number_of_points;
-        let offset = ly.assign_region(
+        let (offset, saved_rows) = ly.assign_region(
             || "app",
             |region| {
-                cfg.msm_gate.unassign_constants();
                 let ctx = &mut RegionCtx::new(region);
-                let points: Vec<_> = (0..number_of_points)
+                let mut points: Vec<_> = (0..number_of_points)
                     .map(|_| rand_point())
                     .collect::<Vec<_>>();
-                let scalars = (0..number_of_points)
+                let mut scalars = (0..number_of_points)
                     .map(|_| rand_scalar())
                     .collect::<Vec<_>>();
-                let res0 = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+                // the table of the identity and a zero scalar keep adding the
+                // identity, and `P, -P` sharing a scalar read opposite points
+                if self.complete {
+                    points[0] = App::CurveExt::identity();
+                    points[2] = -points[1];
+                    scalars[2] = scalars[1];
+                    scalars[3] = App::Scalar::ZERO;
+                }
+                let fixed_scalars = (0..self.number_of_fixed)
+                    .map(|_| rand_scalar())
+                    .collect::<Vec<_>>();
+                let all_points = fixed_bases::<App>(self.number_of_fixed)
+                    .iter()
+                    .map(|base| base.to_curve())
+                    .chain(points.iter().cloned())
+                    .collect::<Vec<_>>();
+                let all_scalars = fixed_scalars
+                    .iter()
+                    .chain(scalars.iter())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let res0 = multiexp_naive_var(&all_points[..], &all_scalars[..]).to_affine();
                 let res0 = cfg.msm_gate.assign_point(ctx, &v!(res0))?;
                 let points: Vec<_> = points
                     .iter()
-                    .map(|point| v!(point.to_affine()))
-                    .collect::<Vec<_>>();
+                    .map(|point| {
+                        let point = v!(point.to_affine());
+                        cfg.msm_gate.assign_point(ctx, &point)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
                 let scalars = scalars
                     .into_iter()
-                    .map(|scalar| v!(scalar))
-                    .collect::<Vec<_>>();
-                let res1 = cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?;
+                    .map(|scalar| cfg.msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let fixed_scalars = fixed_scalars
+                    .into_iter()
+                    .map(|scalar| cfg.msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let res1 = match (&cfg.fixed_gate, self.number_of_bits) {
+                    (Some(fixed_gate), _) => cfg.msm_gate.msm_mixed(
+                        ctx,
+                        fixed_gate,
+                        &fixed_scalars[..],
+                        &points[..],
+                        &scalars[..],
+                    )?,
+                    (None, Some(number_of_bits)) => {
+                        cfg.msm_gate
+                            .msm_short(ctx, &points[..], &scalars[..], number_of_bits)?
+                    }
+                    (None, None) => cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?,
+                };
                 cfg.msm_gate.equal(ctx, &res0, &res1)?;
-                Ok(ctx.offset())
+                if self.complete {
+                    let msm_gate = &cfg.msm_gate;
+                    let point = &points[1];
+                    let value = point.value()?;
+                    let identity = App::CurveExt::identity().to_affine();
+                    let identity = msm_gate.assign_point(ctx, &v!(identity))?;
+                    let neg = msm_gate.assign_point(ctx, &value.map(|p| -p))?;
+                    let dbl = value.map(|p| (p + p).to_affine());
+                    let dbl = msm_gate.assign_point(ctx, &dbl)?;
+                    let res = msm_gate.dbl(ctx, &identity)?;
+                    msm_gate.equal(ctx, &res, &identity)?;
+                    let res = msm_gate.dbl(ctx, point)?;
+                    msm_gate.equal(ctx, &res, &dbl)?;
+                    let res = msm_gate.add(ctx, point, point)?;
+                    msm_gate.equal(ctx, &res, &dbl)?;
+                    let res = msm_gate.add(ctx, point, &neg)?;
+                    msm_gate.equal(ctx, &res, &identity)?;
+                    let res = msm_gate.add(ctx, &identity, point)?;
+                    msm_gate.equal(ctx, &res, point)?;
+                }
+                Ok((ctx.offset(), ctx.constants().saved_rows()))
             },
         )?;
         println!(
-            "narrow sliding gate, window {}, # terms: {}, row cost: {}, area cost: {}",
+            "narrow sliding gate, window {}, # terms: {}, row cost: {}, area cost: {}, constant rows saved: {}",
             self.window,
             self.number_of_points,
             offset / number_of_points,
             5 * offset / number_of_points,
+            saved_rows,
         );
         cfg.msm_gate.layout_range_table(ly)?;
+        if let Some(fixed_gate) = &cfg.fixed_gate {
+            fixed_gate.layout_point_table(ly)?;
+        }
         Ok(())
     }
     fn params(&self) -> Self::Params {
         Params {
             window: self.window,
+            number_of_fixed: self.number_of_fixed,
+            fixed_window: self.fixed_window,
+            glv: self.glv,
+            signed: self.signed,
+            complete: self.complete,
         }
     }
 }
@@ -133,6 +248,36 @@ fn test_sliding_narrow_msm_var() {
         _marker: PhantomData::<(Fq, EqAffine)>,
         window,
         number_of_points: 10000,
+        glv: false,
+        signed: false,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_var_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fp, Secp256r1Affine> {
+        _marker: PhantomData::<(Fp, Secp256r1Affine)>,
+        window,
+        number_of_points: 20,
+        glv: false,
+        signed: false,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 0,
+        fixed_window: window,
     };
     let public_inputs = vec![vec![]];
     let prover = match MockProver::run(K, &circuit, public_inputs) {
@@ -141,3 +286,252 @@ fn test_sliding_narrow_msm_var() {
     };
     prover.assert_satisfied();
 }
+
+#[test]
+fn test_sliding_narrow_msm_var_glv() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 20,
+        glv: true,
+        signed: false,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_var_short() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 20,
+        glv: false,
+        signed: false,
+        complete: false,
+        number_of_bits: Some(128),
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_mixed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 10,
+        glv: false,
+        signed: false,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 10,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_var_complete() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 20,
+        glv: false,
+        signed: false,
+        complete: true,
+        number_of_bits: None,
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_var_signed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 20,
+        glv: false,
+        signed: true,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_var_glv_signed() {
+    use halo2::halo2curves::pasta::{EpAffine, Fp};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fp, EpAffine> {
+        _marker: PhantomData::<(Fp, EpAffine)>,
+        window,
+        number_of_points: 20,
+        glv: true,
+        signed: true,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_var_short_signed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 20,
+        glv: false,
+        signed: true,
+        complete: false,
+        number_of_bits: Some(128),
+        number_of_fixed: 0,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_mixed_signed() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 10,
+        glv: false,
+        signed: true,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 10,
+        fixed_window: window,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_narrow_msm_mixed_window_mismatch() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 10,
+        glv: false,
+        signed: false,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 10,
+        fixed_window: window - 1,
+    };
+    let e = match MockProver::run(K, &circuit, vec![vec![]]) {
+        Ok(_) => panic!("mixed msm over different windows must fail"),
+        Err(e) => e,
+    };
+    assert!(matches!(e, Error::Synthesis));
+    assert_eq!(
+        check_mixed(window, window - 1, false),
+        Err(MsmError::WindowMismatch {
+            expected: window,
+            actual: window - 1
+        })
+    );
+}
+
+#[test]
+fn test_sliding_narrow_msm_mixed_glv() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window,
+        number_of_points: 10,
+        glv: true,
+        signed: false,
+        complete: false,
+        number_of_bits: None,
+        number_of_fixed: 10,
+        fixed_window: window,
+    };
+    let e = match MockProver::run(K, &circuit, vec![vec![]]) {
+        Ok(_) => panic!("mixed msm in the glv mode must fail"),
+        Err(e) => e,
+    };
+    assert!(matches!(e, Error::Synthesis));
+    assert!(matches!(
+        check_mixed(window, window, true),
+        Err(MsmError::UnsupportedMode { .. })
+    ));
+}
@@ -0,0 +1,54 @@
+use crate::{
+    error::{transpose, MsmError},
+    point_from_coords,
+};
+use ff::PrimeField;
+use halo2::{circuit::Value, halo2curves::CurveAffine};
+use std::collections::BTreeMap;
+
+/// Point tables written so far. Each table takes its own addresses so that
+/// msm calls sharing the memory don't overwrite each other
+#[derive(Clone, Debug, Default)]
+pub struct Memory<F: PrimeField + Ord> {
+    state: BTreeMap<F, (F, F)>,
+    size: usize,
+}
+impl<F: PrimeField + Ord> Memory<F> {
+    /// Reserves `size` addresses and returns the first one
+    pub(crate) fn reserve(&mut self, size: usize) -> usize {
+        let offset = self.size;
+        self.size += size;
+        offset
+    }
+    pub(crate) fn read<C: CurveAffine<Base = F>>(
+        &mut self,
+        address: &Value<F>,
+        offset: F,
+    ) -> Result<Value<C>, MsmError> {
+        let coords = transpose(address.map(|address| {
+            self.state
+                .get(&(address + offset))
+                .copied()
+                .ok_or(MsmError::UnwrittenAddress)
+        }))?;
+        transpose(coords.map(|(x, y)| point_from_coords(x, y)))
+    }
+    /// Writes the point once. Rewriting an address with another point is an
+    /// error since the table is laid out as fixed addresses
+    pub(crate) fn write(
+        &mut self,
+        address: F,
+        offset: F,
+        coords: &Value<(F, F)>,
+    ) -> Result<(), MsmError> {
+        transpose(
+            coords.map(|coords| match self.state.insert(address + offset, coords) {
+                Some(coords_old) if coords_old != coords => Err(MsmError::InconsistentMemory {
+                    context: "write point",
+                }),
+                _ => Ok(()),
+            }),
+        )?;
+        Ok(())
+    }
+}
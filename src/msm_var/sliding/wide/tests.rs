@@ -1,4 +1,5 @@
 use crate::msm_var::sliding::instructions::MSMGate;
+use crate::range::RangeTable;
 use crate::util::multiexp_naive_var;
 use crate::RegionCtx;
 use ff::Field;
@@ -20,6 +21,7 @@ use super::config::VarMSMGateWide;
 #[derive(Default, Clone, Debug)]
 struct Params {
     window: usize,
+    signed: bool,
 }
 #[derive(Clone, Debug)]
 struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
@@ -30,6 +32,7 @@ struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
     _marker: PhantomData<(F, App)>,
     window: usize,
     number_of_points: usize,
+    signed: bool,
 }
 
 impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
@@ -41,38 +44,20 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
             _marker: PhantomData,
             window: self.window,
             number_of_points: self.number_of_points,
+            signed: self.signed,
         }
     }
     fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
-        let a0 = meta.advice_column();
-        let a1 = meta.advice_column();
-        let a2 = meta.advice_column();
-        let a3 = meta.advice_column();
-        let a4 = meta.advice_column();
-        let a5 = meta.advice_column();
-        let a6 = meta.advice_column();
-        let a7 = meta.advice_column();
-        let a8 = meta.advice_column();
+        let a = [(); 9].map(|_| meta.advice_column());
         let constant = meta.fixed_column();
-        let range_table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
         let window = params.window;
-        let aux = App::CurveExt::random(OsRng).to_affine();
-        let msm_gate = VarMSMGateWide::configure(
-            meta,
-            a0,
-            a1,
-            a2,
-            a3,
-            a4,
-            a5,
-            a6,
-            a7,
-            a8,
-            range_table,
-            constant,
-            window,
-            aux,
-        );
+        let mut msm_gate =
+            VarMSMGateWide::configure_default(meta, &a, range_table, constant, instance, window);
+        if params.signed {
+            msm_gate.configure_signed_digits(meta);
+        }
         Self::Config { msm_gate }
     }
     fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
@@ -88,10 +73,9 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
         let rand_scalar = || App::Scalar::random(OsRng);
         let rand_point = || App::CurveExt::random(OsRng);
         let number_of_points = self.number_of_points;
-        let offset = ly.assign_region(
+        let (offset, saved_rows) = ly.assign_region(
             || "app",
             |region| {
-                cfg.msm_gate.unassign_constants();
                 let ctx = &mut RegionCtx::new(region);
                 let points: Vec<_> = (0..number_of_points)
                     .map(|_| rand_point())
@@ -103,23 +87,27 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
                 let res0 = cfg.msm_gate.assign_point(ctx, &v!(res0))?;
                 let points: Vec<_> = points
                     .iter()
-                    .map(|point| v!(point.to_affine()))
-                    .collect::<Vec<_>>();
+                    .map(|point| {
+                        let point = v!(point.to_affine());
+                        cfg.msm_gate.assign_point(ctx, &point)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
                 let scalars = scalars
                     .into_iter()
-                    .map(|scalar| v!(scalar))
-                    .collect::<Vec<_>>();
+                    .map(|scalar| cfg.msm_gate.assign_scalar(ctx, &v!(scalar)))
+                    .collect::<Result<Vec<_>, Error>>()?;
                 let res1 = cfg.msm_gate.msm(ctx, &points[..], &scalars[..])?;
                 cfg.msm_gate.equal(ctx, &res0, &res1)?;
-                Ok(ctx.offset())
+                Ok((ctx.offset(), ctx.constants().saved_rows()))
             },
         )?;
         println!(
-            "wide sliding gate, window {}, # terms: {}, row cost: {}, area cost: {}",
+            "wide sliding gate, window {}, # terms: {}, row cost: {}, area cost: {}, constant rows saved: {}",
             self.window,
             self.number_of_points,
             offset / number_of_points,
             9 * offset / number_of_points,
+            saved_rows,
         );
         cfg.msm_gate.layout_range_table(ly)?;
         Ok(())
@@ -127,6 +115,7 @@ impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F
     fn params(&self) -> Self::Params {
         Params {
             window: self.window,
+            signed: self.signed,
         }
     }
 }
@@ -140,6 +129,45 @@ fn test_sliding_wide_msm_var() {
         _marker: PhantomData::<(Fq, EqAffine)>,
         window,
         number_of_points: 10000,
+        signed: false,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_wide_msm_var_a_nonzero() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fp, Secp256r1Affine> {
+        _marker: PhantomData::<(Fp, Secp256r1Affine)>,
+        window,
+        number_of_points: 20,
+        signed: false,
+    };
+    let public_inputs = vec![vec![]];
+    let prover = match MockProver::run(K, &circuit, public_inputs) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sliding_wide_msm_var_signed() {
+    use halo2::halo2curves::secp256r1::{Fp, Secp256r1Affine};
+    const K: u32 = 17;
+    let window = 4;
+    let circuit = MyCircuit::<Fp, Secp256r1Affine> {
+        _marker: PhantomData::<(Fp, Secp256r1Affine)>,
+        window,
+        number_of_points: 20,
+        signed: true,
     };
     let public_inputs = vec![vec![]];
     let prover = match MockProver::run(K, &circuit, public_inputs) {
@@ -0,0 +1,96 @@
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error, Expression, TableColumn, VirtualCells},
+};
+
+/// Ranges sharing the range table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tag {
+    /// Windows of scalars and bucket addresses in `0..2^window`
+    Window = 0,
+    /// Timestamp differences of memory queries minus one in
+    /// `0..number_of_queries`
+    Timestamp = 1,
+    /// Addresses of memory queries
+    Address = 2,
+}
+
+/// Lookup table of small ranges told apart by a tag. `(tag, value)` is in
+/// the table when `value` is in the range laid out for the tag. Rows where
+/// none of the selectors of a lookup is enabled look up `(0, 0)` which is
+/// always in the table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeTable {
+    pub(crate) tag: TableColumn,
+    pub(crate) value: TableColumn,
+}
+
+impl RangeTable {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tag: meta.lookup_table_column(),
+            value: meta.lookup_table_column(),
+        }
+    }
+    /// Looks up `(selector, tag, value)` inputs in a single argument. Selectors
+    /// of the inputs must not be enabled on the same row
+    pub(crate) fn lookup<F: PrimeField>(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        inputs: impl FnOnce(&mut VirtualCells<'_, F>) -> Vec<(Expression<F>, Tag, Expression<F>)>,
+    ) {
+        let (tag_column, value_column) = (self.tag, self.value);
+        meta.lookup(name, |meta| {
+            let zero = Expression::Constant(F::ZERO);
+            let (tag, value) = inputs(meta).into_iter().fold(
+                (e!(zero), zero),
+                |(tag_acc, value_acc), (s, tag, value)| {
+                    (tag_acc + e!(s) * F::from(tag as u64), value_acc + s * value)
+                },
+            );
+            vec![(tag, tag_column), (value, value_column)]
+        });
+    }
+    /// Lays out `(tag, size)` ranges of values `0..size`. It is called once
+    /// with all the ranges lookups of the circuit use
+    pub fn layout<F: PrimeField>(
+        &self,
+        ly: &mut impl Layouter<F>,
+        ranges: &[(Tag, usize)],
+    ) -> Result<(), Error> {
+        let zero = (!Self::has_zero(ranges)).then_some((Tag::Window, 0));
+        ly.assign_table(
+            || "range table",
+            |mut table| {
+                let rows = zero.into_iter().chain(
+                    ranges
+                        .iter()
+                        .flat_map(|(tag, size)| (0..*size).map(move |value| (*tag, value))),
+                );
+                for (offset, (tag, value)) in rows.enumerate() {
+                    table.assign_cell(
+                        || "tag",
+                        self.tag,
+                        offset,
+                        || Value::known(F::from(tag as u64)),
+                    )?;
+                    table.assign_cell(
+                        || "value in range",
+                        self.value,
+                        offset,
+                        || Value::known(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+    /// Whether the window range covers `(0, 0)`
+    fn has_zero(ranges: &[(Tag, usize)]) -> bool {
+        ranges
+            .iter()
+            .any(|(tag, size)| *tag == Tag::Window && *size > 0)
+    }
+}
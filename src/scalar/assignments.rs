@@ -0,0 +1,477 @@
+use super::{config::ScalarChip, AssignedScalar};
+use crate::{
+    range::Tag,
+    util::{big_to_fe, decompose_big, fe_to_big, modulus},
+    AssignedValue, RegionCtx,
+};
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::Error,
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Zero};
+use std::marker::PhantomData;
+
+impl<F: PrimeField + Ord, S: PrimeField> ScalarChip<F, S> {
+    /// Assigns a row of the gate where given inputs are copied along with
+    /// their coefficients and the output `a4` is computed from them
+    fn assign_row(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        inputs: [Option<(F, &AssignedValue<F>)>; 4],
+        cm: F,
+        cc: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        ctx.enable(self.s_linear)?;
+        let columns = [
+            (self.a0, self.c0),
+            (self.a1, self.c1),
+            (self.a2, self.c2),
+            (self.a3, self.c3),
+        ];
+        let mut output = Value::known(cc);
+        let mut values = vec![];
+        for ((advice, fixed), input) in columns.into_iter().zip(inputs.into_iter()) {
+            match input {
+                Some((coeff, assigned)) => {
+                    ctx.copy(|| "scalar: input", advice, assigned)?;
+                    ctx.fixed(|| "scalar: coeff", fixed, coeff)?;
+                    let value = assigned.value().copied();
+                    output = output + value.map(|value| value * coeff);
+                    values.push(value);
+                }
+                None => {
+                    ctx.empty(|| "scalar:", advice.into())?;
+                    ctx.empty(|| "scalar:", fixed.into())?;
+                    values.push(Value::known(F::ZERO));
+                }
+            }
+        }
+        output = output + values[0].zip(values[1]).map(|(a0, a1)| a0 * a1 * cm);
+        ctx.fixed(|| "scalar: mul coeff", self.cm, cm)?;
+        ctx.fixed(|| "scalar: constant", self.cc, cc)?;
+        let output = ctx.advice(|| "scalar: output", self.a4, output)?;
+        ctx.next();
+        Ok(output)
+    }
+    /// Assigns a free cell outside of the gate
+    fn witness(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: Value<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let assigned = ctx.advice(|| "scalar: witness", self.a0, value)?;
+        for column in [self.a1, self.a2, self.a3, self.a4] {
+            ctx.empty(|| "scalar:", column.into())?;
+        }
+        ctx.next();
+        Ok(assigned)
+    }
+    pub fn get_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        constant: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        match ctx.constants_mut().get(&constant) {
+            Some(constant) => Ok(constant),
+            _ => {
+                let assigned = self.assign_row(ctx, [None, None, None, None], F::ZERO, constant)?;
+                ctx.constants_mut().insert(constant, assigned.clone());
+                Ok(assigned)
+            }
+        }
+    }
+    /// Computes `sum c_i * v_i + constant`. Each row takes four terms and
+    /// further rows take three more along with the sum so far
+    pub fn combine(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[(F, AssignedValue<F>)],
+        constant: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        let mut terms = terms.iter().map(|(coeff, value)| (*coeff, value));
+        let inputs = [(); 4].map(|_| terms.next());
+        let mut acc = self.assign_row(ctx, inputs, F::ZERO, constant)?;
+        loop {
+            let rest = [(); 3].map(|_| terms.next());
+            if rest[0].is_none() {
+                return Ok(acc);
+            }
+            let inputs = [Some((F::ONE, &acc)), rest[0], rest[1], rest[2]];
+            acc = self.assign_row(ctx, inputs, F::ZERO, F::ZERO)?;
+        }
+    }
+    /// Constrains `sum c_i * v_i + constant` to be zero
+    fn assert_zero(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[(F, AssignedValue<F>)],
+        constant: F,
+    ) -> Result<(), Error> {
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        let sum = self.combine(ctx, terms, constant)?;
+        ctx.equal(sum.cell(), zero.cell())
+    }
+    /// Constrains the value to be a bit with `b * b - b = 0`
+    fn assert_bit(&self, ctx: &mut RegionCtx<'_, F>, bit: &AssignedValue<F>) -> Result<(), Error> {
+        let zero = self.get_constant(ctx, F::ZERO)?;
+        let inputs = [Some((-F::ONE, bit)), Some((F::ZERO, bit)), None, None];
+        let out = self.assign_row(ctx, inputs, F::ONE, F::ZERO)?;
+        ctx.equal(out.cell(), zero.cell())
+    }
+    /// Assigns the value range checked to `number_of_bits` bits rounded up
+    /// to the window. Windows are accumulated most significant first and
+    /// the value is the output of the last row
+    fn assign_range(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        value: Value<BigUint>,
+        number_of_bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        let number_of_windows = div_ceil!(std::cmp::max(number_of_bits, 1), self.window);
+        let windows = value
+            .map(|value| {
+                let mut windows: Vec<F> = decompose_big(value, number_of_windows, self.window);
+                windows.reverse();
+                windows
+            })
+            .transpose_vec(number_of_windows);
+        let shift = F::from(1 << self.window);
+        let mut acc: Option<AssignedValue<F>> = None;
+        for window in windows.into_iter() {
+            ctx.enable(self.s_linear)?;
+            ctx.enable(self.s_range)?;
+            let output = match &acc {
+                Some(acc) => {
+                    ctx.copy(|| "range: acc", self.a0, acc)?;
+                    ctx.fixed(|| "range: shift", self.c0, shift)?;
+                    acc.value()
+                        .zip(window)
+                        .map(|(acc, window)| *acc * shift + window)
+                }
+                None => {
+                    ctx.empty(|| "range:", self.a0.into())?;
+                    ctx.empty(|| "range:", self.c0.into())?;
+                    window
+                }
+            };
+            ctx.advice(|| "range: window", self.a1, window)?;
+            ctx.fixed(|| "range: one", self.c1, F::ONE)?;
+            for (advice, fixed) in [(self.a2, self.c2), (self.a3, self.c3)] {
+                ctx.empty(|| "range:", advice.into())?;
+                ctx.empty(|| "range:", fixed.into())?;
+            }
+            ctx.empty(|| "range:", self.cm.into())?;
+            ctx.empty(|| "range:", self.cc.into())?;
+            acc = Some(ctx.advice(|| "range: acc", self.a4, output)?);
+            ctx.next();
+        }
+        Ok(acc.unwrap())
+    }
+    /// Assigns range checked limbs of the integer and constrains it to be
+    /// less than the modulus of `S` by computing `p - 1 - r` with borrows
+    fn assign_canonical(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        integer: Value<BigUint>,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        let limb_bits = self.limb_bits();
+        let number_of_limbs = self.number_of_limbs();
+        let limbs = integer
+            .map(|integer| big_limbs(&integer, number_of_limbs, limb_bits))
+            .transpose_vec(number_of_limbs)
+            .into_iter()
+            .map(|limb| self.assign_range(ctx, limb, limb_bits))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let bound = big_limbs(&(modulus::<S>() - 1usize), number_of_limbs, limb_bits);
+        let base = BigInt::one() << limb_bits;
+        let mut borrow: Option<(Value<BigInt>, AssignedValue<F>)> = None;
+        for (i, (limb, bound)) in limbs.iter().zip(bound.into_iter()).enumerate() {
+            let borrow_in = borrow
+                .as_ref()
+                .map_or(Value::known(BigInt::zero()), |(value, _)| value.clone());
+            // d_i = bound_i - r_i - borrow_in + borrow_out * 2^b
+            let diff = limb.value().zip(borrow_in).map(|(limb, borrow_in)| {
+                to_signed(&bound) - to_signed(&fe_to_big(*limb)) - borrow_in
+            });
+            let borrow_out = diff.as_ref().map(|diff| match diff.sign() {
+                Sign::Minus => BigInt::one(),
+                _ => BigInt::zero(),
+            });
+            let diff = diff
+                .zip(borrow_out.as_ref())
+                .map(|(diff, borrow_out)| (diff + borrow_out * &base).magnitude().clone());
+            let diff = self.assign_range(ctx, diff, limb_bits)?;
+            let mut terms = vec![(-F::ONE, diff), (-F::ONE, limb.clone())];
+            if let Some((_, borrow_in)) = &borrow {
+                terms.push((-F::ONE, borrow_in.clone()));
+            }
+            // no borrow out of the most significant limb
+            borrow = if i + 1 < number_of_limbs {
+                let value = borrow_out
+                    .as_ref()
+                    .map(|borrow| big_to_fe(borrow.magnitude().clone()));
+                let assigned = self.witness(ctx, value)?;
+                self.assert_bit(ctx, &assigned)?;
+                terms.push((big_to_fe(base.magnitude().clone()), assigned.clone()));
+                Some((borrow_out, assigned))
+            } else {
+                None
+            };
+            self.assert_zero(ctx, &terms, big_to_fe(bound))?;
+        }
+        Ok(AssignedScalar {
+            max: vec![(BigUint::one() << limb_bits) - 1usize; number_of_limbs],
+            limbs,
+            limb_bits,
+            reduced: true,
+            _marker: PhantomData,
+        })
+    }
+    /// Assigns the scalar as canonical limbs
+    pub fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: Value<S>,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        self.assign_canonical(ctx, scalar.map(fe_to_big))
+    }
+    pub fn assign_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        scalar: S,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        let limb_bits = self.limb_bits();
+        let limbs = big_limbs(&fe_to_big(scalar), self.number_of_limbs(), limb_bits);
+        Ok(AssignedScalar {
+            limbs: limbs
+                .iter()
+                .map(|limb| self.get_constant(ctx, big_to_fe(limb.clone())))
+                .collect::<Result<Vec<_>, Error>>()?,
+            max: limbs,
+            limb_bits,
+            reduced: true,
+            _marker: PhantomData,
+        })
+    }
+    /// Limb-wise sum that is not reduced
+    pub fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedScalar<F, S>,
+        b: &AssignedScalar<F, S>,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        let number_of_limbs = std::cmp::max(a.limbs.len(), b.limbs.len());
+        let mut limbs = vec![];
+        let mut max = vec![];
+        for i in 0..number_of_limbs {
+            let terms = [a, b]
+                .iter()
+                .filter_map(|scalar| scalar.limbs.get(i).map(|limb| (F::ONE, limb.clone())))
+                .collect::<Vec<_>>();
+            limbs.push(self.combine(ctx, &terms, F::ZERO)?);
+            max.push([a, b].iter().filter_map(|scalar| scalar.max.get(i)).sum());
+        }
+        self.unreduced(limbs, max)
+    }
+    /// Product with a constant as the convolution of limbs that is not
+    /// reduced
+    pub fn mul_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedScalar<F, S>,
+        constant: S,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        let mut constant = big_limbs(
+            &fe_to_big(constant),
+            self.number_of_limbs(),
+            self.limb_bits(),
+        );
+        while constant.len() > 1 && constant.last().unwrap().is_zero() {
+            constant.pop();
+        }
+        let number_of_limbs = a.limbs.len() + constant.len() - 1;
+        let mut limbs = vec![];
+        let mut max = vec![];
+        for m in 0..number_of_limbs {
+            let mut terms = vec![];
+            let mut limb_max = BigUint::zero();
+            for (i, (limb, a_max)) in a.limbs.iter().zip(a.max.iter()).enumerate() {
+                match m.checked_sub(i).and_then(|j| constant.get(j)) {
+                    Some(k) if !k.is_zero() => {
+                        terms.push((big_to_fe(k.clone()), limb.clone()));
+                        limb_max += k * a_max;
+                    }
+                    _ => {}
+                }
+            }
+            limbs.push(self.combine(ctx, &terms, F::ZERO)?);
+            max.push(limb_max);
+        }
+        self.unreduced(limbs, max)
+    }
+    fn unreduced(
+        &self,
+        limbs: Vec<AssignedValue<F>>,
+        max: Vec<BigUint>,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        // carries of `reduce` must not wrap around the native field
+        let bound = modulus::<F>() >> (self.window + 8);
+        assert!(
+            max.iter().all(|max| *max < bound),
+            "scalar limbs overflow the native field, reduce first"
+        );
+        Ok(AssignedScalar {
+            limbs,
+            max,
+            limb_bits: self.limb_bits(),
+            reduced: false,
+            _marker: PhantomData,
+        })
+    }
+    /// Reduces the scalar to canonical limbs. Quotient `q` and remainder
+    /// `r` of `a = q * p + r` are witnessed and the equation is checked limb
+    /// by limb with range checked carries so that it holds over integers
+    pub fn reduce(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedScalar<F, S>,
+    ) -> Result<AssignedScalar<F, S>, Error> {
+        if a.reduced {
+            return Ok(a.clone());
+        }
+        let limb_bits = self.limb_bits();
+        let modulus = modulus::<S>();
+        let max_a: BigUint = a
+            .max
+            .iter()
+            .enumerate()
+            .map(|(i, max)| max << (i * limb_bits))
+            .sum();
+        let max_q = &max_a / &modulus;
+        let number_of_q_limbs = div_ceil!(std::cmp::max(max_q.bits() as usize, 1), limb_bits);
+        let integer = a.integer();
+        let r = self.assign_canonical(ctx, integer.as_ref().map(|a| a % &modulus))?;
+        let q = integer
+            .map(|a| big_limbs(&(a / &modulus), number_of_q_limbs, limb_bits))
+            .transpose_vec(number_of_q_limbs)
+            .into_iter()
+            .map(|limb| self.assign_range(ctx, limb, limb_bits))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let p = big_limbs(&modulus, self.number_of_limbs(), limb_bits);
+        let limb_max = (BigUint::one() << limb_bits) - 1usize;
+        let number_of_positions = *[a.limbs.len(), q.len() + p.len() - 1, r.limbs.len()]
+            .iter()
+            .max()
+            .unwrap();
+        let values = |limbs: &[AssignedValue<F>]| {
+            Value::<Vec<F>>::from_iter(limbs.iter().map(|limb| limb.value().copied())).map(
+                |limbs| {
+                    limbs
+                        .iter()
+                        .map(|limb| to_signed(&fe_to_big(*limb)))
+                        .collect::<Vec<_>>()
+                },
+            )
+        };
+        let (a_values, q_values, r_values) = (values(&a.limbs), values(&q), values(&r.limbs));
+        let base = BigInt::one() << limb_bits;
+        // carries c_m of t_m + c_(m-1) = c_m * 2^b are shifted by their
+        // bound to be range checked
+        let mut carry: Option<(Value<BigInt>, BigUint, AssignedValue<F>)> = None;
+        for m in 0..number_of_positions {
+            let mut terms = vec![];
+            let mut t_max = limb_max.clone();
+            if let Some(limb) = a.limbs.get(m) {
+                terms.push((F::ONE, limb.clone()));
+                t_max += &a.max[m];
+            }
+            let products = (0..q.len())
+                .filter_map(|i| {
+                    m.checked_sub(i)
+                        .and_then(|j| p.get(j))
+                        .map(|p| (i, p.clone()))
+                })
+                .filter(|(_, p)| !p.is_zero())
+                .collect::<Vec<_>>();
+            for (i, p) in products.iter() {
+                terms.push((-big_to_fe::<F>(p.clone()), q[*i].clone()));
+                t_max += p * &limb_max;
+            }
+            if let Some(limb) = r.limbs.get(m) {
+                terms.push((-F::ONE, limb.clone()));
+            }
+            let t = a_values
+                .as_ref()
+                .zip(q_values.as_ref())
+                .zip(r_values.as_ref())
+                .map(|((a, q), r)| {
+                    let mut t = a.get(m).cloned().unwrap_or_default();
+                    for (i, p) in products.iter() {
+                        t -= to_signed(p) * &q[*i];
+                    }
+                    t - r.get(m).cloned().unwrap_or_default()
+                });
+            let mut constant = F::ZERO;
+            let mut total_max = t_max.clone();
+            let carry_in = match &carry {
+                Some((value, max, assigned)) => {
+                    terms.push((F::ONE, assigned.clone()));
+                    constant -= big_to_fe::<F>(max.clone());
+                    total_max += max << 1;
+                    value.clone()
+                }
+                None => Value::known(BigInt::zero()),
+            };
+            let sum = t.zip(carry_in).map(|(t, carry_in)| t + carry_in);
+            // no carry out of the most significant position
+            carry = if m + 1 < number_of_positions {
+                let max = (&total_max >> limb_bits) + 1usize;
+                let value = sum.map(|sum| sum / &base);
+                let shifted = value
+                    .as_ref()
+                    .map(|value| (value + to_signed(&max)).magnitude().clone());
+                let assigned =
+                    self.assign_range(ctx, shifted, (max.clone() << 1).bits() as usize)?;
+                terms.push((-big_to_fe::<F>(base.magnitude().clone()), assigned.clone()));
+                constant += big_to_fe::<F>(max.clone() << limb_bits);
+                Some((value, max, assigned))
+            } else {
+                None
+            };
+            self.assert_zero(ctx, &terms, constant)?;
+        }
+        Ok(r)
+    }
+    /// Constrains the scalars to be equal modulo the modulus of `S`
+    pub fn assert_equal(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedScalar<F, S>,
+        b: &AssignedScalar<F, S>,
+    ) -> Result<(), Error> {
+        let a = self.reduce(ctx, a)?;
+        let b = self.reduce(ctx, b)?;
+        for (a, b) in a.limbs.iter().zip(b.limbs.iter()) {
+            ctx.equal(a.cell(), b.cell())?;
+        }
+        Ok(())
+    }
+    pub fn layout_range_table(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range_table
+            .layout(ly, &[(Tag::Window, 1 << self.window)])
+    }
+}
+
+/// Little endian limbs of `limb_bits` bits
+fn big_limbs(e: &BigUint, number_of_limbs: usize, limb_bits: usize) -> Vec<BigUint> {
+    let mask = (BigUint::one() << limb_bits) - 1usize;
+    (0..number_of_limbs)
+        .map(|i| (e >> (i * limb_bits)) & &mask)
+        .collect()
+}
+
+fn to_signed(e: &BigUint) -> BigInt {
+    BigInt::from_biguint(Sign::Plus, e.clone())
+}
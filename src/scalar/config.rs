@@ -0,0 +1,97 @@
+use crate::range::{RangeTable, Tag};
+use ff::PrimeField;
+use halo2::plonk::{Advice, Column, ConstraintSystem, Fixed, Selector};
+use halo2::poly::Rotation;
+use std::marker::PhantomData;
+
+/// Arithmetic over scalars of `S` in the native field `F`. A single gate
+/// `c0 * a0 + c1 * a1 + c2 * a2 + c3 * a3 + cm * a0 * a1 + cc = a4` with
+/// fixed coefficients covers linear combinations, constants and booleans,
+/// and `a1` is looked up in the window range of the range table
+#[derive(Clone, Debug)]
+pub struct ScalarChip<F: PrimeField, S: PrimeField> {
+    pub(crate) a0: Column<Advice>,
+    pub(crate) a1: Column<Advice>,
+    pub(crate) a2: Column<Advice>,
+    pub(crate) a3: Column<Advice>,
+    pub(crate) a4: Column<Advice>,
+    pub(crate) c0: Column<Fixed>,
+    pub(crate) c1: Column<Fixed>,
+    pub(crate) c2: Column<Fixed>,
+    pub(crate) c3: Column<Fixed>,
+    pub(crate) cm: Column<Fixed>,
+    pub(crate) cc: Column<Fixed>,
+    pub(crate) range_table: RangeTable,
+    pub(crate) s_linear: Selector,
+    pub(crate) s_range: Selector,
+    pub(crate) window: usize,
+    pub(crate) _marker: PhantomData<(F, S)>,
+}
+
+impl<F: PrimeField, S: PrimeField> ScalarChip<F, S> {
+    /// Range table is expected to hold the window range `0..2^window` so
+    /// that it can be shared with an msm gate of the same window
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a0: Column<Advice>,
+        a1: Column<Advice>,
+        a2: Column<Advice>,
+        a3: Column<Advice>,
+        a4: Column<Advice>,
+        range_table: RangeTable,
+        window: usize,
+    ) -> Self {
+        assert!(window > 0 && window <= 32);
+        assert!(
+            F::NUM_BITS as usize > 2 * 64 + window + 16,
+            "native field is too small for 64 bit limbs"
+        );
+        for column in [a0, a1, a2, a3, a4] {
+            meta.enable_equality(column);
+        }
+        let [c0, c1, c2, c3, cm, cc] = [(); 6].map(|_| meta.fixed_column());
+        let s_linear = meta.selector();
+        let s_range = meta.complex_selector();
+        meta.create_gate("scalar linear", |meta| {
+            let s = meta.query_selector(s_linear);
+            let [a0, a1, a2, a3, a4] =
+                [a0, a1, a2, a3, a4].map(|column| meta.query_advice(column, Rotation::cur()));
+            let [c0, c1, c2, c3, cm, cc] =
+                [c0, c1, c2, c3, cm, cc].map(|column| meta.query_fixed(column, Rotation::cur()));
+            let expr = c0 * e!(a0) + c1 * e!(a1) + c2 * a2 + c3 * a3 + cm * a0 * a1 + cc - a4;
+            vec![s * expr]
+        });
+        range_table.lookup(meta, "scalar range", |meta| {
+            let s = meta.query_selector(s_range);
+            let a1 = meta.query_advice(a1, Rotation::cur());
+            vec![(s, Tag::Window, a1)]
+        });
+        Self {
+            a0,
+            a1,
+            a2,
+            a3,
+            a4,
+            c0,
+            c1,
+            c2,
+            c3,
+            cm,
+            cc,
+            range_table,
+            s_linear,
+            s_range,
+            window,
+            _marker: PhantomData,
+        }
+    }
+    /// Bits of a limb. It is the largest multiple of the window up to 64 so
+    /// that limbs can be fed into `msm_limbs` of a gate of the same window
+    pub fn limb_bits(&self) -> usize {
+        (64 / self.window) * self.window
+    }
+    /// Number of limbs of a reduced scalar
+    pub fn number_of_limbs(&self) -> usize {
+        div_ceil!(S::NUM_BITS as usize, self.limb_bits())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    util::{big_to_fe, fe_to_big},
+    AssignedValue,
+};
+use ff::PrimeField;
+use halo2::circuit::Value;
+use num_bigint::BigUint;
+use std::marker::PhantomData;
+
+pub mod assignments;
+pub mod config;
+#[cfg(test)]
+mod tests;
+
+/// Scalar of `S` in the native field `F` as little endian limbs of
+/// `limb_bits` bits. Results of `add` and `mul_constant` are not reduced so
+/// their limbs can grow up to `max`, and `ScalarChip::reduce` brings them
+/// back to canonical limbs
+#[derive(Clone, Debug)]
+pub struct AssignedScalar<F: PrimeField, S: PrimeField> {
+    pub(crate) limbs: Vec<AssignedValue<F>>,
+    pub(crate) max: Vec<BigUint>,
+    pub(crate) limb_bits: usize,
+    pub(crate) reduced: bool,
+    pub(crate) _marker: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: PrimeField> AssignedScalar<F, S> {
+    pub fn limbs(&self) -> &[AssignedValue<F>] {
+        &self.limbs
+    }
+    pub fn limb_bits(&self) -> usize {
+        self.limb_bits
+    }
+    /// Whether limbs are canonical, that is each limb fits in `limb_bits`
+    /// and the integer is less than the modulus of `S`
+    pub fn is_reduced(&self) -> bool {
+        self.reduced
+    }
+    /// Integer that the limbs recompose to
+    pub(crate) fn integer(&self) -> Value<BigUint> {
+        self.limbs
+            .iter()
+            .enumerate()
+            .fold(Value::known(BigUint::default()), |acc, (i, limb)| {
+                acc.zip(limb.value())
+                    .map(|(acc, limb)| acc + (fe_to_big(*limb) << (i * self.limb_bits)))
+            })
+    }
+    pub fn value(&self) -> Value<S> {
+        self.integer().map(big_to_fe)
+    }
+}
@@ -0,0 +1,212 @@
+use super::config::ScalarChip;
+use crate::memory::context::MemoryContext;
+use crate::msm::Msm;
+use crate::msm_var::bucket::narrow::config::VarMSMGateNarrow;
+use crate::point_coords;
+use crate::range::RangeTable;
+use crate::util::multiexp_naive_var;
+use ff::Field;
+use ff::PrimeField;
+use group::Curve;
+use halo2::dev::MockProver;
+use halo2::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::CurveAffine,
+    plonk::Error,
+    plonk::{Circuit, ConstraintSystem},
+};
+use rand_core::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use std::marker::PhantomData;
+
+#[derive(Default, Clone, Debug)]
+struct Params {
+    window: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    msm_gate: VarMSMGateNarrow<F, App>,
+    scalar_chip: ScalarChip<F, App::Scalar>,
+}
+#[derive(Debug, Default)]
+struct MyCircuit<F: PrimeField + Ord, App: CurveAffine<Base = F>> {
+    _marker: PhantomData<(F, App)>,
+    window: usize,
+    number_of_points: usize,
+    /// Expected value of the first scalar is off by one
+    wrong: bool,
+    /// Sums are given to the msm without reducing them
+    unreduced: bool,
+}
+
+/// Points, scalars `a_i`, `b_i` and the constant `k` of `s_i = a_i + k * b_i`
+#[allow(clippy::type_complexity)]
+fn terms<App: CurveAffine>(
+    number_of_points: usize,
+) -> (Vec<App>, Vec<App::Scalar>, Vec<App::Scalar>, App::Scalar) {
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let points = (0..number_of_points)
+        .map(|_| App::CurveExt::random(&mut rng).to_affine())
+        .collect();
+    let a = (0..number_of_points)
+        .map(|_| App::Scalar::random(&mut rng))
+        .collect();
+    let b = (0..number_of_points)
+        .map(|_| App::Scalar::random(&mut rng))
+        .collect();
+    (points, a, b, App::Scalar::random(&mut rng))
+}
+
+impl<F: PrimeField + Ord, App: CurveAffine<Base = F>> Circuit<F> for MyCircuit<F, App> {
+    type Config = TestConfig<F, App>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = Params;
+    fn without_witnesses(&self) -> Self {
+        Self {
+            _marker: PhantomData,
+            window: self.window,
+            number_of_points: self.number_of_points,
+            wrong: self.wrong,
+            unreduced: self.unreduced,
+        }
+    }
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        let a = [(); 5].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let range_table = RangeTable::configure(meta);
+        let msm_gate = VarMSMGateNarrow::configure_default(
+            meta,
+            &a,
+            range_table,
+            constant,
+            instance,
+            params.window,
+        );
+        // range table is shared with the msm gate
+        let scalar_chip = ScalarChip::configure(
+            meta,
+            a[0],
+            a[1],
+            a[2],
+            a[3],
+            a[4],
+            range_table,
+            params.window,
+        );
+        TestConfig {
+            msm_gate,
+            scalar_chip,
+        }
+    }
+    fn configure(_: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!();
+    }
+    fn synthesize(&self, mut cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
+        let ly = &mut ly;
+        let scalar_chip = &cfg.scalar_chip;
+        let mut memory = MemoryContext::new(&cfg.msm_gate);
+        let res = memory.assign_region(
+            ly,
+            &mut cfg.msm_gate,
+            || "app",
+            |ctx, msm_gate| {
+                let (points, a, b, k) = terms::<App>(self.number_of_points);
+                let points = points
+                    .iter()
+                    .map(|point| Msm::assign_point(msm_gate, ctx, &Value::known(*point)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let scalars = a
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| {
+                        let a = scalar_chip.assign_scalar(ctx, Value::known(*a))?;
+                        let b = scalar_chip.assign_scalar(ctx, Value::known(*b))?;
+                        let kb = scalar_chip.mul_constant(ctx, &b, k)?;
+                        let sum = scalar_chip.add(ctx, &a, &kb)?;
+                        if self.unreduced {
+                            return Ok(sum);
+                        }
+                        scalar_chip.reduce(ctx, &sum)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let mut expected = a[0] + k * b[0];
+                if self.wrong {
+                    expected += App::Scalar::ONE;
+                }
+                let expected = scalar_chip.assign_constant(ctx, expected)?;
+                scalar_chip.assert_equal(ctx, &scalars[0], &expected)?;
+                msm_gate.msm_scalars(ctx, &points, &scalars)
+            },
+        )?;
+        cfg.msm_gate.expose_point(ly, &res, 0)?;
+        memory.layout(ly, &mut cfg.msm_gate)
+    }
+    fn params(&self) -> Self::Params {
+        Params {
+            window: self.window,
+        }
+    }
+}
+
+fn run_scalar_msm(wrong: bool) -> Result<(), Vec<halo2::dev::VerifyFailure>> {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let number_of_points = 3;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window: 4,
+        number_of_points,
+        wrong,
+        unreduced: false,
+    };
+    let (points, a, b, k) = terms::<EqAffine>(number_of_points);
+    let points = points
+        .iter()
+        .map(|point| point.to_curve())
+        .collect::<Vec<_>>();
+    let scalars = a
+        .iter()
+        .zip(b.iter())
+        .map(|(a, b)| *a + k * b)
+        .collect::<Vec<_>>();
+    let res = multiexp_naive_var(&points[..], &scalars[..]).to_affine();
+    let (x, y) = point_coords(res);
+    let prover = match MockProver::run(K, &circuit, vec![vec![x, y]]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:#?}", e),
+    };
+    prover.verify()
+}
+
+#[test]
+fn test_scalar_msm() {
+    assert_eq!(run_scalar_msm(false), Ok(()));
+}
+
+#[test]
+fn test_scalar_not_equal() {
+    assert!(run_scalar_msm(true).is_err());
+}
+
+#[test]
+fn test_scalar_msm_unreduced() {
+    use halo2::halo2curves::pasta::{EqAffine, Fq};
+    const K: u32 = 17;
+    let circuit = MyCircuit::<Fq, EqAffine> {
+        _marker: PhantomData::<(Fq, EqAffine)>,
+        window: 4,
+        number_of_points: 3,
+        wrong: false,
+        unreduced: true,
+    };
+    let e = match MockProver::run(K, &circuit, vec![vec![Fq::ZERO, Fq::ZERO]]) {
+        Ok(_) => panic!("msm over unreduced scalars must fail"),
+        Err(e) => e,
+    };
+    assert!(matches!(e, Error::Synthesis));
+}
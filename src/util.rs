@@ -1,6 +1,7 @@
+use crate::error::MsmError;
 use ff::PrimeField;
 use num_bigint::BigUint;
-use num_traits::Num;
+use num_traits::{Num, ToPrimitive};
 use std::ops::Shl;
 
 pub(crate) fn modulus<F: PrimeField>() -> BigUint {
@@ -14,13 +15,6 @@ pub(crate) fn big_to_fe<F: PrimeField>(e: BigUint) -> F {
 pub(crate) fn fe_to_big<F: PrimeField>(fe: F) -> BigUint {
     BigUint::from_bytes_le(fe.to_repr().as_ref())
 }
-pub(crate) fn decompose<W: PrimeField, N: PrimeField>(
-    e: W,
-    number_of_limbs: usize,
-    bit_len: usize,
-) -> Vec<N> {
-    decompose_big(fe_to_big(e), number_of_limbs, bit_len)
-}
 pub(crate) fn decompose_big<F: PrimeField>(
     e: BigUint,
     number_of_limbs: usize,
@@ -38,6 +32,138 @@ pub(crate) fn decompose_big<F: PrimeField>(
 
     limbs
 }
+pub(crate) fn decompose_u64(e: &BigUint, number_of_limbs: usize, bit_len: usize) -> Vec<u64> {
+    let mask = BigUint::from(1usize).shl(bit_len) - 1usize;
+    (0..number_of_limbs)
+        .map(|i| ((e >> (i * bit_len)) & &mask).to_u64().unwrap())
+        .collect()
+}
+/// Largest integer that both is a canonical scalar and fits in the native
+/// field, that is `min(|S|, |N|) - 1`
+pub(crate) fn scalar_max<N: PrimeField, S: PrimeField>() -> BigUint {
+    std::cmp::min(modulus::<N>(), modulus::<S>()) - 1usize
+}
+pub(crate) fn number_of_windows<N: PrimeField, S: PrimeField>(window: usize) -> usize {
+    let number_of_bits = scalar_max::<N, S>().bits() as usize;
+    div_ceil!(number_of_bits, window)
+}
+/// Bound `2^number_of_bits - 1` of short scalars such as challenges
+pub(crate) fn short_scalar_max<N: PrimeField, S: PrimeField>(
+    number_of_bits: usize,
+) -> Result<BigUint, MsmError> {
+    let max = BigUint::from(1usize).shl(number_of_bits) - 1usize;
+    if number_of_bits == 0 || max > scalar_max::<N, S>() {
+        return Err(MsmError::InvalidBitLength { number_of_bits });
+    }
+    Ok(max)
+}
+/// Number of signed digit windows. One more bit is reserved so that the most
+/// significant digit never carries out
+pub(crate) fn number_of_signed_windows<N: PrimeField, S: PrimeField>(window: usize) -> usize {
+    let number_of_bits = scalar_max::<N, S>().bits() as usize;
+    div_ceil!(number_of_bits + 1, window)
+}
+/// Maps a scalar to the native field keeping its integer value. Scalars
+/// that don't fit, which is only possible when the scalar field is larger,
+/// have to be given as limbs instead
+pub(crate) fn scalar_to_native<N: PrimeField, S: PrimeField>(e: S) -> Result<N, MsmError> {
+    let e = fe_to_big(e);
+    if e > scalar_max::<N, S>() {
+        return Err(MsmError::ScalarOutOfRange);
+    }
+    Ok(big_to_fe(e))
+}
+/// Windowed decomposition of `e` with the borrow chain of `max - e`.
+/// Returns rows of `[diff, window, borrow, acc]` most significant window first
+pub(crate) fn canonical_decomposition<F: PrimeField>(
+    e: BigUint,
+    max: &BigUint,
+    number_of_windows: usize,
+    window: usize,
+) -> Result<Vec<[F; 4]>, MsmError> {
+    if &e > max {
+        return Err(MsmError::ScalarOutOfRange);
+    }
+    let base = 1u64 << window;
+    let windows = decompose_u64(&e, number_of_windows, window);
+    let bound = decompose_u64(max, number_of_windows, window);
+    let mut borrow = 0;
+    let mut rows = windows
+        .iter()
+        .zip(bound.iter())
+        .map(|(&w, &c)| {
+            let (diff, borrow_out) = if c >= w + borrow {
+                (c - w - borrow, 0)
+            } else {
+                (c + base - w - borrow, 1)
+            };
+            borrow = borrow_out;
+            (w, diff, borrow_out)
+        })
+        .collect::<Vec<_>>();
+    // `e <= max` leaves no borrow out of the most significant window
+    debug_assert_eq!(borrow, 0);
+    rows.reverse();
+    let mut acc = F::ZERO;
+    Ok(rows
+        .into_iter()
+        .map(|(w, diff, borrow)| {
+            let w = F::from(w);
+            acc = acc * F::from(base) + w;
+            [F::from(diff), w, F::from(borrow), acc]
+        })
+        .collect())
+}
+/// Recodes unsigned windows into signed digits in `[-2^(w-1), 2^(w-1)]`.
+/// Returns rows of `[sign, carry, magnitude]` most significant first where
+/// `carry` goes into the more significant digit
+pub(crate) fn signed_recoding<F: PrimeField>(windows: &[F], window: usize) -> Vec<[F; 3]> {
+    let half = 1u64 << (window - 1);
+    let base = 1u64 << window;
+    let mut carry = 0;
+    let mut rows = windows
+        .iter()
+        .rev()
+        .map(|window| {
+            let digit = fe_to_big(*window).to_u64().unwrap() + carry;
+            let (sign, magnitude) = if digit > half {
+                carry = 1;
+                (1, base - digit)
+            } else {
+                carry = 0;
+                (0, digit)
+            };
+            [F::from(sign), F::from(carry), F::from(magnitude)]
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(carry, 0, "most significant digit carries out");
+    rows.reverse();
+    rows
+}
+/// Witness of the double-and-add step `out = (acc + q) + acc` as
+/// `[lambda1, lambda2, inverse, x_out, y_out]` where `inverse` is of
+/// `x_acc - x_q`
+pub(crate) fn double_add_step<F: PrimeField>(acc: (F, F), q: (F, F)) -> Result<[F; 5], MsmError> {
+    let (x_acc, y_acc) = acc;
+    let (x_q, y_q) = q;
+    let error = MsmError::ExceptionalAddition {
+        operation: "double add",
+    };
+    let inverse = Option::<F>::from((x_acc - x_q).invert()).ok_or(e!(error))?;
+    let lambda1 = (y_acc - y_q) * inverse;
+    let x_r = lambda1.square() - x_acc - x_q;
+    let lambda2 =
+        y_acc.double() * Option::<F>::from((x_acc - x_r).invert()).ok_or(error)? - lambda1;
+    let x_out = lambda2.square() - x_acc - x_r;
+    let y_out = lambda2 * (x_acc - x_out) - y_acc;
+    Ok([lambda1, lambda2, inverse, x_out, y_out])
+}
+/// Bits of a window most significant first
+pub(crate) fn window_bits<F: PrimeField>(window: F, window_size: usize) -> Vec<F> {
+    let mut bits = decompose_big::<F>(fe_to_big(window), window_size, 1);
+    bits.reverse();
+    bits
+}
 #[cfg(test)]
 use halo2::halo2curves::CurveExt;
 #[cfg(test)]